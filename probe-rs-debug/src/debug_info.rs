@@ -7,27 +7,108 @@ use super::{
     variable::*,
 };
 use crate::{
-    SourceLocation, VerifiedBreakpoint, registers, stack_frame::StackFrameInfo, unit_info::RangeExt,
+    SourceLocation, StatementBlock, VerifiedBreakpoint, registers,
+    source_instructions::{self, SequenceCache},
+    stack_frame::StackFrameInfo,
+    unit_info::RangeExt,
 };
 use gimli::{
     BaseAddresses, DebugFrame, DebugInfoOffset, RunTimeEndian, UnwindContext, UnwindSection,
     UnwindTableRow,
 };
-use object::read::{Object, ObjectSection};
+use object::read::{Object, ObjectSection, ObjectSymbol};
 use probe_rs::{Error, MemoryInterface, RegisterDataType, RegisterRole, RegisterValue, UnwindRule};
 use probe_rs_target::InstructionSet;
 use std::{
-    borrow, cmp::Ordering, num::NonZeroU64, ops::ControlFlow, path::Path, rc::Rc, str::from_utf8,
+    borrow,
+    cell::{OnceCell, RefCell},
+    cmp::Ordering,
+    collections::HashSet,
+    num::NonZeroU64,
+    ops::{ControlFlow, Range},
+    path::Path,
+    rc::Rc,
+    str::from_utf8,
 };
 use typed_path::{TypedPath, TypedPathBuf};
 
-pub(crate) type GimliReader = gimli::EndianReader<RunTimeEndian, std::rc::Rc<[u8]>>;
-pub(crate) type GimliReaderOffset =
-    <gimli::EndianReader<RunTimeEndian, Rc<[u8]>> as gimli::Reader>::Offset;
+/// The shared storage of the loaded DWARF sections, which all readers refer to.
+///
+/// All reader types are defined in terms of this, so this is the only place which has to
+/// change to share the debug information between threads (with `Arc<[u8]>`).
+pub(crate) type SectionData = Rc<[u8]>;
+
+pub(crate) type GimliReader = gimli::EndianReader<RunTimeEndian, SectionData>;
+pub(crate) type GimliReaderOffset = <GimliReader as gimli::Reader>::Offset;
 
 pub(crate) type GimliAttribute = gimli::Attribute<GimliReader>;
 
-pub(crate) type DwarfReader = gimli::read::EndianRcSlice<RunTimeEndian>;
+pub(crate) type DwarfReader = GimliReader;
+
+/// The maximum number of stack frames unwound by [`DebugInfo::unwind`].
+pub const DEFAULT_MAX_UNWIND_FRAMES: usize = 256;
+
+/// The stack frames found by [`DebugInfo::unwind_with_limit`].
+pub struct Backtrace {
+    /// The unwound stack frames, with the most recently called function first.
+    pub frames: Vec<StackFrame>,
+    /// Unwinding was stopped before reaching the bottom of the stack, either because the frame
+    /// limit was reached, or because the same frame was found twice.
+    pub truncated: bool,
+}
+
+/// Detects when unwinding a corrupt stack would not terminate.
+struct UnwindGuard {
+    max_frames: usize,
+    /// The program counter and CFA of every frame seen so far.
+    seen_frames: HashSet<(u64, u64)>,
+}
+
+impl UnwindGuard {
+    fn new(max_frames: usize) -> Self {
+        Self {
+            max_frames,
+            seen_frames: HashSet::new(),
+        }
+    }
+
+    /// Check if the frame at `pc` can be unwound, when `frame_count` frames have been unwound already.
+    fn allow_frame(&mut self, frame_count: usize, pc: u64, cfa: Option<u64>) -> bool {
+        if frame_count >= self.max_frames {
+            tracing::warn!(
+                "UNWIND: Reached the limit of {} stack frames, the stack trace is truncated.",
+                self.max_frames
+            );
+            return false;
+        }
+
+        // Without a CFA, the frame can not be identified, e.g. when unwinding without debug info.
+        if let Some(cfa) = cfa {
+            if !self.seen_frames.insert((pc, cfa)) {
+                tracing::warn!(
+                    "UNWIND: Found the frame at pc={pc:#010x}, cfa={cfa:#010x} twice, the stack is likely corrupt."
+                );
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// The (non-inlined) function containing an address, found by
+/// [`DebugInfo::function_containing_address`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionInfo {
+    /// The name of the function, if it has one.
+    pub name: Option<String>,
+    /// The address range of the function which contains the address.
+    ///
+    /// Functions with disjoint address ranges are split into one entry per range.
+    pub address_range: Range<u64>,
+    /// The offset of the function DIE in the `.debug_info` section.
+    pub die_offset: DebugInfoOffset,
+}
 
 /// Debug information which is parsed from DWARF debugging information.
 pub struct DebugInfo {
@@ -39,6 +120,21 @@ pub struct DebugInfo {
 
     pub(crate) unit_infos: Vec<UnitInfo>,
     pub(crate) endianness: gimli::RunTimeEndian,
+
+    /// The address ranges of all functions, sorted by start address. Built on first use by
+    /// [`DebugInfo::function_containing_address`].
+    function_ranges: OnceCell<Vec<FunctionInfo>>,
+
+    /// The sorted start and end addresses of all function ranges. Built on first use by
+    /// [`DebugInfo::function_range_boundaries`].
+    function_range_boundaries: OnceCell<Vec<u64>>,
+
+    /// Recently used instruction sequences, to avoid running the line program for every
+    /// breakpoint and step at the same location.
+    pub(crate) sequence_cache: RefCell<SequenceCache>,
+
+    /// The address of the `_SEGGER_RTT` symbol, if it is defined.
+    rtt_control_block: Option<u64>,
 }
 
 impl DebugInfo {
@@ -66,22 +162,33 @@ impl DebugInfo {
                 .and_then(|section| section.uncompressed_data().ok())
                 .unwrap_or_else(|| borrow::Cow::Borrowed(&[][..]));
 
-            Ok(gimli::read::EndianRcSlice::new(
-                Rc::from(&*data),
+            Ok(gimli::EndianReader::new(
+                SectionData::from(&*data),
                 endianness,
             ))
         };
 
+        let rtt_control_block = find_symbol(&object, RTT_CONTROL_BLOCK_SYMBOL);
+
+        Self::from_sections(load_section, endianness, rtt_control_block)
+    }
+
+    /// Parse debug information from the DWARF sections returned by `load_section`.
+    fn from_sections(
+        load_section: impl Fn(gimli::SectionId) -> Result<DwarfReader, gimli::Error>,
+        endianness: RunTimeEndian,
+        rtt_control_block: Option<u64>,
+    ) -> Result<Self, DebugError> {
         // Load all of the sections.
         let dwarf_cow = gimli::Dwarf::load(&load_section)?;
 
         use gimli::Section;
-        let mut frame_section = gimli::DebugFrame::load(load_section)?;
-        let address_section = gimli::DebugAddr::load(load_section)?;
-        let debug_loc = gimli::DebugLoc::load(load_section)?;
-        let debug_loc_lists = gimli::DebugLocLists::load(load_section)?;
+        let mut frame_section = gimli::DebugFrame::load(&load_section)?;
+        let address_section = gimli::DebugAddr::load(&load_section)?;
+        let debug_loc = gimli::DebugLoc::load(&load_section)?;
+        let debug_loc_lists = gimli::DebugLocLists::load(&load_section)?;
         let locations_section = gimli::LocationLists::new(debug_loc, debug_loc_lists);
-        let debug_line_section = gimli::DebugLine::load(load_section)?;
+        let debug_line_section = gimli::DebugLine::load(&load_section)?;
 
         let mut unit_infos = Vec::new();
 
@@ -109,6 +216,10 @@ impl DebugInfo {
             debug_line_section,
             unit_infos,
             endianness,
+            function_ranges: OnceCell::new(),
+            function_range_boundaries: OnceCell::new(),
+            sequence_cache: RefCell::new(SequenceCache::default()),
+            rtt_control_block,
         })
     }
 
@@ -529,6 +640,28 @@ impl DebugInfo {
         self.unwind_impl(initial_registers, core, exception_handler, instruction_set)
     }
 
+    /// Performs an unwind like [`DebugInfo::unwind`], but stops after at most `max_unwind_frames`
+    /// stack frames.
+    ///
+    /// Unwinding also stops when the same frame is found twice, which can happen when the stack is
+    /// corrupt, e.g. after a fault. In both cases [`Backtrace::truncated`] is set.
+    pub fn unwind_with_limit(
+        &self,
+        core: &mut impl MemoryInterface,
+        initial_registers: DebugRegisters,
+        exception_handler: &dyn ExceptionInterface,
+        instruction_set: Option<InstructionSet>,
+        max_unwind_frames: usize,
+    ) -> Result<Backtrace, probe_rs::Error> {
+        self.unwind_backtrace(
+            initial_registers,
+            core,
+            exception_handler,
+            instruction_set,
+            max_unwind_frames,
+        )
+    }
+
     pub(crate) fn unwind_impl(
         &self,
         initial_registers: registers::DebugRegisters,
@@ -536,7 +669,28 @@ impl DebugInfo {
         exception_handler: &dyn ExceptionInterface,
         instruction_set: Option<InstructionSet>,
     ) -> Result<Vec<StackFrame>, probe_rs::Error> {
+        let backtrace = self.unwind_backtrace(
+            initial_registers,
+            memory,
+            exception_handler,
+            instruction_set,
+            DEFAULT_MAX_UNWIND_FRAMES,
+        )?;
+
+        Ok(backtrace.frames)
+    }
+
+    fn unwind_backtrace(
+        &self,
+        initial_registers: registers::DebugRegisters,
+        memory: &mut impl MemoryInterface,
+        exception_handler: &dyn ExceptionInterface,
+        instruction_set: Option<InstructionSet>,
+        max_unwind_frames: usize,
+    ) -> Result<Backtrace, probe_rs::Error> {
         let mut stack_frames = Vec::<StackFrame>::new();
+        let mut unwind_guard = UnwindGuard::new(max_unwind_frames);
+        let mut truncated = false;
 
         let mut unwind_context = Box::new(gimli::UnwindContext::new());
 
@@ -572,6 +726,11 @@ impl DebugInfo {
                 .and_then(|unwind_info| determine_cfa(&unwind_registers, unwind_info).ok())
                 .flatten();
 
+            if !unwind_guard.allow_frame(stack_frames.len(), frame_pc, cfa) {
+                truncated = true;
+                break;
+            }
+
             // PART 1-a: Prepare the `StackFrame`s that holds the current frame information.
             let cached_stack_frames =
                 match self.get_stackframe_info(memory, frame_pc, cfa, &unwind_registers) {
@@ -784,7 +943,10 @@ impl DebugInfo {
             }
         }
 
-        Ok(stack_frames)
+        Ok(Backtrace {
+            frames: stack_frames,
+            truncated,
+        })
     }
 
     /// Find the program counter where a breakpoint should be set,
@@ -808,6 +970,206 @@ impl DebugInfo {
         VerifiedBreakpoint::for_source_location(self, path, line, column)
     }
 
+    /// Find all valid breakpoint locations with an address inside `range`, ordered by address.
+    ///
+    /// This can be used to show which source lines a breakpoint can be set on.
+    pub fn verified_breakpoints_in_range(
+        &self,
+        range: Range<u64>,
+    ) -> Result<Vec<VerifiedBreakpoint>, DebugError> {
+        VerifiedBreakpoint::for_address_range(self, range)
+    }
+
+    /// Find the function containing `address`.
+    ///
+    /// Inlined functions are not taken into account, the outermost function is returned.
+    ///
+    /// This is fast enough to be called for every sample of a profiler: the address ranges of all
+    /// functions are collected on the first call, and looked up with a binary search afterwards,
+    /// instead of walking the DIEs of every unit for each lookup.
+    pub fn function_containing_address(&self, address: u64) -> Option<FunctionInfo> {
+        let function_ranges = self
+            .function_ranges
+            .get_or_init(|| self.collect_function_ranges());
+
+        // The ranges don't overlap, so only the last range starting at or before the address
+        // can contain it.
+        let index =
+            function_ranges.partition_point(|function| function.address_range.start <= address);
+
+        index
+            .checked_sub(1)
+            .map(|index| &function_ranges[index])
+            .filter(|function| function.address_range.contains(&address))
+            .cloned()
+    }
+
+    /// Collect the address ranges of all functions in all units, sorted by start address.
+    ///
+    /// Ranges which overlap an earlier range, such as those of functions removed by the linker
+    /// which all start at address zero, are dropped, so the returned ranges don't overlap.
+    fn collect_function_ranges(&self) -> Vec<FunctionInfo> {
+        let mut function_ranges = Vec::new();
+
+        for unit_info in &self.unit_infos {
+            let mut entries_cursor = unit_info.unit.entries();
+            while let Ok(Some((_, entry))) = entries_cursor.next_dfs() {
+                if entry.tag() != gimli::DW_TAG_subprogram {
+                    continue;
+                }
+
+                let Ok(mut ranges) = self.dwarf.die_ranges(&unit_info.unit, entry) else {
+                    continue;
+                };
+                let Ok(Some(first_range)) = ranges.next() else {
+                    continue;
+                };
+
+                // Validates the ranges, and resolves the specification DIE for the name.
+                let Ok(Some(function_die)) =
+                    FunctionDie::new(entry.clone(), unit_info, self, first_range.begin)
+                else {
+                    continue;
+                };
+
+                let name = function_die.function_name(self);
+                let Some(die_offset) = entry.offset().to_debug_info_offset(&unit_info.unit.header)
+                else {
+                    continue;
+                };
+
+                function_ranges.extend(function_die.ranges.iter().map(|range| FunctionInfo {
+                    name: name.clone(),
+                    address_range: range.clone(),
+                    die_offset,
+                }));
+            }
+        }
+
+        function_ranges.sort_by_key(|function| function.address_range.start);
+
+        let mut covered_until = 0;
+        function_ranges.retain(|function| {
+            if function.address_range.start < covered_until {
+                tracing::debug!(
+                    "Ignoring range {:#010x?} of function {:?}, it overlaps another function",
+                    function.address_range,
+                    function.name
+                );
+                return false;
+            }
+            covered_until = function.address_range.end;
+            true
+        });
+
+        function_ranges
+    }
+
+    /// Return the start and end addresses of all function ranges which lie strictly inside
+    /// `address_range`, sorted and without duplicates.
+    ///
+    /// A function with a `DW_AT_ranges` attribute contributes the edges of each of its ranges,
+    /// so this also finds the places where a function continues at a discontiguous address.
+    pub(crate) fn function_range_boundaries(&self, address_range: &Range<u64>) -> Vec<u64> {
+        let boundaries = self.function_range_boundaries.get_or_init(|| {
+            let function_ranges = self
+                .function_ranges
+                .get_or_init(|| self.collect_function_ranges());
+
+            let mut boundaries: Vec<u64> = function_ranges
+                .iter()
+                .flat_map(|function| [function.address_range.start, function.address_range.end])
+                .collect();
+            boundaries.sort_unstable();
+            boundaries.dedup();
+
+            boundaries
+        });
+
+        let start = boundaries.partition_point(|&boundary| boundary <= address_range.start);
+        let end = boundaries.partition_point(|&boundary| boundary < address_range.end);
+
+        boundaries[start..end.max(start)].to_vec()
+    }
+
+    /// Get the [`StatementBlock`]s of the line program sequence that contains `address`.
+    ///
+    /// This allows debuggers to implement their own stepping, using the same halt locations
+    /// as [`SteppingMode`](crate::SteppingMode).
+    pub fn blocks_for_address(&self, address: u64) -> Result<Vec<StatementBlock>, DebugError> {
+        StatementBlock::for_address(self, address)
+    }
+
+    /// Same as [`DebugInfo::blocks_for_address`], but the opcodes in `memory` are decoded to
+    /// also end a block after each branch, call or return.
+    ///
+    /// The instruction following a branch starts a new block, but is not a halt location. Only
+    /// Thumb and ARM (A32) instructions are decoded, and if `memory` can not be read, the blocks
+    /// are the same as for [`DebugInfo::blocks_for_address`].
+    pub fn blocks_for_address_with_memory(
+        &self,
+        address: u64,
+        memory: &mut dyn MemoryInterface,
+        instruction_set: InstructionSet,
+    ) -> Result<Vec<StatementBlock>, DebugError> {
+        StatementBlock::for_address_with_memory(self, address, memory, instruction_set)
+    }
+
+    /// Return the addresses of all the rows of the line sequence that contains `address`.
+    ///
+    /// Unlike [`DebugInfo::blocks_for_address`], this does not filter the rows by their role,
+    /// so it includes the prologue and all rows which are not statements. This allows debuggers
+    /// to step at instruction granularity, e.g. for assembly level debugging.
+    pub fn instruction_addresses(&self, address: u64) -> Result<Vec<u64>, DebugError> {
+        source_instructions::instruction_addresses(self, address)
+    }
+
+    /// Find the address to halt at when stepping out of the inlined function containing `pc`.
+    ///
+    /// This follows the [`StatementBlock::steps_to`] edges to the first statement after the
+    /// innermost inlined function. Returns `Ok(None)` if `pc` is not in an inlined function, in
+    /// which case the caller has to unwind the stack to find the return address.
+    pub fn step_out_address(&self, pc: u64) -> Result<Option<u64>, DebugError> {
+        StatementBlock::step_out_address(self, pc)
+    }
+
+    /// Find the address to halt at when stepping past the end of a sequence.
+    ///
+    /// `after` is the end of the current sequence, i.e. the end of its address range. The first
+    /// halt location of the next sequence is returned, which can be in a different compilation
+    /// unit. Returns `None` at the end of the program.
+    pub fn next_sequence_start(&self, after: u64) -> Option<u64> {
+        source_instructions::next_sequence_start(self, after)
+    }
+
+    /// Read the text of the source line at `address`.
+    ///
+    /// The source location is resolved with [`VerifiedBreakpoint::for_address`], and the source
+    /// file is read with `reader`, so that the caller decides how source files are accessed.
+    /// Returns `Ok(None)` if the source location has no line, or the file does not contain it.
+    pub fn source_line_text(
+        &self,
+        address: u64,
+        reader: &mut dyn FnMut(&TypedPathBuf) -> std::io::Result<String>,
+    ) -> Result<Option<String>, DebugError> {
+        let source_location = VerifiedBreakpoint::for_address(self, address)?.source_location;
+
+        let Some(line) = source_location.line else {
+            return Ok(None);
+        };
+
+        let source = reader(&source_location.path)?;
+
+        // Line numbers in the debug information start at 1.
+        let line_text = line
+            .checked_sub(1)
+            .and_then(|index| usize::try_from(index).ok())
+            .and_then(|index| source.lines().nth(index))
+            .map(str::to_string);
+
+        Ok(line_text)
+    }
+
     /// Get the path for an entry in a line program header, using the compilation unit's directory and file entries.
     // TODO: Determine if it is necessary to navigate the include directories to find the file absolute path for C files.
     pub(crate) fn get_path(
@@ -962,6 +1324,37 @@ impl DebugInfo {
     pub fn endianness(&self) -> RunTimeEndian {
         self.endianness
     }
+
+    /// The address of the RTT control block, from the `_SEGGER_RTT` symbol.
+    ///
+    /// This allows attaching to RTT without scanning the target memory for the control block.
+    pub fn rtt_control_block_address(&self) -> Result<u64, DebugError> {
+        self.rtt_control_block.ok_or_else(|| {
+            DebugError::Other(
+                "The `_SEGGER_RTT` symbol was not found in the program binary. Please specify the address of the RTT control block manually."
+                    .to_string(),
+            )
+        })
+    }
+}
+
+/// The name of the symbol of the SEGGER RTT control block.
+pub const RTT_CONTROL_BLOCK_SYMBOL: &str = "_SEGGER_RTT";
+
+/// Find the address of the defined symbol `name` in the symbol table of the ELF file `data`.
+///
+/// The symbol table is searched on demand, without building an index of all symbols.
+pub fn symbol_address_in_elf(data: &[u8], name: &str) -> Result<Option<u64>, DebugError> {
+    let object = object::File::parse(data)?;
+
+    Ok(find_symbol(&object, name))
+}
+
+fn find_symbol(object: &object::File<'_>, name: &str) -> Option<u64> {
+    object
+        .symbols()
+        .find(|symbol| !symbol.is_undefined() && symbol.name().is_ok_and(|symbol| symbol == name))
+        .map(|symbol| symbol.address())
 }
 
 /// Uses the [`TypedPathBuf::normalize`] function to normalize both paths before comparing them
@@ -1400,7 +1793,7 @@ fn add_to_address(address: u64, offset: i64, address_size_in_bytes: usize) -> u6
 #[cfg(test)]
 mod test {
     use crate::{
-        DebugInfo, DebugRegister, DebugRegisters,
+        DebugError, DebugInfo, DebugRegister, DebugRegisters, VerifiedBreakpoint,
         exception_handling::{
             armv6m::ArmV6MExceptionHandler, armv7m::ArmV7MExceptionHandler,
             exception_handler_for_core,
@@ -1415,10 +1808,13 @@ mod test {
         architecture::arm::core::registers::cortex_m::{self, CORTEX_M_CORE_REGISTERS},
         test::MockMemory,
     };
-    use std::path::{Path, PathBuf};
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
     use test_case::test_case;
 
-    use super::unwind_register_using_rule;
+    use super::{DwarfReader, SectionData, UnwindGuard, unwind_register_using_rule};
 
     /// Get the full path to a file in the `tests` directory.
     fn get_path_for_test_files(relative_file: &str) -> PathBuf {
@@ -1819,6 +2215,336 @@ mod test {
         insta::assert_snapshot!(printed_backtrace);
     }
 
+    #[test]
+    fn breakpoint_inline_frames() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+
+        // `wait` is inlined into `__cortex_m_rt_main` through several `delay` functions.
+        let breakpoint = VerifiedBreakpoint::for_address(&debug_info, 0x2e4).unwrap();
+        assert_eq!(breakpoint.address, 0x2e4);
+        assert_eq!(breakpoint.source_location.line, Some(266));
+
+        let call_site_lines: Vec<_> = breakpoint
+            .inline_frames
+            .iter()
+            .map(|call_site| call_site.line)
+            .collect();
+        assert_eq!(
+            call_site_lines,
+            [Some(145), Some(324), Some(297), Some(306), Some(20)]
+        );
+        assert!(
+            breakpoint.inline_frames[4]
+                .path
+                .to_string_lossy()
+                .ends_with("inlined-functions/src/main.rs")
+        );
+
+        // The trampoline is not inlined.
+        let breakpoint = VerifiedBreakpoint::for_address(&debug_info, 0x15c).unwrap();
+        assert!(breakpoint.inline_frames.is_empty());
+    }
+
+    #[test]
+    fn source_line_text_from_reader() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+        let source: String = (1..=300).map(|line| format!("line {line}\n")).collect();
+
+        let mut read_paths = Vec::new();
+        let line_text = debug_info
+            .source_line_text(0x2e4, &mut |path| {
+                read_paths.push(path.clone());
+                Ok(source.clone())
+            })
+            .unwrap();
+
+        // The halt location at 0x2e4 is on line 266.
+        assert_eq!(line_text.as_deref(), Some("line 266"));
+        assert_eq!(
+            read_paths,
+            [VerifiedBreakpoint::for_address(&debug_info, 0x2e4)
+                .unwrap()
+                .source_location
+                .path]
+        );
+
+        // A file which is shorter than the line has no text for it.
+        let line_text = debug_info
+            .source_line_text(0x2e4, &mut |_| Ok("line 1\n".to_string()))
+            .unwrap();
+        assert_eq!(line_text, None);
+    }
+
+    #[test]
+    fn next_sequence_start_across_adjacent_sequences() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+
+        // The sequence at 0x316..0x318 is directly followed by the sequence at 0x318..0x31a,
+        // which starts with a halt location.
+        assert_eq!(debug_info.next_sequence_start(0x318), Some(0x318));
+
+        // The sequence at 0x342..0x344 is the last one of the program.
+        assert_eq!(debug_info.next_sequence_start(0x344), None);
+    }
+
+    #[test]
+    fn function_containing_address_matches_die_walk() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+        assert!(debug_info.function_ranges.get().is_none());
+
+        // Compare against walking the DIEs for every address in `.text`.
+        for address in (0x100..0x344).step_by(2) {
+            let cached = debug_info.function_containing_address(address);
+            let walked = debug_info
+                .get_function_dies(address)
+                .ok()
+                .map(|(_, function_dies)| function_dies[0].function_name(&debug_info));
+
+            assert_eq!(
+                cached.as_ref().map(|function| function.name.clone()),
+                walked,
+                "Function mismatch at {address:#x}"
+            );
+            if let Some(function) = cached {
+                assert!(function.address_range.contains(&address));
+            }
+        }
+
+        // The ranges are only collected once.
+        assert!(debug_info.function_ranges.get().is_some());
+
+        let function = debug_info.function_containing_address(0x2e4).unwrap();
+        assert_eq!(function.address_range, 0x162..0x302);
+        assert_eq!(function.name.as_deref(), Some("__cortex_m_rt_main"));
+
+        assert!(debug_info.function_containing_address(0x10).is_none());
+    }
+
+    /// Parse the sections written for `dwarf`, without an ELF file around them.
+    fn debug_info_from_dwarf(dwarf: &mut gimli::write::Dwarf) -> DebugInfo {
+        let mut sections =
+            gimli::write::Sections::new(gimli::write::EndianVec::new(gimli::RunTimeEndian::Little));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut section_data = HashMap::new();
+        sections
+            .for_each(|id, data| {
+                section_data.insert(id, data.slice().to_vec());
+                Ok::<_, gimli::write::Error>(())
+            })
+            .unwrap();
+
+        let load_section = |id: gimli::SectionId| -> Result<DwarfReader, gimli::Error> {
+            let data = section_data.get(&id).map(Vec::as_slice).unwrap_or_default();
+            Ok(gimli::EndianReader::new(
+                SectionData::from(data),
+                gimli::RunTimeEndian::Little,
+            ))
+        };
+
+        DebugInfo::from_sections(load_section, gimli::RunTimeEndian::Little, None).unwrap()
+    }
+
+    #[test]
+    fn function_range_boundaries_of_discontiguous_function() {
+        use gimli::write::{Address, AttributeValue, Dwarf, LineProgram, Range, RangeList, Unit};
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+
+        let root = unit.root();
+        unit.get_mut(root).set(
+            gimli::DW_AT_language,
+            AttributeValue::Language(gimli::DW_LANG_Rust),
+        );
+
+        // A function which is split into two disjoint ranges, with a gap between them.
+        let ranges = unit.ranges.add(RangeList(vec![
+            Range::StartLength {
+                begin: Address::Constant(0x1000),
+                length: 0x8,
+            },
+            Range::StartLength {
+                begin: Address::Constant(0x1010),
+                length: 0x8,
+            },
+        ]));
+        let function = unit.add(root, gimli::DW_TAG_subprogram);
+        let function = unit.get_mut(function);
+        function.set(gimli::DW_AT_name, AttributeValue::String(b"split".to_vec()));
+        function.set(gimli::DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+
+        let debug_info = debug_info_from_dwarf(&mut dwarf);
+
+        assert_eq!(
+            debug_info.function_range_boundaries(&(0x1000..0x1018)),
+            [0x1008, 0x1010]
+        );
+        assert_eq!(
+            debug_info.function_range_boundaries(&(0x0..0x2000)),
+            [0x1000, 0x1008, 0x1010, 0x1018]
+        );
+        assert!(
+            debug_info
+                .function_range_boundaries(&(0x1008..0x1010))
+                .is_empty()
+        );
+
+        let first = debug_info.function_containing_address(0x1004).unwrap();
+        assert_eq!(first.name.as_deref(), Some("split"));
+        assert_eq!(first.address_range, 0x1000..0x1008);
+
+        let second = debug_info.function_containing_address(0x1014).unwrap();
+        assert_eq!(second.name.as_deref(), Some("split"));
+        assert_eq!(second.address_range, 0x1010..0x1018);
+        assert_eq!(second.die_offset, first.die_offset);
+
+        assert!(debug_info.function_containing_address(0x100c).is_none());
+    }
+
+    #[test]
+    fn function_containing_address_ignores_overlapping_ranges() {
+        use gimli::write::{Address, AttributeValue, Dwarf, LineProgram, Unit};
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+
+        // `outer` covers `inner` completely, and continues after it.
+        for (name, low_pc, length) in [("outer", 0x1000, 0x20), ("inner", 0x1008, 0x8)] {
+            let function = unit.add(root, gimli::DW_TAG_subprogram);
+            let function = unit.get_mut(function);
+            function.set(gimli::DW_AT_name, AttributeValue::String(name.into()));
+            function.set(
+                gimli::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(low_pc)),
+            );
+            function.set(gimli::DW_AT_high_pc, AttributeValue::Udata(length));
+        }
+
+        let debug_info = debug_info_from_dwarf(&mut dwarf);
+
+        for address in [0x1000, 0x100c, 0x101c] {
+            let function = debug_info.function_containing_address(address).unwrap();
+            assert_eq!(function.name.as_deref(), Some("outer"));
+            assert_eq!(function.address_range, 0x1000..0x1020);
+        }
+        assert!(debug_info.function_containing_address(0x1020).is_none());
+    }
+
+    /// Compare the cached lookup with walking the DIEs, on a unit with many functions.
+    #[test]
+    fn function_containing_address_with_many_functions() {
+        use gimli::write::{Address, AttributeValue, Dwarf, LineProgram, Unit};
+
+        const FUNCTION_COUNT: u64 = 2000;
+        const FUNCTION_SIZE: u64 = 0x10;
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = Dwarf::new();
+        let unit_id = dwarf.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = dwarf.units.get_mut(unit_id);
+        let root = unit.root();
+
+        for index in 0..FUNCTION_COUNT {
+            let function = unit.add(root, gimli::DW_TAG_subprogram);
+            let function = unit.get_mut(function);
+            function.set(
+                gimli::DW_AT_name,
+                AttributeValue::String(format!("function_{index}").into_bytes()),
+            );
+            function.set(
+                gimli::DW_AT_low_pc,
+                AttributeValue::Address(Address::Constant(0x1000 + index * FUNCTION_SIZE)),
+            );
+            function.set(gimli::DW_AT_high_pc, AttributeValue::Udata(FUNCTION_SIZE));
+        }
+
+        let debug_info = debug_info_from_dwarf(&mut dwarf);
+
+        // Sample addresses spread over all functions.
+        let addresses: Vec<u64> = (0..FUNCTION_COUNT)
+            .step_by(10)
+            .map(|index| 0x1000 + index * FUNCTION_SIZE + 4)
+            .collect();
+
+        let walked: Vec<_> = addresses
+            .iter()
+            .map(|&address| {
+                let (_, function_dies) = debug_info.get_function_dies(address).unwrap();
+                function_dies[0].function_name(&debug_info)
+            })
+            .collect();
+
+        assert!(debug_info.function_ranges.get().is_none());
+        let cached: Vec<_> = addresses
+            .iter()
+            .map(|&address| {
+                debug_info
+                    .function_containing_address(address)
+                    .unwrap()
+                    .name
+            })
+            .collect();
+
+        assert_eq!(cached, walked);
+        // The ranges of all functions were collected once, on the first lookup.
+        let function_ranges = debug_info.function_ranges.get().unwrap();
+        assert_eq!(function_ranges.len(), FUNCTION_COUNT as usize);
+        debug_info.function_containing_address(0x1000);
+        assert!(std::ptr::eq(
+            function_ranges,
+            debug_info.function_ranges.get().unwrap()
+        ));
+    }
+
+    #[test]
+    fn step_out_of_inlined_function() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+
+        // `wait` is inlined at 0x2e2..0x2fc, the first statement after it is at 0x2fc.
+        assert_eq!(debug_info.step_out_address(0x2e4).unwrap(), Some(0x2fc));
+        assert_eq!(debug_info.step_out_address(0x2f6).unwrap(), Some(0x2fc));
+
+        // The trampoline is not inlined, so the return address has to be unwound.
+        assert_eq!(debug_info.step_out_address(0x15c).unwrap(), None);
+    }
+
+    #[test]
+    fn verified_breakpoints_across_sequences() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+
+        // Covers the sequence of the trampoline, and the start of the sequence of the main function.
+        let breakpoints = debug_info
+            .verified_breakpoints_in_range(0x158..0x170)
+            .unwrap();
+
+        let locations: Vec<_> = breakpoints
+            .iter()
+            .map(|breakpoint| (breakpoint.address, breakpoint.source_location.line))
+            .collect();
+        assert_eq!(
+            locations,
+            [(0x15c, Some(7)), (0x166, Some(29)), (0x16c, Some(38))]
+        );
+    }
+
     #[test]
     fn unwinding_inlined() {
         let debug_info = load_test_elf_as_debug_info("inlined-functions");
@@ -2140,4 +2866,59 @@ mod test {
         // we assume that it is the same as the canonical frame address.
         assert_eq!(value, Some(RegisterValue::U32(0x200)));
     }
+
+    #[test]
+    fn unwind_guard_stops_at_self_referential_frame() {
+        let mut guard = UnwindGuard::new(256);
+
+        assert!(guard.allow_frame(0, 0x1000, Some(0x2000_1000)));
+        assert!(guard.allow_frame(1, 0x1100, Some(0x2000_1010)));
+        // A corrupt stack, where the frame points back to itself.
+        assert!(!guard.allow_frame(2, 0x1100, Some(0x2000_1010)));
+    }
+
+    #[test]
+    fn unwind_guard_ignores_frames_without_cfa() {
+        let mut guard = UnwindGuard::new(256);
+
+        assert!(guard.allow_frame(0, 0x1000, None));
+        assert!(guard.allow_frame(1, 0x1000, None));
+    }
+
+    #[test]
+    fn unwind_guard_stops_at_frame_limit() {
+        let mut guard = UnwindGuard::new(2);
+
+        assert!(guard.allow_frame(0, 0x1000, Some(0x2000_1000)));
+        assert!(guard.allow_frame(1, 0x1100, Some(0x2000_1010)));
+        assert!(!guard.allow_frame(2, 0x1200, Some(0x2000_1020)));
+    }
+
+    #[test]
+    fn rtt_control_block_from_symbol_table() {
+        let debug_info = load_test_elf_as_debug_info("probe-rs-debugger-test");
+
+        assert_eq!(debug_info.rtt_control_block_address().unwrap(), 0x2000_004c);
+    }
+
+    #[test]
+    fn symbol_address_from_elf_data() {
+        let data = std::fs::read(get_path_for_test_files("probe-rs-debugger-test")).unwrap();
+
+        assert_eq!(
+            symbol_address_in_elf(&data, RTT_CONTROL_BLOCK_SYMBOL).unwrap(),
+            Some(0x2000_004c)
+        );
+        assert_eq!(symbol_address_in_elf(&data, "not_a_symbol").unwrap(), None);
+    }
+
+    #[test]
+    fn rtt_control_block_without_symbol() {
+        let debug_info = load_test_elf_as_debug_info("inlined-functions");
+
+        assert!(matches!(
+            debug_info.rtt_control_block_address(),
+            Err(DebugError::Other(message)) if message.contains("manually")
+        ));
+    }
 }
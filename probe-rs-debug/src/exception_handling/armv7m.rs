@@ -161,6 +161,131 @@ memory_mapped_bitfield_register! {
     0xE000ED38, "BFAR",
     impl From;
 }
+memory_mapped_bitfield_register! {
+    /// SHCSR - System Handler Control and State Register
+    pub struct Shcsr(u32);
+    0xE000ED24, "SHCSR",
+    impl From;
+    svcall_pended, _: 15;
+    busfault_pended, _: 14;
+    memfault_pended, _: 13;
+    usgfault_pended, _: 12;
+    systick_act, _: 11;
+    pendsv_act, _: 10;
+    monitor_act, _: 8;
+    svcall_act, _: 7;
+    usgfault_act, _: 3;
+    busfault_act, _: 1;
+    memfault_act, _: 0;
+}
+
+memory_mapped_bitfield_register! {
+    /// ICSR - Interrupt Control and State Register
+    pub struct Icsr(u32);
+    0xE000ED04, "ICSR",
+    impl From;
+    nmipendset, _: 31;
+    pendsvset, _: 28;
+    pendstset, _: 26;
+}
+
+memory_mapped_bitfield_register! {
+    /// ICTR - Interrupt Controller Type Register
+    pub struct Ictr(u32);
+    0xE000E004, "ICTR",
+    impl From;
+    /// The number of implemented NVIC registers for each register type, minus one.
+    intlinesnum, _: 3, 0;
+}
+
+/// NVIC_ISPR0 - The first of the Interrupt Set-Pending Registers
+const NVIC_ISPR: u64 = 0xE000_E200;
+/// NVIC_IABR0 - The first of the Interrupt Active Bit Registers
+const NVIC_IABR: u64 = 0xE000_E300;
+
+/// The state of an exception which is active or pending, as reported by [`read_exception_states`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionState {
+    /// The exception number. External interrupts start at exception number 16.
+    pub number: u32,
+    /// The name of the exception.
+    pub name: String,
+    /// The exception handler is running, or was preempted by a higher priority exception.
+    pub active: bool,
+    /// The exception is waiting to be serviced.
+    pub pending: bool,
+}
+
+/// Read the system handler and NVIC state, and list all exceptions which are currently active or pending.
+///
+/// The active state of NMI and HardFault is not visible in these registers, use the
+/// IPSR of the current frame to detect these.
+pub fn read_exception_states(
+    memory: &mut dyn MemoryInterface,
+) -> Result<Vec<ExceptionState>, Error> {
+    let shcsr = Shcsr(memory.read_word_32(Shcsr::get_mmio_address())?);
+    let icsr = Icsr(memory.read_word_32(Icsr::get_mmio_address())?);
+    let ictr = Ictr(memory.read_word_32(Ictr::get_mmio_address())?);
+
+    let nvic_words = ictr.intlinesnum() as usize + 1;
+    let mut nvic_active = vec![0; nvic_words];
+    memory.read_32(NVIC_IABR, &mut nvic_active)?;
+    let mut nvic_pending = vec![0; nvic_words];
+    memory.read_32(NVIC_ISPR, &mut nvic_pending)?;
+
+    Ok(decode_exception_states(
+        shcsr,
+        icsr,
+        &nvic_active,
+        &nvic_pending,
+    ))
+}
+
+fn decode_exception_states(
+    shcsr: Shcsr,
+    icsr: Icsr,
+    nvic_active: &[u32],
+    nvic_pending: &[u32],
+) -> Vec<ExceptionState> {
+    let system_handlers = [
+        (2, false, icsr.nmipendset()),
+        (4, shcsr.memfault_act(), shcsr.memfault_pended()),
+        (5, shcsr.busfault_act(), shcsr.busfault_pended()),
+        (6, shcsr.usgfault_act(), shcsr.usgfault_pended()),
+        (11, shcsr.svcall_act(), shcsr.svcall_pended()),
+        (12, shcsr.monitor_act(), false),
+        (14, shcsr.pendsv_act(), icsr.pendsvset()),
+        (15, shcsr.systick_act(), icsr.pendstset()),
+    ];
+
+    let external_interrupts =
+        nvic_active
+            .iter()
+            .zip(nvic_pending)
+            .enumerate()
+            .flat_map(|(word, (active, pending))| {
+                (0..32).map(move |bit| {
+                    (
+                        16 + (word as u32 * 32) + bit,
+                        active & (1 << bit) != 0,
+                        pending & (1 << bit) != 0,
+                    )
+                })
+            });
+
+    system_handlers
+        .into_iter()
+        .chain(external_interrupts)
+        .filter(|(_, active, pending)| *active || *pending)
+        .map(|(number, active, pending)| ExceptionState {
+            number,
+            name: ExceptionReason::from(number).name(),
+            active,
+            pending,
+        })
+        .collect()
+}
+
 /// Decode the exception number.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) enum ExceptionReason {
@@ -213,6 +338,25 @@ impl From<u32> for ExceptionReason {
 }
 
 impl ExceptionReason {
+    /// The name of the exception, without reading any additional information from the core.
+    pub(crate) fn name(&self) -> String {
+        match self {
+            ExceptionReason::ThreadMode => "Thread mode".to_string(),
+            ExceptionReason::Reset => "Reset".to_string(),
+            ExceptionReason::NonMaskableInterrupt => "NMI".to_string(),
+            ExceptionReason::HardFault => "HardFault".to_string(),
+            ExceptionReason::MemoryManagementFault => "MemManage".to_string(),
+            ExceptionReason::BusFault => "BusFault".to_string(),
+            ExceptionReason::UsageFault => "UsageFault".to_string(),
+            ExceptionReason::SVCall => "SVC".to_string(),
+            ExceptionReason::DebugMonitor => "DebugMonitor".to_string(),
+            ExceptionReason::PendSV => "PendSV".to_string(),
+            ExceptionReason::SysTick => "SysTick".to_string(),
+            ExceptionReason::ExternalInterrupt(exti) => format!("External interrupt #{exti}"),
+            ExceptionReason::Reserved => "Reserved".to_string(),
+        }
+    }
+
     /// Expands the exception reason, by providing additional information about the exception from the
     /// HFSR and CFSR registers.
     pub(crate) fn expanded_description(
@@ -378,3 +522,39 @@ impl ExceptionInterface for ArmV7MExceptionHandler {
         Ok(description)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{ExceptionState, Icsr, Shcsr, decode_exception_states};
+
+    #[test]
+    fn decode_active_and_pending_exceptions() {
+        // SysTick and SVCall active, BusFault pended.
+        let shcsr = Shcsr((1 << 11) | (1 << 14) | (1 << 7));
+        // PendSV pending.
+        let icsr = Icsr(1 << 28);
+        // IRQ 3 active and pending, IRQ 33 pending.
+        let nvic_active = [1 << 3, 0];
+        let nvic_pending = [1 << 3, 1 << 1];
+
+        let states = decode_exception_states(shcsr, icsr, &nvic_active, &nvic_pending);
+
+        let state = |number: u32, name: &str, active: bool, pending: bool| ExceptionState {
+            number,
+            name: name.to_string(),
+            active,
+            pending,
+        };
+        assert_eq!(
+            states,
+            vec![
+                state(5, "BusFault", false, true),
+                state(11, "SVC", true, false),
+                state(14, "PendSV", false, true),
+                state(15, "SysTick", true, false),
+                state(19, "External interrupt #3", true, true),
+                state(49, "External interrupt #33", false, true),
+            ]
+        );
+    }
+}
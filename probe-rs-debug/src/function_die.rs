@@ -1,4 +1,3 @@
-use gimli::RunTimeEndian;
 use std::ops::Range;
 
 use crate::{MemoryInterface, stack_frame::StackFrameInfo};
@@ -244,7 +243,7 @@ fn collapsed_attribute(
     function_die: &Die,
     specification_die: &Option<Die>,
     attribute_name: gimli::DwAt,
-) -> Option<gimli::Attribute<gimli::EndianReader<RunTimeEndian, std::rc::Rc<[u8]>>>> {
+) -> Option<debug_info::GimliAttribute> {
     let attribute = specification_die
         .as_ref()
         .and_then(|specification_die| {
@@ -30,17 +30,25 @@ pub(crate) mod exception_handling;
 mod test;
 
 pub use self::{
-    debug_info::*, debug_step::SteppingMode, exception_handling::exception_handler_for_core,
-    registers::*, source_instructions::SourceLocation, source_instructions::VerifiedBreakpoint,
-    stack_frame::StackFrame, variable::*, variable_cache::VariableCache,
+    debug_info::*,
+    debug_step::SteppingMode,
+    exception_handling::armv7m::{ExceptionState, read_exception_states},
+    exception_handling::exception_handler_for_core,
+    registers::*,
+    source_instructions::SourceLocation,
+    source_instructions::StatementBlock,
+    source_instructions::VerifiedBreakpoint,
+    stack_frame::StackFrame,
+    variable::*,
+    variable_cache::VariableCache,
 };
 
 use probe_rs::{Core, MemoryInterface};
 
+use gimli::AttributeValue;
 use gimli::DebuggingInformationEntry;
 use gimli::EvaluationResult;
-use gimli::{AttributeValue, RunTimeEndian};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use typed_path::TypedPathBuf;
 
 use std::num::ParseIntError;
@@ -53,7 +61,7 @@ use std::{
 };
 
 /// A simplified type alias of the [`gimli::EndianReader`] type.
-pub type EndianReader = gimli::EndianReader<RunTimeEndian, std::rc::Rc<[u8]>>;
+pub type EndianReader = debug_info::GimliReader;
 
 /// An error occurred while debugging the target.
 #[derive(Debug, thiserror::Error)]
@@ -98,7 +106,10 @@ pub enum DebugError {
 }
 
 /// A copy of [`gimli::ColumnType`] which uses [`u64`] instead of [`NonZeroU64`](std::num::NonZeroU64).
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+///
+/// Columns are compared by their position on the line, with [`ColumnType::LeftEdge`] first.
+/// `Column(0)` is the same position as `LeftEdge`, and compares equal to it.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ColumnType {
     /// The `LeftEdge` means that the statement begins at the start of the new line.
     LeftEdge,
@@ -106,6 +117,42 @@ pub enum ColumnType {
     Column(u64),
 }
 
+impl ColumnType {
+    /// The position on the line, where `LeftEdge` is 0.
+    fn position(&self) -> u64 {
+        match self {
+            ColumnType::LeftEdge => 0,
+            ColumnType::Column(column) => *column,
+        }
+    }
+}
+
+impl PartialEq for ColumnType {
+    fn eq(&self, other: &Self) -> bool {
+        self.position() == other.position()
+    }
+}
+
+impl Eq for ColumnType {}
+
+impl std::hash::Hash for ColumnType {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.position().hash(state);
+    }
+}
+
+impl PartialOrd for ColumnType {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ColumnType {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.position().cmp(&other.position())
+    }
+}
+
 impl From<gimli::ColumnType> for ColumnType {
     fn from(column: gimli::ColumnType) -> Self {
         match column {
@@ -3,11 +3,13 @@ use super::{
     unit_info::{self, UnitInfo},
 };
 use gimli::LineSequence;
-use serde::Serialize;
+use probe_rs::{InstructionSet, MemoryInterface};
+use serde::{Deserialize, Serialize};
 use std::{
     fmt::{Debug, Formatter},
     num::NonZeroU64,
-    ops::Range,
+    ops::{Range, RangeInclusive},
+    rc::Rc,
 };
 use typed_path::{TypedPath, TypedPathBuf};
 
@@ -22,6 +24,10 @@ pub struct VerifiedBreakpoint {
     pub address: u64,
     /// If the breakpoint request was for a specific source location, then this field will contain the resolved source location.
     pub source_location: SourceLocation,
+    /// The call sites of the inlined functions containing the breakpoint, starting with the innermost one.
+    ///
+    /// This is empty if the breakpoint is not inside an inlined function.
+    pub inline_frames: Vec<SourceLocation>,
 }
 
 impl VerifiedBreakpoint {
@@ -50,6 +56,114 @@ impl VerifiedBreakpoint {
         Err(DebugError::WarnAndContinue { message })
     }
 
+    /// Return the last valid breakpoint location of the statement that is less than OR equal to `address`.
+    /// e.g., if the `address` is the program counter inside a trap handler, then the return value will be
+    /// the halt address at, or immediately before, the program counter in the current sequence.
+    pub fn for_address_before(
+        debug_info: &DebugInfo,
+        address: u64,
+    ) -> Result<VerifiedBreakpoint, DebugError> {
+        let instruction_sequence = InstructionSequence::from_address(debug_info, address)?;
+
+        if let Some(verified_breakpoint) =
+            match_address_before(&instruction_sequence, address, debug_info)
+        {
+            tracing::debug!(
+                "Found valid breakpoint at or before address: {:#010x} : {verified_breakpoint:?}",
+                &address
+            );
+            return Ok(verified_breakpoint);
+        }
+        // If we get here, there is no valid breakpoint location before the address.
+        let message = format!(
+            "Could not identify a valid breakpoint at or before address: {address:#010x}. Please consider using instruction level stepping."
+        );
+        Err(DebugError::WarnAndContinue { message })
+    }
+
+    /// Return the next halt location after `address`, on the same source line as `address`.
+    ///
+    /// This allows stepping through a line with multiple statements one column at a time.
+    /// Returns `None` once there are no more statements on the line, in which case the next
+    /// halt location can be found with [`Self::for_address`].
+    pub fn next_halt_after(
+        debug_info: &DebugInfo,
+        address: u64,
+    ) -> Result<Option<VerifiedBreakpoint>, DebugError> {
+        let instruction_sequence = InstructionSequence::from_address(debug_info, address)?;
+
+        Ok(instruction_sequence.next_halt_after(address))
+    }
+
+    /// Return all valid breakpoint locations with an address inside `range`, ordered by address.
+    ///
+    /// The range can span multiple sequences and compilation units. If multiple halt locations
+    /// share an address, only the first one is returned.
+    pub(crate) fn for_address_range(
+        debug_info: &DebugInfo,
+        range: Range<u64>,
+    ) -> Result<Vec<VerifiedBreakpoint>, DebugError> {
+        let mut verified_breakpoints = Vec::new();
+
+        for program_unit in &debug_info.unit_infos {
+            let Some(ref line_program) = program_unit.unit.line_program else {
+                // Not all compilation units need to have debug line information, so we skip those.
+                continue;
+            };
+
+            let Ok((complete_line_program, line_sequences)) = line_program.clone().sequences()
+            else {
+                tracing::debug!("Failed to get line sequences for line program");
+                continue;
+            };
+
+            for line_sequence in line_sequences.iter().filter(|line_sequence| {
+                line_sequence.start < range.end && range.start < line_sequence.end
+            }) {
+                let instruction_sequence = InstructionSequence::from_line_sequence(
+                    debug_info,
+                    program_unit,
+                    &complete_line_program,
+                    line_sequence,
+                );
+
+                let halt_locations =
+                    instruction_sequence
+                        .instructions
+                        .iter()
+                        .filter(|instruction_location| {
+                            instruction_location.instruction_type == InstructionType::HaltLocation
+                                && range.contains(&instruction_location.address)
+                        });
+
+                for instruction_location in halt_locations {
+                    let Some(source_location) = SourceLocation::from_instruction_location(
+                        debug_info,
+                        program_unit,
+                        instruction_location,
+                    ) else {
+                        continue;
+                    };
+
+                    verified_breakpoints.push(VerifiedBreakpoint {
+                        address: instruction_location.address,
+                        source_location,
+                        inline_frames: inline_frames(
+                            debug_info,
+                            program_unit,
+                            instruction_location.address,
+                        ),
+                    });
+                }
+            }
+        }
+
+        verified_breakpoints.sort_by_key(|verified_breakpoint| verified_breakpoint.address);
+        verified_breakpoints.dedup_by_key(|verified_breakpoint| verified_breakpoint.address);
+
+        Ok(verified_breakpoints)
+    }
+
     /// Identifying the breakpoint location for a specific location (path, line, colunmn) is a bit more complex,
     /// compared to the `for_address()` method, due to a few factors:
     /// - The correct program instructions, may be in any of the compilation units of the current program.
@@ -68,8 +182,9 @@ impl VerifiedBreakpoint {
     /// 3. Convert remaining [`LineSequence`], to [`InstructionSequence`].
     /// 4. Return the first [`InstructionSequence`] that contains the requested source location.
     ///    1. This may be an exact match on file/line/column, or,
-    ///    2. Failing an exact match, a match on file/line only.
-    ///    3. Failing that, a match on file only, where the line number is the "next" available instruction,
+    ///    2. Failing an exact match, a match on file/line with the nearest column after the requested column.
+    ///    3. Failing that, a match on file/line only.
+    ///    4. Failing that, a match on file only, where the line number is the "next" available instruction,
     ///       on the next available line of the specified file.
     pub(crate) fn for_source_location(
         debug_info: &DebugInfo,
@@ -77,6 +192,9 @@ impl VerifiedBreakpoint {
         line: u64,
         column: Option<u64>,
     ) -> Result<Self, DebugError> {
+        // The reasons why units which contain the file did not provide a breakpoint location.
+        let mut unit_failures = Vec::new();
+
         for program_unit in &debug_info.unit_infos {
             let Some(ref line_program) = program_unit.unit.line_program else {
                 // Not all compilation units need to have debug line information, so we skip those.
@@ -125,9 +243,16 @@ impl VerifiedBreakpoint {
             let Ok((complete_line_program, line_sequences)) = line_program.clone().sequences()
             else {
                 tracing::debug!("Failed to get line sequences for line program");
+                unit_failures.push(format!(
+                    "the file matched in unit {:?}, but its line program could not be read",
+                    unit_name(program_unit)
+                ));
                 continue;
             };
 
+            // The last line of the file which has instructions in this unit.
+            let mut last_line = None;
+
             for line_sequence in line_sequences {
                 let instruction_sequence = InstructionSequence::from_line_sequence(
                     debug_info,
@@ -136,6 +261,15 @@ impl VerifiedBreakpoint {
                     &line_sequence,
                 );
 
+                last_line = last_line.max(
+                    instruction_sequence
+                        .instructions
+                        .iter()
+                        .filter(|location| matching_file_indices.contains(&location.file_index))
+                        .filter_map(|location| location.line.map(NonZeroU64::get))
+                        .max(),
+                );
+
                 for matching_file_index in &matching_file_indices {
                     // Cycle through various degrees of matching, to find the most relevant source location.
                     if let Some(verified_breakpoint) = match_file_line_column(
@@ -149,6 +283,17 @@ impl VerifiedBreakpoint {
                         return Ok(verified_breakpoint);
                     }
 
+                    if let Some(verified_breakpoint) = match_file_line_nearest_greater_column(
+                        &instruction_sequence,
+                        *matching_file_index,
+                        line,
+                        column,
+                        debug_info,
+                        program_unit,
+                    ) {
+                        return Ok(verified_breakpoint);
+                    }
+
                     if let Some(verified_breakpoint) = match_file_line_first_available_column(
                         &instruction_sequence,
                         *matching_file_index,
@@ -160,12 +305,51 @@ impl VerifiedBreakpoint {
                     }
                 }
             }
+
+            let unit_name = unit_name(program_unit);
+            unit_failures.push(match last_line {
+                Some(last_line) if line > last_line => format!(
+                    "the file matched in unit {unit_name:?}, but line {line} is past its last statement on line {last_line}"
+                ),
+                Some(_) => {
+                    format!("the file matched in unit {unit_name:?}, but line {line} has no statement")
+                }
+                None => format!("the file matched in unit {unit_name:?}, but has no statements in it"),
+            });
         }
+
         // If we get here, we have not found a valid breakpoint location.
-        Err(DebugError::Other(format!(
-            "No valid breakpoint information found for file: {}, line: {line:?}, column: {column:?}",
-            path.display()
-        )))
+        let reason = if unit_failures.is_empty() {
+            "the file was not found in the debug information".to_string()
+        } else {
+            unit_failures.join("; ")
+        };
+
+        Err(DebugError::WarnAndContinue {
+            message: format!(
+                "No valid breakpoint information found for file: {}, line: {line:?}, column: {column:?}: {reason}",
+                path.display()
+            ),
+        })
+    }
+}
+
+/// Resolve the call sites of the inlined functions that contain `address`, innermost first.
+fn inline_frames(
+    debug_info: &DebugInfo,
+    program_unit: &UnitInfo,
+    address: u64,
+) -> Vec<SourceLocation> {
+    match program_unit.get_function_dies(debug_info, address) {
+        Ok(function_dies) => function_dies
+            .iter()
+            .rev()
+            .filter_map(|function_die| function_die.inline_call_location(debug_info))
+            .collect(),
+        Err(error) => {
+            tracing::debug!("Unable to resolve inlined functions at {address:#010x}: {error}");
+            Vec::new()
+        }
     }
 }
 
@@ -194,12 +378,42 @@ fn match_address(
         Some(VerifiedBreakpoint {
             address: instruction_location.address,
             source_location,
+            inline_frames: inline_frames(
+                debug_info,
+                instruction_sequence.program_unit,
+                instruction_location.address,
+            ),
         })
     } else {
         None
     }
 }
 
+/// Find the valid halt instruction location that is equal to, or less than, the address.
+fn match_address_before(
+    instruction_sequence: &InstructionSequence<'_>,
+    address: u64,
+    debug_info: &DebugInfo,
+) -> Option<VerifiedBreakpoint> {
+    let instruction_location = instruction_sequence.haltpoint_at_or_before_address(address)?;
+
+    let source_location = SourceLocation::from_instruction_location(
+        debug_info,
+        instruction_sequence.program_unit,
+        instruction_location,
+    )?;
+
+    Some(VerifiedBreakpoint {
+        address: instruction_location.address,
+        source_location,
+        inline_frames: inline_frames(
+            debug_info,
+            instruction_sequence.program_unit,
+            instruction_location.address,
+        ),
+    })
+}
+
 /// Find the valid halt instruction location that matches the file, line and column.
 fn match_file_line_column(
     instruction_sequence: &InstructionSequence<'_>,
@@ -228,26 +442,26 @@ fn match_file_line_column(
     Some(VerifiedBreakpoint {
         address: instruction_location.address,
         source_location,
+        inline_frames: inline_frames(debug_info, program_unit, instruction_location.address),
     })
 }
 
-/// Find the first valid halt instruction location that matches the file and line, ignoring column.
-fn match_file_line_first_available_column(
+/// Find the valid halt instruction location that matches the file and line, with the smallest column
+/// that is greater than or equal to the requested column.
+fn match_file_line_nearest_greater_column(
     instruction_sequence: &InstructionSequence<'_>,
     matching_file_index: u64,
     line: u64,
+    column: Option<u64>,
     debug_info: &DebugInfo,
     program_unit: &UnitInfo,
 ) -> Option<VerifiedBreakpoint> {
-    let instruction_location =
-        instruction_sequence
-            .instructions
-            .iter()
-            .find(|instruction_location| {
-                instruction_location.instruction_type == InstructionType::HaltLocation
-                    && matching_file_index == instruction_location.file_index
-                    && NonZeroU64::new(line) == instruction_location.line
-            })?;
+    let instruction_location = nearest_greater_column(
+        &instruction_sequence.instructions,
+        matching_file_index,
+        line,
+        column?,
+    )?;
 
     let source_location =
         SourceLocation::from_instruction_location(debug_info, program_unit, instruction_location)?;
@@ -255,155 +469,683 @@ fn match_file_line_first_available_column(
     Some(VerifiedBreakpoint {
         address: instruction_location.address,
         source_location,
+        inline_frames: inline_frames(debug_info, program_unit, instruction_location.address),
     })
 }
 
-fn serialize_typed_path<S>(path: &TypedPathBuf, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: serde::Serializer,
-{
-    serializer.serialize_str(&path.to_string_lossy())
+/// Find the halt location on the file and line with the smallest column that is greater than
+/// or equal to `column`.
+fn nearest_greater_column(
+    instructions: &[InstructionLocation],
+    matching_file_index: u64,
+    line: u64,
+    column: u64,
+) -> Option<&InstructionLocation> {
+    instructions
+        .iter()
+        .filter(|instruction_location| {
+            instruction_location.instruction_type == InstructionType::HaltLocation
+                && matching_file_index == instruction_location.file_index
+                && NonZeroU64::new(line) == instruction_location.line
+                && instruction_location.column >= ColumnType::Column(column)
+        })
+        .min_by_key(|instruction_location| instruction_location.column)
 }
 
-/// A specific location in source code.
-/// Each unique line, column, file and directory combination is a unique source location.
-#[derive(Clone, PartialEq, Eq, Serialize)]
-pub struct SourceLocation {
-    /// The path to the source file
-    #[serde(serialize_with = "serialize_typed_path")]
-    pub path: TypedPathBuf,
-    /// The line number in the source file with zero based indexing.
-    pub line: Option<u64>,
-    /// The column number in the source file.
-    pub column: Option<ColumnType>,
-    /// The address of the source location.
-    pub address: Option<u64>,
+/// Find the halt location on the file and line with the lowest address.
+fn first_available_column(
+    instructions: &[InstructionLocation],
+    matching_file_index: u64,
+    line: u64,
+) -> Option<&InstructionLocation> {
+    instructions
+        .iter()
+        .filter(|instruction_location| {
+            instruction_location.instruction_type == InstructionType::HaltLocation
+                && matching_file_index == instruction_location.file_index
+                && NonZeroU64::new(line) == instruction_location.line
+        })
+        .min_by_key(|instruction_location| instruction_location.address)
 }
 
-impl Debug for SourceLocation {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}:{:?}:{:?}",
-            self.path.to_path().display(),
-            self.line,
-            self.column
-        )
-    }
-}
+/// Find the valid halt instruction location that matches the file and line, with the lowest
+/// address.
+fn match_file_line_first_available_column(
+    instruction_sequence: &InstructionSequence<'_>,
+    matching_file_index: u64,
+    line: u64,
+    debug_info: &DebugInfo,
+    program_unit: &UnitInfo,
+) -> Option<VerifiedBreakpoint> {
+    let instruction_location = first_available_column(
+        &instruction_sequence.instructions,
+        matching_file_index,
+        line,
+    )?;
 
-impl SourceLocation {
-    /// Resolve debug information for a [`InstructionLocation`] and create a [`SourceLocation`].
-    fn from_instruction_location(
-        debug_info: &DebugInfo,
-        program_unit: &unit_info::UnitInfo,
-        instruction_location: &InstructionLocation,
-    ) -> Option<SourceLocation> {
-        debug_info
-            .find_file_and_directory(&program_unit.unit, instruction_location.file_index)
-            .map(|path| SourceLocation {
-                line: instruction_location.line.map(std::num::NonZeroU64::get),
-                column: Some(instruction_location.column),
-                path,
-                address: Some(instruction_location.address),
-            })
-    }
+    let source_location =
+        SourceLocation::from_instruction_location(debug_info, program_unit, instruction_location)?;
 
-    /// Get the file name of the source file
-    pub fn file_name(&self) -> Option<String> {
-        self.path
-            .file_name()
-            .map(|name| String::from_utf8_lossy(name).to_string())
-    }
+    Some(VerifiedBreakpoint {
+        address: instruction_location.address,
+        source_location,
+        inline_frames: inline_frames(debug_info, program_unit, instruction_location.address),
+    })
 }
 
-/// Keep track of all the instruction locations required to satisfy the operations of [`SteppingMode`][s].
-/// This is a list of target instructions, belonging to a [`gimli::LineSequence`],
-/// and filters it to only user code instructions (no prologue code, and no non-statement instructions),
-/// so that we are left only with what DWARF terms as "recommended breakpoint location".
+/// A read-only view of the instructions belonging to a single statement, for debuggers that
+/// implement their own stepping on top of [`DebugInfo`].
 ///
-/// [s]: crate::debug::debug_step::SteppingMode
-struct InstructionSequence<'debug_info> {
-    /// The `address_range.start` is the starting address of the program counter for which this sequence is valid,
-    /// and allows us to identify target instruction locations where the program counter lies inside the prologue.
-    /// The `address_range.end` is the first address that is not covered by this sequence within the line number program,
-    /// and allows us to identify when stepping over a instruction location would result in leaving a sequence.
-    /// - This is typically the instruction address of the first instruction in the next sequence,
-    ///   which may also be the first instruction in a new function.
-    address_range: Range<u64>,
-    // NOTE: Use Vec as a container, because we will have relatively few statements per sequence, and we need to maintain the order.
-    instructions: Vec<InstructionLocation>,
-    // The following private fields are required to resolve the source location information for
-    // each instruction location.
-    debug_info: &'debug_info DebugInfo,
-    program_unit: &'debug_info UnitInfo,
+/// A statement block starts at a halt location, and extends up to the next halt location, or the
+/// end of the sequence. The edges connect the blocks in address order, and do not take
+/// branches into account. A block also ends at the edge of a function range, e.g. where a
+/// function with `DW_AT_ranges` continues at a discontiguous address, and no edges cross it.
+/// The instructions between such an edge and the next halt location, like the instructions
+/// before the first halt location of a sequence, are not part of any block.
+/// When the blocks are built with access to target memory, a block also ends after each branch,
+/// call or return, see [`DebugInfo::blocks_for_address_with_memory`].
+///
+/// An epilogue (`DW_LNS_set_epilogue_begin`) always starts its own block, which returns to the
+/// caller instead of continuing with the next block. The block following an epilogue is linked
+/// to the preceding statement based on its line and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementBlock {
+    /// The addresses covered by the block, including the last instruction.
+    pub address_range: RangeInclusive<u64>,
+    /// Whether the block is part of an inlined function.
+    pub inlined: bool,
+    /// The start address of the preceding block, if any.
+    ///
+    /// This is the neighbour of the block in address order, not necessarily the block which
+    /// executes before it. Only for the block following an epilogue, the preceding statement is
+    /// chosen by line and column instead.
+    pub stepped_from: Option<u64>,
+    /// The start address of the following block, if any.
+    ///
+    /// This is the neighbour of the block in address order, not necessarily the block which
+    /// executes after it. Epilogue blocks have no following block.
+    pub steps_to: Option<u64>,
 }
 
-impl Debug for InstructionSequence<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "Instruction Sequence with address range: {:#010x} - {:#010x}",
-            self.address_range.start, self.address_range.end
-        )?;
-        for instruction_location in &self.instructions {
-            writeln!(
-                f,
-                "\t{instruction_location:?} - {}",
-                self.debug_info
-                    .get_path(&self.program_unit.unit, instruction_location.file_index)
-                    .map(|file_path| file_path.to_string_lossy().to_string())
-                    .unwrap_or("<unknown file>".to_string())
-            )?;
+impl StatementBlock {
+    /// Return the statement blocks of the sequence that contains `address`.
+    pub(crate) fn for_address(
+        debug_info: &DebugInfo,
+        address: u64,
+    ) -> Result<Vec<StatementBlock>, DebugError> {
+        let instruction_sequence = InstructionSequence::from_address(debug_info, address)?;
+
+        Ok(Self::for_sequence(debug_info, &instruction_sequence))
+    }
+
+    /// Same as [`Self::for_address`], but the opcodes in target memory are used to also end a
+    /// block after each branch, call or return, which are not described by the DWARF line rows.
+    ///
+    /// The instruction following a branch only starts a new block, it does not become a halt
+    /// location. Only Thumb and ARM (A32) instructions are decoded, for all other instruction
+    /// sets this behaves like [`Self::for_address`].
+    pub(crate) fn for_address_with_memory(
+        debug_info: &DebugInfo,
+        address: u64,
+        memory: &mut dyn MemoryInterface,
+        instruction_set: InstructionSet,
+    ) -> Result<Vec<StatementBlock>, DebugError> {
+        let mut instruction_sequence = InstructionSequence::from_address(debug_info, address)?;
+        instruction_sequence.classify_with_memory(memory, instruction_set);
+
+        Ok(Self::for_sequence(debug_info, &instruction_sequence))
+    }
+
+    fn for_sequence(
+        debug_info: &DebugInfo,
+        instruction_sequence: &InstructionSequence<'_>,
+    ) -> Vec<StatementBlock> {
+        let mut blocks = statement_blocks(
+            &instruction_sequence.address_range,
+            &instruction_sequence.instructions,
+            &debug_info.function_range_boundaries(&instruction_sequence.address_range),
+        );
+        for block in &mut blocks {
+            block.inlined = debug_info
+                .get_function_dies(*block.address_range.start())
+                .is_ok_and(|(_, function_dies)| {
+                    function_dies.last().is_some_and(|die| die.is_inline())
+                });
         }
-        Ok(())
+
+        blocks
     }
-}
 
-impl<'debug_info> InstructionSequence<'debug_info> {
-    /// Extract all the instruction locations, belonging to the active sequence (i.e. the sequence that contains the `address`).
-    fn from_address(
-        debug_info: &'debug_info DebugInfo,
-        program_counter: u64,
-    ) -> Result<Self, DebugError> {
-        let program_unit = debug_info.compile_unit_info(program_counter)?;
-        let (offset, address_size) = if let Some(line_program) =
-            program_unit.unit.line_program.clone()
-        {
-            (
-                line_program.header().offset(),
-                line_program.header().address_size(),
-            )
-        } else {
-            let message = "The specified source location does not have any line_program information available. Please consider using instruction level stepping.".to_string();
-            return Err(DebugError::WarnAndContinue { message });
+    /// Find the address to halt at when stepping out of the inlined function containing `pc`.
+    ///
+    /// The `steps_to` edges are followed from the block containing `pc`, until a block starts
+    /// outside of the innermost inlined function. Returns `None` if `pc` is not in an inlined
+    /// function, or no such block exists in the sequence.
+    pub(crate) fn step_out_address(
+        debug_info: &DebugInfo,
+        pc: u64,
+    ) -> Result<Option<u64>, DebugError> {
+        let (_, function_dies) = debug_info.get_function_dies(pc)?;
+        let Some(inlined_function) = function_dies.last().filter(|die| die.is_inline()) else {
+            // Stepping out of a non-inlined function requires the return address.
+            return Ok(None);
         };
 
-        // Get the sequences of rows from the CompleteLineProgram at the given program_counter.
-        let incomplete_line_program =
-            debug_info
-                .debug_line_section
-                .program(offset, address_size, None, None)?;
-        let (complete_line_program, line_sequences) = incomplete_line_program.sequences()?;
-
-        // Get the sequence of rows that belongs to the program_counter.
-        let Some(line_sequence) = line_sequences.iter().find(|line_sequence| {
-            line_sequence.start <= program_counter && program_counter < line_sequence.end
-        }) else {
-            let message = "The specified source location does not have any line information available. Please consider using instruction level stepping.".to_string();
-            return Err(DebugError::WarnAndContinue { message });
-        };
-        let instruction_sequence = Self::from_line_sequence(
-            debug_info,
-            program_unit,
-            &complete_line_program,
-            line_sequence,
+        let instruction_sequence = InstructionSequence::from_address(debug_info, pc)?;
+        let blocks = statement_blocks(
+            &instruction_sequence.address_range,
+            &instruction_sequence.instructions,
+            &debug_info.function_range_boundaries(&instruction_sequence.address_range),
         );
 
-        if instruction_sequence.len() == 0 {
-            let message = "Could not find valid instruction locations for this address. Consider using instruction level stepping.".to_string();
-            Err(DebugError::WarnAndContinue { message })
-        } else {
+        let mut block = blocks
+            .iter()
+            .find(|block| block.address_range.contains(&pc));
+        while let Some(next) = block.and_then(|block| block.steps_to) {
+            if !inlined_function.range_contains(next) {
+                return Ok(Some(next));
+            }
+
+            block = blocks
+                .iter()
+                .find(|block| *block.address_range.start() == next);
+        }
+
+        Ok(None)
+    }
+}
+
+/// Return the addresses of all the rows of the sequence that contains `address`.
+///
+/// See [`DebugInfo::instruction_addresses`].
+pub(crate) fn instruction_addresses(
+    debug_info: &DebugInfo,
+    address: u64,
+) -> Result<Vec<u64>, DebugError> {
+    let instruction_sequence = InstructionSequence::from_address(debug_info, address)?;
+
+    Ok(instruction_sequence.all_instruction_addresses())
+}
+
+/// Find the first halt location of the sequence starting at, or closest after, `after`.
+///
+/// The sequences of all compilation units are considered, so the next sequence can belong to a
+/// different unit. Sequences without any halt location are skipped. Returns `None` if there are
+/// no more sequences, i.e. at the end of the program.
+pub(crate) fn next_sequence_start(debug_info: &DebugInfo, after: u64) -> Option<u64> {
+    let mut line_programs = Vec::new();
+    let mut candidates = Vec::new();
+
+    for program_unit in &debug_info.unit_infos {
+        let Some(ref line_program) = program_unit.unit.line_program else {
+            continue;
+        };
+
+        let Ok((complete_line_program, line_sequences)) = line_program.clone().sequences() else {
+            tracing::debug!("Failed to get line sequences for line program");
+            continue;
+        };
+
+        for (index, line_sequence) in line_sequences.iter().enumerate() {
+            if line_sequence.start >= after {
+                candidates.push((line_sequence.start, line_programs.len(), index));
+            }
+        }
+
+        line_programs.push((program_unit, complete_line_program, line_sequences));
+    }
+
+    // Only build the sequences in address order, until one with a halt location is found.
+    candidates.sort_unstable();
+
+    candidates.into_iter().find_map(|(_, program, index)| {
+        let (program_unit, complete_line_program, line_sequences) = &line_programs[program];
+        let instruction_sequence = InstructionSequence::from_line_sequence(
+            debug_info,
+            program_unit,
+            complete_line_program,
+            &line_sequences[index],
+        );
+
+        instruction_sequence
+            .instructions
+            .iter()
+            .find(|instruction_location| {
+                instruction_location.instruction_type == InstructionType::HaltLocation
+            })
+            .map(|halt_location| halt_location.address)
+    })
+}
+
+/// The maximum number of sequences kept in a [`SequenceCache`].
+const SEQUENCE_CACHE_SIZE: usize = 16;
+
+/// A least recently used cache of the instruction locations of line sequences.
+///
+/// Sequences are identified by the offset of their line program and their start address.
+#[derive(Debug, Default)]
+pub(crate) struct SequenceCache {
+    /// The cached sequences, with the most recently used one last.
+    sequences: Vec<CachedSequence>,
+}
+
+/// The instruction locations of a line sequence, as built by [`InstructionSequence::from_line_sequence`].
+///
+/// The instruction locations are shared, so that a cache hit doesn't copy them.
+#[derive(Debug, Clone)]
+struct CachedSequence {
+    line_program: gimli::DebugLineOffset,
+    address_range: Range<u64>,
+    instructions: Rc<[InstructionLocation]>,
+}
+
+impl SequenceCache {
+    /// Get the sequence of the line program at `line_program` which contains `address`, and
+    /// mark it as the most recently used one.
+    fn get(
+        &mut self,
+        line_program: gimli::DebugLineOffset,
+        address: u64,
+    ) -> Option<CachedSequence> {
+        let index = self.sequences.iter().position(|sequence| {
+            sequence.line_program == line_program && sequence.address_range.contains(&address)
+        })?;
+
+        let sequence = self.sequences.remove(index);
+        self.sequences.push(sequence);
+
+        self.sequences.last().cloned()
+    }
+
+    /// Add a sequence, evicting the least recently used one if the cache is full.
+    fn insert(&mut self, sequence: CachedSequence) {
+        self.sequences.retain(|cached| {
+            cached.line_program != sequence.line_program
+                || cached.address_range.start != sequence.address_range.start
+        });
+
+        if self.sequences.len() >= SEQUENCE_CACHE_SIZE {
+            self.sequences.remove(0);
+        }
+
+        self.sequences.push(sequence);
+    }
+}
+
+/// Read the line program of `program_unit` at `offset`, and split it into its sequences.
+///
+/// Failures are reported with the unit and offset, as they are caused by broken DWARF.
+fn line_program_sequences(
+    debug_info: &DebugInfo,
+    program_unit: &UnitInfo,
+    offset: gimli::DebugLineOffset,
+    address_size: u8,
+) -> Result<
+    (
+        gimli::CompleteLineProgram<GimliReader>,
+        Vec<LineSequence<GimliReader>>,
+    ),
+    DebugError,
+> {
+    debug_info
+        .debug_line_section
+        .program(offset, address_size, None, None)
+        .and_then(|line_program| line_program.sequences())
+        .map_err(|error| DebugError::WarnAndContinue {
+            message: format!(
+                "Failed to read the line program at offset {:#x} of unit {:?}: {error}. The debug information may be corrupt, please consider using instruction level stepping.",
+                offset.0,
+                unit_name(program_unit)
+            ),
+        })
+}
+
+/// The name of the unit, for use in diagnostic messages.
+fn unit_name(program_unit: &UnitInfo) -> String {
+    program_unit
+        .unit
+        .name
+        .as_ref()
+        .and_then(|name| name.to_string_lossy().ok())
+        .map(|name| name.into_owned())
+        .unwrap_or_else(|| "<unnamed>".to_string())
+}
+
+/// Split the instruction locations of a sequence into [`StatementBlock`]s.
+///
+/// A block starts at each halt location, and at each location marked as a branch successor by
+/// [`mark_branch_successors`]. Multiple halt locations can share an address, e.g. for the call
+/// site of an inlined function, in which case only a single block is created. Halt locations
+/// outside of `address_range` would result in blocks without any instructions, so they are
+/// skipped.
+///
+/// Epilogue blocks are linked from the preceding statement block, and do not step to any block
+/// of the sequence. The block following an epilogue is reached by a branch, so it is linked from
+/// the preceding statement in the same file and function range with the closest line and column
+/// before its own.
+///
+/// The sorted `range_boundaries` are the edges of the function ranges inside the sequence, see
+/// [`DebugInfo::function_range_boundaries`]. A block never extends across such an edge, and no
+/// edges are created between blocks on different sides of it, because a discontiguous part of
+/// a function is only reached by a branch. The instructions from such an edge up to the next
+/// block start are not covered by any block, as there is no halt location to start one at.
+fn statement_blocks(
+    address_range: &Range<u64>,
+    instructions: &[InstructionLocation],
+    range_boundaries: &[u64],
+) -> Vec<StatementBlock> {
+    let mut block_starts: Vec<&InstructionLocation> = instructions
+        .iter()
+        .filter(|instruction| {
+            (instruction.instruction_type == InstructionType::HaltLocation
+                || instruction.branch_successor)
+                && address_range.contains(&instruction.address)
+        })
+        .collect();
+    block_starts.dedup_by_key(|instruction| instruction.address);
+
+    // The first edge of a function range after `address`.
+    let next_boundary = |address: u64| {
+        let index = range_boundaries.partition_point(|&boundary| boundary <= address);
+        range_boundaries.get(index).copied()
+    };
+
+    // Whether `from` and `to` are not separated by the edge of a function range.
+    let same_range = |from: u64, to: u64| next_boundary(from).is_none_or(|boundary| boundary > to);
+
+    block_starts
+        .iter()
+        .enumerate()
+        .map(|(index, start)| {
+            let next_start = block_starts.get(index + 1).map(|next| next.address);
+            let end = next_start
+                .into_iter()
+                .chain(next_boundary(start.address))
+                .min()
+                .unwrap_or(address_range.end);
+            let next_start = next_start.filter(|&next| same_range(start.address, next));
+
+            let preceding_statements = block_starts[..index].iter().filter(|previous| {
+                !previous.epilogue_begin && same_range(previous.address, start.address)
+            });
+
+            let (stepped_from, steps_to) = if start.epilogue_begin {
+                (preceding_statements.last(), None)
+            } else if index > 0 && block_starts[index - 1].epilogue_begin {
+                // Only statements of the same function range are candidates, a statement of
+                // another function can have a lower line by chance.
+                let stepped_from = preceding_statements
+                    .filter(|previous| {
+                        previous.file_index == start.file_index
+                            && (previous.line, previous.column) <= (start.line, start.column)
+                    })
+                    .max_by_key(|previous| (previous.line, previous.column));
+
+                (stepped_from, next_start)
+            } else {
+                (
+                    index
+                        .checked_sub(1)
+                        .map(|previous| &block_starts[previous])
+                        .filter(|previous| same_range(previous.address, start.address)),
+                    next_start,
+                )
+            };
+
+            StatementBlock {
+                address_range: start.address..=end.saturating_sub(1),
+                inlined: false,
+                stepped_from: stepped_from.map(|previous| previous.address),
+                steps_to,
+            }
+        })
+        .collect()
+}
+
+/// The style of the path of a serialized [`SourceLocation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum PathStyle {
+    Unix,
+    Windows,
+}
+
+/// The serialized form of a [`SourceLocation`].
+///
+/// The style of the path is stored next to it, so the path is read back with the style it was
+/// written with. Locations written without a style, e.g. by older versions, get the style
+/// derived from the path.
+#[derive(Serialize, Deserialize)]
+struct SerializedSourceLocation {
+    path: String,
+    #[serde(default)]
+    path_style: Option<PathStyle>,
+    line: Option<u64>,
+    column: Option<ColumnType>,
+    address: Option<u64>,
+}
+
+impl From<SourceLocation> for SerializedSourceLocation {
+    fn from(location: SourceLocation) -> Self {
+        let path_style = if location.path.is_windows() {
+            Some(PathStyle::Windows)
+        } else {
+            Some(PathStyle::Unix)
+        };
+
+        Self {
+            path: location.path.to_string_lossy().into_owned(),
+            path_style,
+            line: location.line,
+            column: location.column,
+            address: location.address,
+        }
+    }
+}
+
+impl From<SerializedSourceLocation> for SourceLocation {
+    fn from(location: SerializedSourceLocation) -> Self {
+        let path = match location.path_style {
+            Some(PathStyle::Unix) => TypedPathBuf::from_unix(location.path),
+            Some(PathStyle::Windows) => TypedPathBuf::from_windows(location.path),
+            None => TypedPath::derive(&location.path).to_path_buf(),
+        };
+
+        Self {
+            path,
+            line: location.line,
+            column: location.column,
+            address: location.address,
+        }
+    }
+}
+
+/// A specific location in source code.
+/// Each unique line, column, file and directory combination is a unique source location.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "SerializedSourceLocation", into = "SerializedSourceLocation")]
+pub struct SourceLocation {
+    /// The path to the source file
+    pub path: TypedPathBuf,
+    /// The line number in the source file, starting at 1.
+    pub line: Option<u64>,
+    /// The column number in the source file.
+    pub column: Option<ColumnType>,
+    /// The address of the source location.
+    pub address: Option<u64>,
+}
+
+impl Debug for SourceLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{:?}:{:?}",
+            self.path.to_path().display(),
+            self.line,
+            self.column
+        )
+    }
+}
+
+impl SourceLocation {
+    /// Resolve debug information for a [`InstructionLocation`] and create a [`SourceLocation`].
+    fn from_instruction_location(
+        debug_info: &DebugInfo,
+        program_unit: &unit_info::UnitInfo,
+        instruction_location: &InstructionLocation,
+    ) -> Option<SourceLocation> {
+        debug_info
+            .find_file_and_directory(&program_unit.unit, instruction_location.file_index)
+            .map(|path| SourceLocation {
+                line: instruction_location.line.map(std::num::NonZeroU64::get),
+                column: Some(instruction_location.column),
+                path,
+                address: Some(instruction_location.address),
+            })
+    }
+
+    /// Get the file name of the source file
+    pub fn file_name(&self) -> Option<String> {
+        self.path
+            .file_name()
+            .map(|name| String::from_utf8_lossy(name).to_string())
+    }
+
+    /// Check if `path` refers to the source file of this location.
+    ///
+    /// If the encodings differ, `path` is reinterpreted with the encoding of this location
+    /// before both paths are normalized and compared.
+    pub fn matches_path(&self, path: &TypedPathBuf) -> bool {
+        let path = match (&self.path, path) {
+            // Windows paths accept both separators, so the bytes can be reused as is.
+            (TypedPathBuf::Windows(_), TypedPathBuf::Unix(path)) => {
+                TypedPathBuf::from_windows(path.as_bytes())
+            }
+            (TypedPathBuf::Unix(_), TypedPathBuf::Windows(path)) => TypedPathBuf::from_unix(
+                path.as_bytes()
+                    .iter()
+                    .map(|&byte| if byte == b'\\' { b'/' } else { byte })
+                    .collect::<Vec<u8>>(),
+            ),
+            _ => path.clone(),
+        };
+
+        canonical_path_eq(self.path.to_path(), path.to_path())
+    }
+}
+
+/// Keep track of all the instruction locations required to satisfy the operations of [`SteppingMode`][s].
+/// This is a list of target instructions, belonging to a [`gimli::LineSequence`],
+/// and filters it to only user code instructions (no prologue code, and no non-statement instructions),
+/// so that we are left only with what DWARF terms as "recommended breakpoint location".
+///
+/// [s]: crate::debug::debug_step::SteppingMode
+struct InstructionSequence<'debug_info> {
+    /// The `address_range.start` is the starting address of the program counter for which this sequence is valid,
+    /// and allows us to identify target instruction locations where the program counter lies inside the prologue.
+    /// The `address_range.end` is the first address that is not covered by this sequence within the line number program,
+    /// and allows us to identify when stepping over a instruction location would result in leaving a sequence.
+    /// - This is typically the instruction address of the first instruction in the next sequence,
+    ///   which may also be the first instruction in a new function.
+    address_range: Range<u64>,
+    // NOTE: Use Vec as a container, because we will have relatively few statements per sequence, and we need to maintain the order.
+    instructions: Vec<InstructionLocation>,
+    // The following private fields are required to resolve the source location information for
+    // each instruction location.
+    debug_info: &'debug_info DebugInfo,
+    program_unit: &'debug_info UnitInfo,
+}
+
+impl Debug for InstructionSequence<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Instruction Sequence with address range: {:#010x} - {:#010x}",
+            self.address_range.start, self.address_range.end
+        )?;
+        for instruction_location in &self.instructions {
+            writeln!(
+                f,
+                "\t{instruction_location:?} - {}",
+                self.debug_info
+                    .get_path(&self.program_unit.unit, instruction_location.file_index)
+                    .map(|file_path| file_path.to_string_lossy().to_string())
+                    .unwrap_or("<unknown file>".to_string())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'debug_info> InstructionSequence<'debug_info> {
+    /// Extract all the instruction locations, belonging to the active sequence (i.e. the sequence that contains the `address`).
+    fn from_address(
+        debug_info: &'debug_info DebugInfo,
+        program_counter: u64,
+    ) -> Result<Self, DebugError> {
+        let program_unit = debug_info.compile_unit_info(program_counter)?;
+        let (offset, address_size) = if let Some(line_program) =
+            program_unit.unit.line_program.clone()
+        {
+            (
+                line_program.header().offset(),
+                line_program.header().address_size(),
+            )
+        } else {
+            let message = "The specified source location does not have any line_program information available. Please consider using instruction level stepping.".to_string();
+            return Err(DebugError::WarnAndContinue { message });
+        };
+
+        // Building the sequence requires running the whole line program, so reuse the
+        // instruction locations of recently used sequences.
+        let cached_sequence = debug_info
+            .sequence_cache
+            .borrow_mut()
+            .get(offset, program_counter);
+
+        let instruction_sequence = if let Some(cached_sequence) = cached_sequence {
+            InstructionSequence {
+                address_range: cached_sequence.address_range,
+                instructions: cached_sequence.instructions.to_vec(),
+                debug_info,
+                program_unit,
+            }
+        } else {
+            // Get the sequences of rows from the CompleteLineProgram at the given program_counter.
+            let (complete_line_program, line_sequences) =
+                line_program_sequences(debug_info, program_unit, offset, address_size)?;
+
+            // Get the sequence of rows that belongs to the program_counter.
+            let Some(line_sequence) = line_sequences.iter().find(|line_sequence| {
+                line_sequence.start <= program_counter && program_counter < line_sequence.end
+            }) else {
+                let message = "The specified source location does not have any line information available. Please consider using instruction level stepping.".to_string();
+                return Err(DebugError::WarnAndContinue { message });
+            };
+            let instruction_sequence = Self::from_line_sequence(
+                debug_info,
+                program_unit,
+                &complete_line_program,
+                line_sequence,
+            );
+
+            debug_info
+                .sequence_cache
+                .borrow_mut()
+                .insert(CachedSequence {
+                    line_program: offset,
+                    address_range: instruction_sequence.address_range.clone(),
+                    instructions: instruction_sequence.instructions.as_slice().into(),
+                });
+
+            instruction_sequence
+        };
+
+        instruction_sequence.validate_coverage()?;
+
+        if instruction_sequence.len() == 0 {
+            let message = "Could not find valid instruction locations for this address. Consider using instruction level stepping.".to_string();
+            Err(DebugError::WarnAndContinue { message })
+        } else {
             tracing::trace!(
                 "Instruction location for pc={:#010x}\n{:?}",
                 program_counter,
@@ -434,28 +1176,9 @@ impl<'debug_info> InstructionSequence<'debug_info> {
         let mut previous_row: Option<gimli::LineRow> = None;
         while let Ok(Some((_, row))) = sequence_rows.next_row() {
             // Don't do anything until we are at least at the prologue_end() of a function.
-            if row.prologue_end() {
-                prologue_completed = true;
-            }
-
-            // For GNU C, it is known that the `DW_LNS_set_prologue_end` is not set, so we employ the same heuristic as GDB to determine when the prologue is complete.
-            // For other C compilers in the C99/11/17 standard, they will either set the `DW_LNS_set_prologue_end` or they will trigger this heuristic also.
-            // See https://gcc.gnu.org/legacy-ml/gcc-patches/2011-03/msg02106.html
-            if !prologue_completed
-                && matches!(
-                    program_language,
-                    gimli::DW_LANG_C99 | gimli::DW_LANG_C11 | gimli::DW_LANG_C17
-                )
-            {
-                if let Some(prev_row) = previous_row {
-                    if row.end_sequence()
-                        || (row.is_stmt()
-                            && (row.file_index() == prev_row.file_index()
-                                && (row.line() != prev_row.line() || row.line().is_none())))
-                    {
-                        prologue_completed = true;
-                    }
-                }
+            if !prologue_completed {
+                prologue_completed =
+                    is_prologue_complete(program_language, row, previous_row.as_ref());
             }
 
             if !prologue_completed {
@@ -483,42 +1206,298 @@ impl<'debug_info> InstructionSequence<'debug_info> {
         row: &gimli::LineRow,
         previous_row: Option<&gimli::LineRow>,
     ) {
-        // Workaround the line number issue (if recorded as 0 in the DWARF, then gimli reports it as None).
-        // For debug purposes, it makes more sense to be the same as the previous line, which almost always
-        // has the same file index and column value.
-        // This prevents the debugger from jumping to the top of the file unexpectedly.
-        let mut instruction_line = row.line();
-        if let Some(prev_row) = previous_row {
-            if row.line().is_none()
-                && prev_row.line().is_some()
-                && row.file_index() == prev_row.file_index()
-                && prev_row.column() == row.column()
-            {
-                instruction_line = prev_row.line();
-            }
-        }
+        let instruction_location = instruction_location(prologue_completed, row, previous_row);
 
-        let instruction_location = InstructionLocation {
-            address: row.address(),
-            file_index: row.file_index(),
-            line: instruction_line,
-            column: row.column().into(),
-            instruction_type: if !prologue_completed {
-                InstructionType::Prologue
-            } else if row.epilogue_begin() || row.is_stmt() {
-                InstructionType::HaltLocation
-            } else {
-                InstructionType::Unspecified
-            },
-        };
+        push_instruction_location(&mut self.instructions, instruction_location);
+    }
+
+    /// The addresses of all the rows of the sequence, in ascending order.
+    ///
+    /// Unlike the halt locations, this includes rows in the prologue and rows which are not
+    /// statements, for stepping at instruction granularity.
+    fn all_instruction_addresses(&self) -> Vec<u64> {
+        let mut addresses: Vec<u64> = self
+            .instructions
+            .iter()
+            .map(|instruction| instruction.address)
+            .collect();
+        addresses.dedup();
 
-        self.instructions.push(instruction_location);
+        addresses
+    }
+
+    /// Find the halt location at, or immediately before, the `address`.
+    fn haltpoint_at_or_before_address(&self, address: u64) -> Option<&InstructionLocation> {
+        if !self.address_range.contains(&address) {
+            return None;
+        }
+
+        halt_location_at_or_before(&self.instructions, address)
+    }
+
+    /// Use the opcodes in target memory to find the block edges after branches and calls.
+    ///
+    /// See [`mark_branch_successors`] for details.
+    fn classify_with_memory(
+        &mut self,
+        memory: &mut dyn MemoryInterface,
+        instruction_set: InstructionSet,
+    ) {
+        mark_branch_successors(
+            &self.address_range,
+            &mut self.instructions,
+            memory,
+            instruction_set,
+        );
+    }
+
+    /// Find the next halt location after `address`, with a different column on the same line.
+    fn next_halt_after(&self, address: u64) -> Option<VerifiedBreakpoint> {
+        let instruction_location = next_halt_on_line_after(&self.instructions, address)?;
+
+        let source_location = SourceLocation::from_instruction_location(
+            self.debug_info,
+            self.program_unit,
+            instruction_location,
+        )?;
+
+        Some(VerifiedBreakpoint {
+            address: instruction_location.address,
+            source_location,
+            inline_frames: inline_frames(
+                self.debug_info,
+                self.program_unit,
+                instruction_location.address,
+            ),
+        })
     }
 
     /// Get the number of instruction locations in the list.
     fn len(&self) -> usize {
         self.instructions.len()
     }
+
+    /// Check that every instruction location is inside the address range of the sequence,
+    /// and that the addresses never decrease, as required by DWARF 6.2.5.
+    ///
+    /// A failure means that halt locations were lost or mixed up while building the sequence.
+    fn validate_coverage(&self) -> Result<(), DebugError> {
+        validate_instruction_coverage(&self.address_range, &self.instructions)
+    }
+}
+
+/// Search the instruction locations in descending order for the halt location at, or
+/// immediately before, the `address`.
+///
+/// If the `address` is the first instruction of the sequence, that instruction is returned even
+/// if it is not a halt location, unless its role is [`InstructionType::Unknown`].
+fn halt_location_at_or_before(
+    instructions: &[InstructionLocation],
+    address: u64,
+) -> Option<&InstructionLocation> {
+    instructions
+        .iter()
+        .rev()
+        .find(|instruction_location| {
+            instruction_location.instruction_type == InstructionType::HaltLocation
+                && instruction_location.address <= address
+        })
+        .or_else(|| {
+            instructions.first().filter(|instruction_location| {
+                instruction_location.address == address
+                    && instruction_location.instruction_type != InstructionType::Unknown
+            })
+        })
+}
+
+/// A branch, call or return ends a basic block, so the instruction immediately following it
+/// is marked as a [`InstructionLocation::branch_successor`], which starts a new
+/// [`StatementBlock`].
+///
+/// The instruction type is not changed, because the instruction after a call is usually in the
+/// middle of a statement, and halting there would interrupt stepping over that statement.
+///
+/// The DWARF line rows do not contain any branch information, so this is only possible by
+/// decoding the instructions in target memory, which are read in a single block for the whole
+/// sequence. An instruction location only covers a single instruction if the next location
+/// directly follows it, otherwise the branch (if any) is somewhere in between and the locations
+/// are left as they are.
+fn mark_branch_successors(
+    address_range: &Range<u64>,
+    instructions: &mut [InstructionLocation],
+    memory: &mut dyn MemoryInterface,
+    instruction_set: InstructionSet,
+) {
+    if !matches!(
+        instruction_set,
+        InstructionSet::Thumb2 | InstructionSet::A32
+    ) {
+        return;
+    }
+
+    let Ok(length) = usize::try_from(address_range.end.saturating_sub(address_range.start)) else {
+        return;
+    };
+    let mut code = vec![0; length];
+    if let Err(error) = memory.read_8(address_range.start, &mut code) {
+        tracing::debug!(
+            "Unable to read instructions at {:#010x}, using DWARF statement blocks only: {error}",
+            address_range.start
+        );
+        return;
+    }
+
+    for index in 1..instructions.len() {
+        if instructions[index].instruction_type != InstructionType::Unspecified {
+            continue;
+        }
+
+        let previous = &instructions[index - 1];
+        let Some(code) = previous
+            .address
+            .checked_sub(address_range.start)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .and_then(|offset| code.get(offset..))
+        else {
+            continue;
+        };
+
+        if previous.next_after_branch(code, instruction_set) == Some(instructions[index].address) {
+            instructions[index].branch_successor = true;
+        }
+    }
+}
+
+/// Find the position of the instruction location that contains `address`, i.e. the last one
+/// which starts at or before it.
+///
+/// The instruction locations of a sequence are sorted by address, see
+/// [`validate_instruction_coverage`], so this is a binary search.
+fn instruction_position(instructions: &[InstructionLocation], address: u64) -> Option<usize> {
+    instructions
+        .partition_point(|instruction_location| instruction_location.address <= address)
+        .checked_sub(1)
+}
+
+/// Search the instruction locations following the one that contains `address`, for a halt
+/// location with a different column on the same line.
+///
+/// The search stops at the first instruction location for another line.
+fn next_halt_on_line_after(
+    instructions: &[InstructionLocation],
+    address: u64,
+) -> Option<&InstructionLocation> {
+    let position = instruction_position(instructions, address)?;
+    let current = &instructions[position];
+
+    instructions[position + 1..]
+        .iter()
+        .take_while(|instruction_location| {
+            instruction_location.file_index == current.file_index
+                && instruction_location.line == current.line
+        })
+        .find(|instruction_location| {
+            instruction_location.instruction_type == InstructionType::HaltLocation
+                && instruction_location.address > address
+                && instruction_location.column != current.column
+        })
+}
+
+fn validate_instruction_coverage(
+    address_range: &Range<u64>,
+    instructions: &[InstructionLocation],
+) -> Result<(), DebugError> {
+    let mut previous_address = None;
+
+    for instruction in instructions {
+        if !address_range.contains(&instruction.address) {
+            return Err(DebugError::Other(format!(
+                "Instruction location at {:#010x} is outside of the sequence address range {:#010x} - {:#010x}",
+                instruction.address, address_range.start, address_range.end
+            )));
+        }
+
+        // Multiple rows can share an address, e.g. for the call site of an inlined function.
+        if previous_address.is_some_and(|previous| instruction.address < previous) {
+            return Err(DebugError::Other(format!(
+                "Instruction location at {:#010x} follows an instruction location at a higher address in the sequence",
+                instruction.address
+            )));
+        }
+
+        previous_address = Some(instruction.address);
+    }
+
+    Ok(())
+}
+
+/// Append `instruction_location` to `instructions`, unless it duplicates the last one.
+///
+/// Some compilers emit consecutive rows with the same address, file, line and column. Only one
+/// instruction location is kept for them, which is a halt location if any of the rows is one,
+/// and begins an epilogue if any of the rows does.
+fn push_instruction_location(
+    instructions: &mut Vec<InstructionLocation>,
+    instruction_location: InstructionLocation,
+) {
+    if let Some(last) = instructions.last_mut() {
+        if last.address == instruction_location.address
+            && last.file_index == instruction_location.file_index
+            && last.line == instruction_location.line
+            && last.column == instruction_location.column
+        {
+            let epilogue_begin = last.epilogue_begin || instruction_location.epilogue_begin;
+            if last.instruction_type != InstructionType::HaltLocation
+                && instruction_location.instruction_type == InstructionType::HaltLocation
+            {
+                *last = instruction_location;
+            }
+            last.epilogue_begin = epilogue_begin;
+            return;
+        }
+    }
+
+    instructions.push(instruction_location);
+}
+
+/// Create the [`InstructionLocation`] for a `row` of a sequence.
+fn instruction_location(
+    prologue_completed: bool,
+    row: &gimli::LineRow,
+    previous_row: Option<&gimli::LineRow>,
+) -> InstructionLocation {
+    // Workaround the line number issue (if recorded as 0 in the DWARF, then gimli reports it as None).
+    // For debug purposes, it makes more sense to be the same as the previous line, which almost always
+    // has the same file index and column value.
+    // This prevents the debugger from jumping to the top of the file unexpectedly.
+    let mut instruction_line = row.line();
+    if let Some(prev_row) = previous_row {
+        if row.line().is_none()
+            && prev_row.line().is_some()
+            && row.file_index() == prev_row.file_index()
+            && prev_row.column() == row.column()
+        {
+            instruction_line = prev_row.line();
+        }
+    }
+
+    // Without a line, the instruction can not be attributed to any source statement,
+    // e.g. for code synthesized by the compiler.
+    let instruction_type = if prologue_completed && instruction_line.is_none() {
+        InstructionType::Unknown
+    } else {
+        instruction_type(prologue_completed, row)
+    };
+
+    InstructionLocation {
+        address: row.address(),
+        file_index: row.file_index(),
+        line: instruction_line,
+        column: row.column().into(),
+        instruction_type,
+        epilogue_begin: row.epilogue_begin(),
+        branch_successor: false,
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -529,10 +1508,16 @@ enum InstructionType {
     Prologue,
     /// DWARF defined "recommended breakpoint location",
     /// typically marked with `is_stmt` or `epilogue_begin`.
+    /// The start of a basic block (`DW_LNS_basic_block`) is also a valid halt location,
+    /// for the compilers which emit it.
     HaltLocation,
     /// Any other instruction that is not part of the prologue or epilogue, and is not a statement,
     /// is considered to be an unspecified instruction type.
     Unspecified,
+    /// An instruction on line 0, which could not inherit the line of a preceding row.
+    /// Its role is ambiguous, so it is never used as a halt location, not even when it is
+    /// marked as a statement or follows a branch.
+    Unknown,
 }
 
 #[derive(Clone, Copy)]
@@ -550,6 +1535,10 @@ struct InstructionLocation {
     line: Option<NonZeroU64>,
     column: ColumnType,
     instruction_type: InstructionType,
+    /// The instruction is the first one of an epilogue (`DW_LNS_set_epilogue_begin`).
+    epilogue_begin: bool,
+    /// The instruction directly follows a branch, call or return, see [`mark_branch_successors`].
+    branch_successor: bool,
 }
 
 impl Debug for InstructionLocation {
@@ -572,6 +1561,128 @@ impl Debug for InstructionLocation {
     }
 }
 
+impl InstructionLocation {
+    /// Decode the instruction at the start of `code`, which was read from this location, to
+    /// determine if it changes the control flow.
+    ///
+    /// Returns the address of the following instruction, if this is a branch, call or return.
+    /// Only little endian Thumb and ARM (A32) instructions are decoded, all other instruction
+    /// sets are treated as not branching.
+    fn next_after_branch(&self, code: &[u8], instruction_set: InstructionSet) -> Option<u64> {
+        let halfword = |offset: usize| {
+            code.get(offset..offset + 2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        };
+
+        let size = match instruction_set {
+            InstructionSet::Thumb2 => {
+                let first = halfword(0)?;
+                if is_thumb32_prefix(first) {
+                    is_thumb32_branch(first, halfword(2)?).then_some(4)
+                } else {
+                    is_thumb16_branch(first).then_some(2)
+                }
+            }
+            InstructionSet::A32 => {
+                let opcode = u32::from_le_bytes(code.get(..4)?.try_into().ok()?);
+                is_a32_branch(opcode).then_some(4)
+            }
+            _ => None,
+        }?;
+
+        Some(self.address + size)
+    }
+}
+
+/// The first halfword of a 32-bit Thumb instruction starts with `0b11101`, `0b11110` or `0b11111`.
+fn is_thumb32_prefix(halfword: u16) -> bool {
+    matches!(halfword >> 11, 0b11101..=0b11111)
+}
+
+/// `B<c>`, `B`, `BX`, `BLX`, `CBZ`, `CBNZ` and `POP {..., pc}`.
+fn is_thumb16_branch(opcode: u16) -> bool {
+    let conditional_branch = opcode & 0xF000 == 0xD000 && !matches!((opcode >> 8) & 0xF, 0xE | 0xF);
+
+    conditional_branch
+        || opcode & 0xF800 == 0xE000
+        || opcode & 0xFF00 == 0x4700
+        || opcode & 0xF500 == 0xB100
+        || opcode & 0xFF00 == 0xBD00
+}
+
+/// `B<c>.W`, `B.W`, `BL` and `BLX`.
+fn is_thumb32_branch(first: u16, second: u16) -> bool {
+    if first & 0xF800 != 0xF000 || second & 0x8000 == 0 {
+        return false;
+    }
+
+    match second & 0x5000 {
+        // A condition of `0b111x` encodes the miscellaneous control instructions instead.
+        0x0000 => (first >> 7) & 0x7 != 0x7,
+        _ => true,
+    }
+}
+
+/// `B`, `BL`, `BLX` (immediate) and `BX`, `BLX` (register).
+fn is_a32_branch(opcode: u32) -> bool {
+    (opcode >> 25) & 0x7 == 0b101 || opcode & 0x0FFF_FFD0 == 0x012F_FF10
+}
+
+/// Determine if the prologue of a function is complete at `row`.
+fn is_prologue_complete(
+    program_language: gimli::DwLang,
+    row: &gimli::LineRow,
+    previous_row: Option<&gimli::LineRow>,
+) -> bool {
+    if row.prologue_end() {
+        return true;
+    }
+
+    // For GNU C, it is known that the `DW_LNS_set_prologue_end` is not set, so we employ the same heuristic as GDB to determine when the prologue is complete.
+    // For other C compilers in the C99/11/17 standard, they will either set the `DW_LNS_set_prologue_end` or they will trigger this heuristic also.
+    // The same applies to C++ and Swift compilers which do not set `DW_LNS_set_prologue_end`.
+    // See https://gcc.gnu.org/legacy-ml/gcc-patches/2011-03/msg02106.html
+    if !matches!(
+        program_language,
+        gimli::DW_LANG_C99
+            | gimli::DW_LANG_C11
+            | gimli::DW_LANG_C17
+            | gimli::DW_LANG_C_plus_plus
+            | gimli::DW_LANG_C_plus_plus_03
+            | gimli::DW_LANG_C_plus_plus_11
+            | gimli::DW_LANG_C_plus_plus_14
+            | gimli::DW_LANG_C_plus_plus_17
+            | gimli::DW_LANG_C_plus_plus_20
+            | gimli::DW_LANG_Swift
+    ) {
+        return false;
+    }
+
+    let Some(prev_row) = previous_row else {
+        return false;
+    };
+
+    row.end_sequence()
+        || (row.is_stmt()
+            && (row.file_index() == prev_row.file_index()
+                && (row.line() != prev_row.line() || row.line().is_none())))
+}
+
+/// Classify a [`gimli::LineRow`] for use as an [`InstructionLocation`].
+///
+/// Rustc does not emit `DW_LNS_basic_block`, so for Rust code only `is_stmt` and `epilogue_begin`
+/// mark halt locations. When a compiler does mark the start of a basic block, that row is
+/// used as a halt location, even if it is not a statement.
+fn instruction_type(prologue_completed: bool, row: &gimli::LineRow) -> InstructionType {
+    if !prologue_completed {
+        InstructionType::Prologue
+    } else if row.epilogue_begin() || row.is_stmt() || row.basic_block() {
+        InstructionType::HaltLocation
+    } else {
+        InstructionType::Unspecified
+    }
+}
+
 /// Helper function to avoid code duplication when logging of information during row evaluation.
 fn log_row_eval(
     active_sequence: &LineSequence<super::GimliReader>,
@@ -599,3 +1710,1038 @@ fn log_row_eval(
         status
     );
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        CachedSequence, ColumnType, DebugError, DebugInfo, InstructionLocation,
+        InstructionSequence, InstructionType, SEQUENCE_CACHE_SIZE, SequenceCache, SourceLocation,
+        StatementBlock, first_available_column, halt_location_at_or_before, instruction_location,
+        instruction_position, instruction_type, is_a32_branch, is_prologue_complete,
+        is_thumb16_branch, is_thumb32_branch, line_program_sequences, mark_branch_successors,
+        nearest_greater_column, next_halt_on_line_after, statement_blocks,
+        validate_instruction_coverage,
+    };
+    use probe_rs::{InstructionSet, test::MockMemory};
+    use std::{
+        fmt::Debug,
+        num::NonZeroU64,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{Event, Level, Metadata, field::Field, level_filters::LevelFilter, span};
+    use typed_path::TypedPathBuf;
+
+    /// A subscriber which formats the messages of all events up to `max_level`.
+    struct MessageCollector {
+        max_level: Level,
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl tracing::Subscriber for MessageCollector {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            *metadata.level() <= self.max_level
+        }
+
+        fn max_level_hint(&self) -> Option<LevelFilter> {
+            Some(LevelFilter::from_level(self.max_level))
+        }
+
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut |field: &Field, value: &dyn Debug| {
+                if field.name() == "message" {
+                    self.messages.lock().unwrap().push(format!("{value:?}"));
+                }
+            });
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    fn location(address: u64, file_index: u64, line: u64) -> InstructionLocation {
+        InstructionLocation {
+            address,
+            file_index,
+            line: NonZeroU64::new(line),
+            column: ColumnType::Column(1),
+            instruction_type: InstructionType::HaltLocation,
+            epilogue_begin: false,
+            branch_successor: false,
+        }
+    }
+
+    #[test]
+    fn coverage_with_inlined_block() {
+        let instructions = [
+            location(0x1000, 1, 10),
+            location(0x1004, 1, 11),
+            // Inlined function from another file, starting at the call site address.
+            location(0x1004, 2, 40),
+            location(0x1008, 2, 41),
+            location(0x100c, 1, 12),
+        ];
+
+        assert!(validate_instruction_coverage(&(0x1000..0x1010), &instructions).is_ok());
+    }
+
+    #[test]
+    fn coverage_with_decreasing_address() {
+        let instructions = [
+            location(0x1000, 1, 10),
+            location(0x1008, 2, 40),
+            location(0x1004, 1, 11),
+        ];
+
+        assert!(validate_instruction_coverage(&(0x1000..0x1010), &instructions).is_err());
+    }
+
+    #[test]
+    fn coverage_with_address_outside_of_sequence() {
+        let instructions = [location(0x1000, 1, 10), location(0x1010, 1, 11)];
+
+        assert!(validate_instruction_coverage(&(0x1000..0x1010), &instructions).is_err());
+    }
+
+    fn source_location(path: TypedPathBuf) -> SourceLocation {
+        SourceLocation {
+            path,
+            line: Some(10),
+            column: Some(ColumnType::Column(5)),
+            address: Some(0x1000),
+        }
+    }
+
+    #[test]
+    fn matches_windows_path_with_unix_input() {
+        let location = source_location(TypedPathBuf::from_windows(r"C:\project\src\main.rs"));
+
+        assert!(location.matches_path(&TypedPathBuf::from_unix("C:/project/src/main.rs")));
+        assert!(location.matches_path(&TypedPathBuf::from_unix("C:/project/src/../src/main.rs")));
+        assert!(!location.matches_path(&TypedPathBuf::from_unix("C:/project/src/lib.rs")));
+    }
+
+    #[test]
+    fn matches_unix_path_with_windows_input() {
+        let location = source_location(TypedPathBuf::from_unix("/project/src/main.rs"));
+
+        assert!(location.matches_path(&TypedPathBuf::from_windows(r"\project\src\main.rs")));
+        assert!(location.matches_path(&TypedPathBuf::from_unix("/project/./src/main.rs")));
+        assert!(!location.matches_path(&TypedPathBuf::from_unix("/other/src/main.rs")));
+    }
+
+    #[test]
+    fn source_location_roundtrip() {
+        for path in [
+            TypedPathBuf::from_windows(r"C:\project\src\main.rs"),
+            TypedPathBuf::from_windows(r"src\main.rs"),
+            // Relative Windows paths with forward slashes can't be told apart from Unix paths.
+            TypedPathBuf::from_windows("src/main.rs"),
+            TypedPathBuf::from_unix("/project/src/main.rs"),
+            // Backslashes are valid in Unix file names.
+            TypedPathBuf::from_unix(r"src/odd\name.rs"),
+        ] {
+            let location = source_location(path);
+
+            let json = serde_json::to_string(&location).unwrap();
+            let deserialized: SourceLocation = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(deserialized, location, "{json}");
+        }
+    }
+
+    #[test]
+    fn source_location_without_path_style() {
+        for path in [
+            TypedPathBuf::from_windows(r"C:\project\src\main.rs"),
+            TypedPathBuf::from_unix("/project/src/main.rs"),
+        ] {
+            let location = source_location(path);
+
+            let mut json = serde_json::to_value(&location).unwrap();
+            json.as_object_mut().unwrap().remove("path_style").unwrap();
+            let deserialized: SourceLocation = serde_json::from_value(json.clone()).unwrap();
+
+            assert_eq!(deserialized, location, "{json}");
+        }
+    }
+
+    #[test]
+    fn nearest_greater_column_on_same_line() {
+        let instructions = vec![
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                ..location(0x1000, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(20),
+                ..location(0x1004, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(30),
+                ..location(0x1008, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(15),
+                ..location(0x100c, 1, 11)
+            },
+        ];
+
+        let matched = nearest_greater_column(&instructions, 1, 10, 12).unwrap();
+        assert_eq!(matched.address, 0x1004);
+
+        let matched = nearest_greater_column(&instructions, 1, 10, 5).unwrap();
+        assert_eq!(matched.address, 0x1000);
+
+        assert!(nearest_greater_column(&instructions, 1, 10, 31).is_none());
+    }
+
+    #[test]
+    fn line_program_at_bogus_offset() {
+        let debug_info = DebugInfo::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/inlined-functions"
+        ))
+        .unwrap();
+        let program_unit = &debug_info.unit_infos[0];
+
+        let Err(DebugError::WarnAndContinue { message }) = line_program_sequences(
+            &debug_info,
+            program_unit,
+            gimli::DebugLineOffset(0xdead_beef),
+            4,
+        ) else {
+            panic!("Reading a line program at a bogus offset must fail");
+        };
+
+        assert!(message.contains("0xdeadbeef"), "{message}");
+        assert!(message.contains("src/main.rs"), "{message}");
+    }
+
+    #[test]
+    fn all_instruction_addresses_include_halt_locations() {
+        let debug_info = DebugInfo::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/inlined-functions"
+        ))
+        .unwrap();
+
+        let sequence = InstructionSequence::from_address(&debug_info, 0x2e4).unwrap();
+        let all_addresses = sequence.all_instruction_addresses();
+
+        assert!(all_addresses.is_sorted());
+        assert!(all_addresses.windows(2).all(|pair| pair[0] != pair[1]));
+
+        let mut halt_locations: Vec<u64> = sequence
+            .instructions
+            .iter()
+            .filter(|instruction| instruction.instruction_type == InstructionType::HaltLocation)
+            .map(|instruction| instruction.address)
+            .collect();
+        halt_locations.dedup();
+
+        assert!(
+            halt_locations
+                .iter()
+                .all(|address| all_addresses.contains(address))
+        );
+        // The sequence contains rows which are not statements.
+        assert!(all_addresses.len() > halt_locations.len());
+    }
+
+    #[test]
+    fn sequence_only_formatted_for_trace() {
+        let debug_info = DebugInfo::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/inlined-functions"
+        ))
+        .unwrap();
+
+        for (max_level, formatted) in [(Level::DEBUG, false), (Level::TRACE, true)] {
+            let messages = Arc::default();
+            let collector = MessageCollector {
+                max_level,
+                messages: Arc::clone(&messages),
+            };
+
+            tracing::subscriber::with_default(collector, || {
+                InstructionSequence::from_address(&debug_info, 0x2e4).unwrap();
+            });
+
+            let messages = messages.lock().unwrap();
+            assert_eq!(
+                messages
+                    .iter()
+                    .any(|message| message.starts_with("Instruction location for pc=0x000002e4")),
+                formatted,
+                "{max_level}: {messages:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sequence_reused_from_cache() {
+        let debug_info = DebugInfo::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/inlined-functions"
+        ))
+        .unwrap();
+
+        let sequence = InstructionSequence::from_address(&debug_info, 0x2e4).unwrap();
+        let instruction_count = sequence.len();
+        assert!(instruction_count > 1);
+
+        // Remove all but the first instruction from the cached sequence, so that a cache hit can
+        // be told apart from rebuilding the sequence.
+        {
+            let mut cache = debug_info.sequence_cache.borrow_mut();
+            assert_eq!(cache.sequences.len(), 1);
+            assert!(cache.sequences[0].address_range.contains(&0x2e4));
+            cache.sequences[0].instructions = cache.sequences[0].instructions[..1].into();
+        }
+
+        let sequence = InstructionSequence::from_address(&debug_info, 0x2e4).unwrap();
+        assert_eq!(sequence.len(), 1);
+        assert_eq!(debug_info.sequence_cache.borrow().sequences.len(), 1);
+    }
+
+    #[test]
+    fn sequence_cache_evicts_least_recently_used() {
+        let sequence = |start: u64| CachedSequence {
+            line_program: gimli::DebugLineOffset(0),
+            address_range: start..start + 0x10,
+            instructions: Rc::from([]),
+        };
+
+        let mut cache = SequenceCache::default();
+        for index in 0..SEQUENCE_CACHE_SIZE as u64 {
+            cache.insert(sequence(index * 0x10));
+        }
+
+        // Using the first sequence makes the second one the least recently used.
+        assert!(cache.get(gimli::DebugLineOffset(0), 0x4).is_some());
+        assert!(cache.get(gimli::DebugLineOffset(1), 0x4).is_none());
+
+        cache.insert(sequence(0x1000));
+
+        assert_eq!(cache.sequences.len(), SEQUENCE_CACHE_SIZE);
+        assert!(cache.get(gimli::DebugLineOffset(0), 0x14).is_none());
+        assert!(cache.get(gimli::DebugLineOffset(0), 0x4).is_some());
+        assert!(cache.get(gimli::DebugLineOffset(0), 0x1004).is_some());
+    }
+
+    #[test]
+    fn left_edge_equals_column_zero() {
+        assert_eq!(ColumnType::LeftEdge, ColumnType::Column(0));
+
+        let mut left_edge = source_location(TypedPathBuf::from_unix("/project/src/main.rs"));
+        left_edge.column = Some(ColumnType::LeftEdge);
+        let mut column_zero = left_edge.clone();
+        column_zero.column = Some(ColumnType::Column(0));
+
+        assert_eq!(left_edge, column_zero);
+
+        let mut locations = vec![left_edge, column_zero];
+        locations.dedup();
+        assert_eq!(locations.len(), 1);
+    }
+
+    #[test]
+    fn column_ordering() {
+        assert!(ColumnType::LeftEdge < ColumnType::Column(1));
+        assert!(ColumnType::Column(1) < ColumnType::Column(2));
+        assert!(ColumnType::Column(u64::MAX) > ColumnType::LeftEdge);
+
+        let mut columns = vec![
+            ColumnType::Column(12),
+            ColumnType::LeftEdge,
+            ColumnType::Column(3),
+            ColumnType::LeftEdge,
+        ];
+        columns.sort();
+        assert_eq!(
+            columns,
+            [
+                ColumnType::LeftEdge,
+                ColumnType::LeftEdge,
+                ColumnType::Column(3),
+                ColumnType::Column(12)
+            ]
+        );
+    }
+
+    #[test]
+    fn first_available_column_prefers_lowest_address() {
+        let instructions = vec![
+            InstructionLocation {
+                column: ColumnType::Column(20),
+                ..location(0x1000, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                ..location(0x1004, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                ..location(0x1008, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::LeftEdge,
+                ..location(0x100c, 1, 11)
+            },
+        ];
+
+        // The lowest address wins, independent of the column and the order of the instructions.
+        let matched = first_available_column(&instructions, 1, 10).unwrap();
+        assert_eq!(matched.address, 0x1000);
+
+        let reversed: Vec<_> = instructions.iter().rev().copied().collect();
+        let matched = first_available_column(&reversed, 1, 10).unwrap();
+        assert_eq!(matched.address, 0x1000);
+
+        let matched = first_available_column(&instructions, 1, 11).unwrap();
+        assert_eq!(matched.address, 0x100c);
+    }
+
+    #[test]
+    fn haltpoint_search_backwards() {
+        let instructions = vec![
+            InstructionLocation {
+                instruction_type: InstructionType::Unspecified,
+                ..location(0x1000, 1, 10)
+            },
+            location(0x1004, 1, 11),
+            InstructionLocation {
+                instruction_type: InstructionType::Unspecified,
+                ..location(0x1008, 1, 11)
+            },
+            location(0x100c, 1, 12),
+        ];
+
+        // Between two halt locations, the preceding one is used.
+        let matched = halt_location_at_or_before(&instructions, 0x100a).unwrap();
+        assert_eq!(matched.address, 0x1004);
+
+        // An exact match is returned as is.
+        let matched = halt_location_at_or_before(&instructions, 0x100c).unwrap();
+        assert_eq!(matched.address, 0x100c);
+
+        // The first instruction of the sequence is returned, even if it is not a halt location.
+        let matched = halt_location_at_or_before(&instructions, 0x1000).unwrap();
+        assert_eq!(matched.address, 0x1000);
+
+        // No halt location precedes the address.
+        assert!(halt_location_at_or_before(&instructions, 0x1002).is_none());
+    }
+
+    /// Generate the rows of a line program with a single sequence starting at `0x1000`.
+    ///
+    /// Each row is given as `(address_offset, line, is_statement, basic_block)`.
+    fn line_rows(rows: &[(u64, u64, bool, bool)]) -> Vec<gimli::LineRow> {
+        let rows: Vec<_> = rows
+            .iter()
+            .map(|&(address_offset, line, is_statement, basic_block)| {
+                (address_offset, line, is_statement, basic_block, false)
+            })
+            .collect();
+
+        line_rows_with_epilogue(&rows)
+    }
+
+    /// Like [`line_rows`], with an additional `epilogue_begin` flag for every row.
+    fn line_rows_with_epilogue(rows: &[(u64, u64, bool, bool, bool)]) -> Vec<gimli::LineRow> {
+        use gimli::write::{
+            Address, DebugLine, DebugLineStrOffsets, DebugStrOffsets, EndianVec, LineProgram,
+            LineString,
+        };
+
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+
+        let mut program = LineProgram::new(
+            encoding,
+            gimli::LineEncoding::default(),
+            LineString::String(b"/project".to_vec()),
+            LineString::String(b"main.c".to_vec()),
+            None,
+        );
+        let directory = program.default_directory();
+        let file = program.add_file(LineString::String(b"main.c".to_vec()), directory, None);
+
+        program.begin_sequence(Some(Address::Constant(0x1000)));
+        for &(address_offset, line, is_statement, basic_block, epilogue_begin) in rows {
+            let row = program.row();
+            row.address_offset = address_offset;
+            row.file = file;
+            row.line = line;
+            row.is_statement = is_statement;
+            row.basic_block = basic_block;
+            row.epilogue_begin = epilogue_begin;
+            program.generate_row();
+        }
+        let end_offset = rows.last().map_or(0, |row| row.0 + 4);
+        program.end_sequence(end_offset);
+
+        let mut debug_line = DebugLine(EndianVec::new(gimli::LittleEndian));
+        let offset = program
+            .write(
+                &mut debug_line,
+                encoding,
+                &DebugLineStrOffsets::none(),
+                &DebugStrOffsets::none(),
+            )
+            .unwrap();
+
+        let debug_line = gimli::DebugLine::new(debug_line.0.slice(), gimli::LittleEndian);
+        let program = debug_line
+            .program(offset, encoding.address_size, None, None)
+            .unwrap();
+
+        let mut rows = program.rows();
+        let mut line_rows = Vec::new();
+        while let Some((_, row)) = rows.next_row().unwrap() {
+            if !row.end_sequence() {
+                line_rows.push(*row);
+            }
+        }
+        line_rows
+    }
+
+    #[test]
+    fn basic_block_starts_halt_location() {
+        let rows = line_rows(&[
+            (0, 10, true, false),
+            (4, 10, false, false),
+            (8, 11, false, true),
+            (12, 11, false, false),
+        ]);
+        assert_eq!(rows.len(), 4);
+
+        let instruction_types: Vec<_> = rows
+            .iter()
+            .map(|row| (row.address(), instruction_type(true, row)))
+            .collect();
+
+        assert!(matches!(
+            instruction_types.as_slice(),
+            [
+                (0x1000, InstructionType::HaltLocation),
+                (0x1004, InstructionType::Unspecified),
+                (0x1008, InstructionType::HaltLocation),
+                (0x100c, InstructionType::Unspecified),
+            ]
+        ));
+
+        // Inside the prologue, the basic block marker is ignored.
+        assert!(matches!(
+            instruction_type(false, &rows[2]),
+            InstructionType::Prologue
+        ));
+    }
+
+    #[test]
+    fn epilogue_begin_is_halt_location() {
+        // The epilogue starts at a row which is not a statement.
+        let rows = line_rows_with_epilogue(&[
+            (0, 10, true, false, false),
+            (4, 11, false, false, true),
+            (8, 11, false, false, false),
+        ]);
+
+        let instruction_types: Vec<_> = rows
+            .iter()
+            .map(|row| (row.address(), instruction_type(true, row)))
+            .collect();
+
+        assert!(matches!(
+            instruction_types.as_slice(),
+            [
+                (0x1000, InstructionType::HaltLocation),
+                (0x1004, InstructionType::HaltLocation),
+                (0x1008, InstructionType::Unspecified),
+            ]
+        ));
+
+        // Inside the prologue, the epilogue marker is ignored.
+        assert!(matches!(
+            instruction_type(false, &rows[1]),
+            InstructionType::Prologue
+        ));
+    }
+
+    #[test]
+    fn duplicate_rows_are_merged() {
+        // The second row only repeats the first one, but is a statement, and the third one
+        // begins the epilogue.
+        let rows = line_rows_with_epilogue(&[
+            (0, 10, false, false, false),
+            (0, 10, true, false, false),
+            (0, 10, false, false, true),
+            (4, 11, true, false, false),
+        ]);
+        assert_eq!(rows.len(), 4);
+
+        let debug_info = DebugInfo::from_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/inlined-functions"
+        ))
+        .unwrap();
+        let mut sequence = InstructionSequence {
+            address_range: 0x1000..0x1008,
+            instructions: Vec::new(),
+            debug_info: &debug_info,
+            program_unit: &debug_info.unit_infos[0],
+        };
+
+        let mut previous_row = None;
+        for row in &rows {
+            sequence.add(true, row, previous_row);
+            previous_row = Some(row);
+        }
+
+        let instruction_types: Vec<_> = sequence
+            .instructions
+            .iter()
+            .map(|instruction| {
+                (
+                    instruction.address,
+                    instruction.instruction_type,
+                    instruction.epilogue_begin,
+                )
+            })
+            .collect();
+
+        assert!(matches!(
+            instruction_types.as_slice(),
+            [
+                (0x1000, InstructionType::HaltLocation, true),
+                (0x1004, InstructionType::HaltLocation, false),
+            ]
+        ));
+    }
+
+    #[test]
+    fn line_zero_at_start_of_sequence() {
+        // The first statement is on line 0, without a previous row to inherit the line from.
+        let rows = line_rows(&[
+            (0, 0, true, false),
+            (4, 10, true, false),
+            (8, 0, false, false),
+            (12, 11, true, false),
+        ]);
+
+        let mut previous_row = None;
+        let instructions: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                let location = instruction_location(true, row, previous_row);
+                previous_row = Some(row);
+                location
+            })
+            .collect();
+
+        assert_eq!(instructions[0].instruction_type, InstructionType::Unknown);
+        assert_eq!(
+            instructions[1].instruction_type,
+            InstructionType::HaltLocation
+        );
+        // Line 0 after a statement inherits the line of the statement.
+        assert_eq!(instructions[2].line, NonZeroU64::new(10));
+        assert_eq!(
+            instructions[2].instruction_type,
+            InstructionType::Unspecified
+        );
+
+        // No breakpoint is offered on the synthetic instruction.
+        assert!(halt_location_at_or_before(&instructions, 0x1000).is_none());
+
+        let blocks = statement_blocks(&(0x1000..0x1010), &instructions, &[]);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].address_range, 0x1004..=0x100b);
+    }
+
+    /// Evaluate the prologue heuristic over `rows`, like `InstructionSequence::from_line_sequence`,
+    /// and return the address of the first row after the prologue.
+    fn prologue_end_address(
+        program_language: gimli::DwLang,
+        rows: &[gimli::LineRow],
+    ) -> Option<u64> {
+        let mut previous_row = None;
+        for row in rows {
+            if is_prologue_complete(program_language, row, previous_row) {
+                return Some(row.address());
+            }
+            previous_row = Some(row);
+        }
+        None
+    }
+
+    #[test]
+    fn cpp_prologue_without_prologue_end() {
+        // A function on line 10, with a prologue spanning two statements, and a body on line 11.
+        let rows = line_rows(&[
+            (0, 10, true, false),
+            (4, 10, true, false),
+            (8, 11, true, false),
+            (12, 11, false, false),
+        ]);
+
+        assert_eq!(
+            prologue_end_address(gimli::DW_LANG_C_plus_plus_14, &rows),
+            Some(0x1008)
+        );
+        assert_eq!(
+            prologue_end_address(gimli::DW_LANG_Swift, &rows),
+            Some(0x1008)
+        );
+
+        // Rust always sets `DW_LNS_set_prologue_end`, so the heuristic is not applied.
+        assert_eq!(prologue_end_address(gimli::DW_LANG_Rust, &rows), None);
+    }
+
+    #[test]
+    fn decode_branches() {
+        // b.n, bne.n, bx lr, blx r3, cbz r0, pop {r4, pc}
+        for opcode in [0xE7FE, 0xD1FC, 0x4770, 0x4798, 0xB108, 0xBD10] {
+            assert!(is_thumb16_branch(opcode), "{opcode:#06x}");
+        }
+        // movs r0, #1, udf #0, svc #0, push {r4, lr}
+        for opcode in [0x2001, 0xDE00, 0xDF00, 0xB510] {
+            assert!(!is_thumb16_branch(opcode), "{opcode:#06x}");
+        }
+
+        // bl, b.w, beq.w
+        assert!(is_thumb32_branch(0xF000, 0xF800));
+        assert!(is_thumb32_branch(0xF000, 0xB800));
+        assert!(is_thumb32_branch(0xF000, 0x8000));
+        // dsb sy, mov.w r0, #0
+        assert!(!is_thumb32_branch(0xF3BF, 0x8F4F));
+        assert!(!is_thumb32_branch(0xF04F, 0x0000));
+
+        // bl, bx lr
+        assert!(is_a32_branch(0xEB00_0000));
+        assert!(is_a32_branch(0xE12F_FF1E));
+        // mov r0, #0
+        assert!(!is_a32_branch(0xE3A0_0000));
+    }
+
+    #[test]
+    fn branch_successor_starts_block() {
+        let unspecified = |address| InstructionLocation {
+            instruction_type: InstructionType::Unspecified,
+            ..location(address, 1, 10)
+        };
+        let mut instructions = [
+            location(0x1000, 1, 10),
+            unspecified(0x1004),
+            unspecified(0x1008),
+            unspecified(0x100c),
+        ];
+
+        let mut memory = MockMemory::new();
+        memory.add_range(
+            0x1000,
+            vec![
+                0x00, 0xF0, 0x00, 0xF8, // bl
+                0x01, 0x20, 0x02, 0x20, // movs r0, #1; movs r0, #2
+                0x70, 0x47, 0x00, 0xBF, // bx lr; nop
+                0x00, 0xBF, 0x00, 0xBF, // nop; nop
+            ],
+        );
+
+        mark_branch_successors(
+            &(0x1000..0x1010),
+            &mut instructions,
+            &mut memory,
+            InstructionSet::Thumb2,
+        );
+
+        // The instruction after the call starts a new block, but is not a halt location.
+        assert!(instructions[1].branch_successor);
+        assert_eq!(
+            instructions[1].instruction_type,
+            InstructionType::Unspecified
+        );
+        // The location at 0x1004 does not start with a branch.
+        assert!(!instructions[2].branch_successor);
+        // The branch is followed by a `nop` that is not covered by the next location.
+        assert!(!instructions[3].branch_successor);
+
+        let blocks = statement_blocks(&(0x1000..0x1010), &instructions, &[]);
+        assert_eq!(
+            blocks,
+            [
+                StatementBlock {
+                    address_range: 0x1000..=0x1003,
+                    inlined: false,
+                    stepped_from: None,
+                    steps_to: Some(0x1004),
+                },
+                StatementBlock {
+                    address_range: 0x1004..=0x100f,
+                    inlined: false,
+                    stepped_from: Some(0x1000),
+                    steps_to: None,
+                },
+            ]
+        );
+
+        // Other instruction sets are left unchanged.
+        let mut instructions = [location(0x1000, 1, 10), unspecified(0x1004)];
+        mark_branch_successors(
+            &(0x1000..0x1008),
+            &mut instructions,
+            &mut memory,
+            InstructionSet::RV32,
+        );
+        assert!(!instructions[1].branch_successor);
+    }
+
+    #[test]
+    fn statement_blocks_are_linked_in_address_order() {
+        let instructions = [
+            InstructionLocation {
+                instruction_type: InstructionType::Prologue,
+                ..location(0x1000, 1, 10)
+            },
+            location(0x1004, 1, 11),
+            InstructionLocation {
+                instruction_type: InstructionType::Unspecified,
+                ..location(0x1008, 1, 11)
+            },
+            location(0x100c, 1, 12),
+            // Inlined function from another file, starting at the same address.
+            location(0x100c, 2, 40),
+        ];
+
+        let blocks = statement_blocks(&(0x1000..0x1014), &instructions, &[]);
+
+        assert_eq!(
+            blocks,
+            [
+                StatementBlock {
+                    address_range: 0x1004..=0x100b,
+                    inlined: false,
+                    stepped_from: None,
+                    steps_to: Some(0x100c),
+                },
+                StatementBlock {
+                    address_range: 0x100c..=0x1013,
+                    inlined: false,
+                    stepped_from: Some(0x1004),
+                    steps_to: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn statement_blocks_are_never_empty() {
+        // A degenerate sequence, which only consists of its end marker.
+        assert!(statement_blocks(&(0x1000..0x1000), &[location(0x1000, 1, 10)], &[]).is_empty());
+
+        // A halt location at the end of the sequence does not start a block.
+        let blocks = statement_blocks(
+            &(0x1000..0x1008),
+            &[location(0x1000, 1, 10), location(0x1008, 1, 11)],
+            &[],
+        );
+
+        assert_eq!(
+            blocks,
+            [StatementBlock {
+                address_range: 0x1000..=0x1007,
+                inlined: false,
+                stepped_from: None,
+                steps_to: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn statement_blocks_link_epilogue_to_preceding_statement() {
+        let epilogue = |address, line| InstructionLocation {
+            epilogue_begin: true,
+            ..location(address, 1, line)
+        };
+        let instructions = [
+            location(0x1000, 1, 10),
+            // Early return
+            location(0x1004, 1, 12),
+            epilogue(0x1008, 13),
+            // Reached by a branch from line 10, placed after the early return
+            location(0x100c, 1, 11),
+            epilogue(0x1010, 15),
+        ];
+
+        let blocks = statement_blocks(&(0x1000..0x1014), &instructions, &[]);
+
+        assert_eq!(
+            blocks,
+            [
+                StatementBlock {
+                    address_range: 0x1000..=0x1003,
+                    inlined: false,
+                    stepped_from: None,
+                    steps_to: Some(0x1004),
+                },
+                StatementBlock {
+                    address_range: 0x1004..=0x1007,
+                    inlined: false,
+                    stepped_from: Some(0x1000),
+                    steps_to: Some(0x1008),
+                },
+                StatementBlock {
+                    address_range: 0x1008..=0x100b,
+                    inlined: false,
+                    stepped_from: Some(0x1004),
+                    steps_to: None,
+                },
+                StatementBlock {
+                    address_range: 0x100c..=0x100f,
+                    inlined: false,
+                    stepped_from: Some(0x1000),
+                    steps_to: Some(0x1010),
+                },
+                StatementBlock {
+                    address_range: 0x1010..=0x1013,
+                    inlined: false,
+                    stepped_from: Some(0x100c),
+                    steps_to: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn statement_blocks_link_epilogue_within_function_range() {
+        let epilogue = |address, line| InstructionLocation {
+            epilogue_begin: true,
+            ..location(address, 1, line)
+        };
+        let instructions = [
+            // A function at 0x1000..0x1008 ...
+            location(0x1000, 1, 10),
+            epilogue(0x1004, 11),
+            // ... followed by another function in the same file, after its epilogue.
+            location(0x1008, 1, 20),
+            epilogue(0x100c, 21),
+            // The second function continues after its epilogue.
+            location(0x1010, 1, 22),
+        ];
+
+        let blocks = statement_blocks(&(0x1000..0x1014), &instructions, &[0x1008]);
+
+        // The statement at 0x1008 follows an epilogue, but is not linked to the statement at
+        // line 10, which is in the other function.
+        assert_eq!(blocks[2].address_range, 0x1008..=0x100b);
+        assert_eq!(blocks[2].stepped_from, None);
+
+        // The statement at 0x1010 is linked to the closest statement before it in its function.
+        assert_eq!(blocks[4].address_range, 0x1010..=0x1013);
+        assert_eq!(blocks[4].stepped_from, Some(0x1008));
+    }
+
+    #[test]
+    fn statement_blocks_break_at_function_range_boundaries() {
+        let instructions = [
+            location(0x1000, 1, 10),
+            // The function continues in a discontiguous range at 0x1008, which has no halt
+            // location at its start.
+            location(0x100c, 1, 11),
+            location(0x1010, 1, 12),
+        ];
+
+        let blocks = statement_blocks(&(0x1000..0x1014), &instructions, &[0x1008]);
+
+        // The instructions at 0x1008..0x100c are not part of any block.
+        assert_eq!(
+            blocks,
+            [
+                StatementBlock {
+                    address_range: 0x1000..=0x1007,
+                    inlined: false,
+                    stepped_from: None,
+                    steps_to: None,
+                },
+                StatementBlock {
+                    address_range: 0x100c..=0x100f,
+                    inlined: false,
+                    stepped_from: None,
+                    steps_to: Some(0x1010),
+                },
+                StatementBlock {
+                    address_range: 0x1010..=0x1013,
+                    inlined: false,
+                    stepped_from: Some(0x100c),
+                    steps_to: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn instruction_position_matches_linear_scan() {
+        // Instructions with gaps between them, and several locations at some addresses.
+        let instructions: Vec<_> = (0..1000u64)
+            .map(|index| location(0x1000 + (index / 3) * 4 + (index % 3 / 2) * 2, 1, index))
+            .collect();
+
+        for address in 0xff0..0x1600 {
+            let linear = instructions
+                .iter()
+                .rposition(|instruction_location| instruction_location.address <= address);
+
+            assert_eq!(
+                instruction_position(&instructions, address),
+                linear,
+                "address {address:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_halt_on_same_line() {
+        let instructions = vec![
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                ..location(0x1000, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                instruction_type: InstructionType::Unspecified,
+                ..location(0x1004, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(20),
+                ..location(0x1008, 1, 10)
+            },
+            InstructionLocation {
+                column: ColumnType::Column(5),
+                ..location(0x100c, 1, 11)
+            },
+        ];
+
+        // The statement in the next column of the line.
+        let matched = next_halt_on_line_after(&instructions, 0x1000).unwrap();
+        assert_eq!(matched.address, 0x1008);
+        let matched = next_halt_on_line_after(&instructions, 0x1004).unwrap();
+        assert_eq!(matched.address, 0x1008);
+
+        // The last statement on the line, the next line is not considered.
+        assert!(next_halt_on_line_after(&instructions, 0x1008).is_none());
+        assert!(next_halt_on_line_after(&instructions, 0x100c).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use probe_rs_debug::{ColumnType, SourceLocation, debug_info::DebugInfo};
+use probe_rs_debug::{ColumnType, DebugError, SourceLocation, debug_info::DebugInfo};
 use std::path::PathBuf;
 use typed_path::{TypedPath, UnixPathBuf};
 
@@ -113,6 +113,26 @@ fn find_non_existing_unit_by_path() {
     );
 }
 
+#[test]
+fn breakpoint_past_last_statement_reports_matched_unit() {
+    let path = UnixPathBuf::from("/Users/jacknoppe/dev/probe-rs-debugger-test/src/main.rs")
+        .to_typed_path_buf();
+
+    let debug_info = DebugInfo::from_file("tests/probe-rs-debugger-test").unwrap();
+
+    let Err(DebugError::WarnAndContinue { message }) =
+        debug_info.get_breakpoint_location(path.to_path(), 100_000, None)
+    else {
+        panic!("There is no statement on line 100000");
+    };
+
+    assert!(message.contains("the file matched in unit"), "{message}");
+    assert!(
+        message.contains("line 100000 is past its last statement"),
+        "{message}"
+    );
+}
+
 #[test]
 fn regression_pr2324() {
     let path = "C:\\_Hobby\\probe-rs-test-c-firmware/Atmel/hpl/core/hpl_init.c";
@@ -891,6 +891,7 @@ impl<P: ProtocolAdapter> DebugAdapter<P> {
                         Ok(VerifiedBreakpoint {
                             address,
                             source_location,
+                            ..
                         }) => created_breakpoints.push(Breakpoint {
                             column: source_location.column.map(|col| match col {
                                 ColumnType::LeftEdge => 0_i64,
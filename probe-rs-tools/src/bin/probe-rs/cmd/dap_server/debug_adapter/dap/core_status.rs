@@ -34,6 +34,15 @@ impl DapStatus for CoreStatus {
                     "data breakpoint",
                     "Core halted due to a watchpoint or data breakpoint".to_string(),
                 ),
+                HaltReason::WatchpointMatch {
+                    comparator,
+                    address,
+                } => (
+                    "data breakpoint",
+                    format!(
+                        "Core halted due to watchpoint {comparator} on address {address:#010x}"
+                    ),
+                ),
                 HaltReason::Step => (
                     "step",
                     format!(
@@ -65,7 +74,7 @@ impl DapStatus for CoreStatus {
                     "Core halted: unrecognized cause".to_string(),
                 ),
             },
-            CoreStatus::Unknown => ("unknown", "Core status cannot be determined".to_string()),
+            _ => ("unknown", "Core status cannot be determined".to_string()),
         }
     }
 }
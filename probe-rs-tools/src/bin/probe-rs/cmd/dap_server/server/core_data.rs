@@ -90,7 +90,12 @@ impl CoreHandle<'_> {
             return Ok(CoreStatus::Unknown);
         }
 
-        let status = match self.core.status() {
+        // A halt at a conditional breakpoint whose condition is not met resumes the core.
+        let status = match self
+            .core
+            .evaluate_conditional_breakpoint()
+            .and_then(|_| self.core.status())
+        {
             Ok(status) => {
                 if status == self.core_data.last_known_status {
                     return Ok(status);
@@ -141,7 +146,8 @@ impl CoreHandle<'_> {
                 debug_adapter.show_message(MessageSeverity::Error, &description);
                 return Err(DebuggerError::Other(anyhow!(description)));
             }
-            CoreStatus::Unknown => {
+            // `CoreStatus::Unknown`, or a status this debugger does not know about.
+            _ => {
                 let error =
                     DebuggerError::Other(anyhow!("Unknown Device status reveived from Probe-rs"));
                 debug_adapter.show_error_message(&error)?;
@@ -361,6 +367,7 @@ impl CoreHandle<'_> {
         let VerifiedBreakpoint {
                  address,
                  source_location,
+                 inline_frames,
              } = self.core_data
             .debug_info
             .get_breakpoint_location(
@@ -380,6 +387,7 @@ impl CoreHandle<'_> {
         Ok(VerifiedBreakpoint {
             address,
             source_location,
+            inline_frames,
         })
     }
 
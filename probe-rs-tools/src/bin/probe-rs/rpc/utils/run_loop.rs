@@ -98,6 +98,8 @@ impl RunLoop {
             // this is important so we do one last poll after halt, so we flush all messages
             // the core printed before halting, such as a panic message.
             let mut return_reason = None;
+            // A halt at a conditional breakpoint whose condition is not met resumes the core.
+            core.evaluate_conditional_breakpoint()?;
             match core.status()? {
                 probe_rs::CoreStatus::Halted(reason) => match predicate(reason, core) {
                     Ok(Some(r)) => return_reason = Some(Ok(ReturnReason::Predicate(r))),
@@ -109,13 +111,11 @@ impl RunLoop {
                         core.run()?;
                     }
                 },
-                probe_rs::CoreStatus::Running
-                | probe_rs::CoreStatus::Sleeping
-                | probe_rs::CoreStatus::Unknown => {
-                    // Carry on
-                }
-
                 probe_rs::CoreStatus::LockedUp => anyhow::bail!("The core is locked up."),
+
+                _ => {
+                    // Running, sleeping or unknown. Carry on
+                }
             }
 
             let poller_result = poller.poll(core);
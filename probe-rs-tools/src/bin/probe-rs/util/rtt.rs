@@ -213,7 +213,7 @@ pub enum RttSymbolError {
     /// RTT symbol not found in the ELF file.
     RttSymbolNotFound,
     /// Failed to parse the firmware as an ELF file.
-    Goblin(#[source] goblin::error::Error),
+    Elf(#[source] probe_rs_debug::DebugError),
 }
 
 /// Once an active connection with the Target RTT control block has been established, we configure
@@ -303,15 +303,7 @@ impl RttConnection {
 }
 
 pub fn get_rtt_symbol_from_bytes(buffer: &[u8]) -> Result<u64, RttSymbolError> {
-    match goblin::elf::Elf::parse(buffer) {
-        Ok(binary) => {
-            for sym in &binary.syms {
-                if binary.strtab.get_at(sym.st_name) == Some("_SEGGER_RTT") {
-                    return Ok(sym.st_value);
-                }
-            }
-            Err(RttSymbolError::RttSymbolNotFound)
-        }
-        Err(err) => Err(RttSymbolError::Goblin(err)),
-    }
+    probe_rs_debug::symbol_address_in_elf(buffer, probe_rs_debug::RTT_CONTROL_BLOCK_SYMBOL)
+        .map_err(RttSymbolError::Elf)?
+        .ok_or(RttSymbolError::RttSymbolNotFound)
 }
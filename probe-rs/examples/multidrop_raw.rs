@@ -44,22 +44,12 @@ fn main() -> Result<()> {
         bank: Some(0x2),
     };
 
-    println!(
-        "core0 DPIDR:    {:08x}",
-        iface.read_raw_dp_register(core0, DPIDR)?
-    );
-    println!(
-        "core0 TARGETID: {:08x}",
-        iface.read_raw_dp_register(core0, TARGETID)?
-    );
-    println!(
-        "core1 DPIDR:    {:08x}",
-        iface.read_raw_dp_register(core1, DPIDR)?
-    );
-    println!(
-        "core1 TARGETID: {:08x}",
-        iface.read_raw_dp_register(core1, TARGETID)?
-    );
+    for (name, dp) in [("core0", core0), ("core1", core1)] {
+        let values = iface.read_raw_dp_registers(dp, &[DPIDR, TARGETID])?;
+
+        println!("{name} DPIDR:    {:08x}", values[0]);
+        println!("{name} TARGETID: {:08x}", values[1]);
+    }
 
     Ok(())
 }
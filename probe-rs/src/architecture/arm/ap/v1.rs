@@ -1,5 +1,7 @@
 //! Types and functions for interacting with v1 access ports.
 
+use std::time::Instant;
+
 use crate::architecture::arm::{
     DapAccess, FullyQualifiedApAddress,
     ap::{ApAccess, ApRegister, GenericAp, IDR},
@@ -16,7 +18,9 @@ pub(crate) fn valid_access_ports<DP>(
 where
     DP: DapAccess,
 {
-    valid_access_ports_allowlist(debug_port, dp, 0..=255)
+    let (access_ports, _) = valid_access_ports_with_deadline(debug_port, dp, None);
+
+    access_ports
 }
 
 /// Determine if an AP exists with the given AP address.
@@ -52,26 +56,35 @@ where
     }
 }
 
-/// Return a Vec of all valid access ports found that the target connected to the debug_probe.
-/// The search is limited to `allowed_aps`.
+/// Like [`valid_access_ports`], but stops probing further access ports once `deadline` has
+/// passed. Without a deadline, all access ports are probed.
 ///
-/// Can fail silently under the hood testing an ap that doesn't exist and would require cleanup.
-#[tracing::instrument(skip(debug_port, allowed_aps))]
-pub(crate) fn valid_access_ports_allowlist<DP>(
+/// Returns the access ports found so far, and whether the search was cut short by the deadline.
+#[tracing::instrument(skip(debug_port))]
+pub(crate) fn valid_access_ports_with_deadline<DP>(
     debug_port: &mut DP,
     dp: DpAddress,
-    allowed_aps: impl IntoIterator<Item = u8>,
-) -> Vec<FullyQualifiedApAddress>
+    deadline: Option<Instant>,
+) -> (Vec<FullyQualifiedApAddress>, bool)
 where
     DP: DapAccess,
 {
-    allowed_aps
-        .into_iter()
-        .map_while(|ap| {
-            let ap = FullyQualifiedApAddress::v1_with_dp(dp, ap);
-            access_port_is_valid(debug_port, &ap).map(|_| ap)
-        })
-        .collect()
+    let mut access_ports = vec![];
+
+    for ap in 0..=255 {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            tracing::debug!("Deadline passed, stopping at AP {ap}");
+            return (access_ports, true);
+        }
+
+        let ap = FullyQualifiedApAddress::v1_with_dp(dp, ap);
+        if access_port_is_valid(debug_port, &ap).is_none() {
+            break;
+        }
+        access_ports.push(ap);
+    }
+
+    (access_ports, false)
 }
 
 /// Tries to find the first AP with the given idr value, returns `None` if there isn't any
@@ -90,3 +103,55 @@ where
             }
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test::FakeArmProbe;
+
+    /// A debug port with all 256 APs, which takes `delay` to read a register of any of them.
+    fn slow_dap(delay: Duration) -> FakeArmProbe {
+        let mut dap = FakeArmProbe::new();
+        dap.set_ap_register_delay(delay);
+        for ap in 0..=255 {
+            // An AMBA AHB3 memory AP designed by ARM.
+            dap.set_ap_register(
+                FullyQualifiedApAddress::v1_with_default_dp(ap),
+                IDR::ADDRESS,
+                0x2477_0011,
+            );
+        }
+        dap
+    }
+
+    #[test]
+    fn access_port_scan_stops_at_deadline() {
+        let mut dap = slow_dap(Duration::from_millis(10));
+        let deadline = Instant::now() + Duration::from_millis(25);
+
+        let (access_ports, truncated) =
+            valid_access_ports_with_deadline(&mut dap, DpAddress::Default, Some(deadline));
+
+        assert!(truncated);
+        assert!(!access_ports.is_empty());
+        assert!(access_ports.len() < 256);
+        assert_eq!(
+            access_ports[0],
+            FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 0)
+        );
+    }
+
+    #[test]
+    fn access_port_scan_without_deadline_is_complete() {
+        let mut dap = slow_dap(Duration::ZERO);
+        let deadline = Instant::now() + Duration::from_secs(60);
+
+        let (access_ports, truncated) =
+            valid_access_ports_with_deadline(&mut dap, DpAddress::Default, Some(deadline));
+
+        assert!(!truncated);
+        assert_eq!(access_ports.len(), 256);
+    }
+}
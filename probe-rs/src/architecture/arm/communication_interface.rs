@@ -2,21 +2,22 @@ use crate::{
     CoreStatus, Error,
     architecture::arm::{
         ApAddress, ArmError, DapAccess, FullyQualifiedApAddress, RawDapAccess, RegisterAddress,
-        SwoAccess, SwoConfig, ap,
+        SwjSequence, SwoAccess, SwoConfig, SwoMode, SwoStatus, ap,
         dp::{Ctrl, DPIDR, DebugPortId, DebugPortVersion, DpAccess},
         dp::{DpAddress, DpRegisterAddress, Select1, SelectV1, SelectV3},
-        memory::{ADIMemoryInterface, ArmMemoryInterface, Component},
+        memory::{ADIMemoryInterface, ArmMemoryInterface, Component, PeripheralID},
         sequences::{ArmDebugSequence, DefaultArmSequence},
     },
+    config::Registry,
     probe::{DebugProbe, DebugProbeError, Probe},
 };
 use jep106::JEP106Code;
 
 use std::{
-    collections::{BTreeSet, HashMap, hash_map},
+    collections::{BTreeMap, BTreeSet, HashMap, hash_map},
     fmt::Debug,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 /// An error in the communication with an access port or
@@ -40,6 +41,36 @@ pub enum DapError {
     IncorrectParity,
 }
 
+/// How single register accesses are retried when the target responds with WAIT.
+///
+/// A WAIT response means that the target could not accept the access yet, e.g. because a
+/// slow AP is still busy, and the host is expected to try again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitRetryPolicy {
+    /// The maximum number of attempts for an access, including the first one.
+    pub attempts: usize,
+    /// The time to wait between two attempts.
+    pub delay: Duration,
+}
+
+impl Default for WaitRetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay: Duration::from_millis(1),
+        }
+    }
+}
+
+/// The result of [`ArmProbeInterface::access_ports_with_deadline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessPortScan {
+    /// The access ports which were found.
+    pub access_ports: BTreeSet<FullyQualifiedApAddress>,
+    /// Whether the deadline passed before all access ports were probed.
+    pub truncated: bool,
+}
+
 /// To be implemented by debug probe drivers that support debugging ARM cores.
 pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
     /// Reinitialize the communication interface (in place).
@@ -56,6 +87,19 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
     /// If you're a chip-specific debug sequence, you're expected to handle this yourself.
     fn reinitialize(&mut self) -> Result<(), ArmError>;
 
+    /// Reinitialize the communication interface, retrying up to `attempts` times.
+    ///
+    /// This is useful for unreliable connections, where the first attempt of setting
+    /// up the debug port may fail. Between the attempts, `delay` is waited. At least one
+    /// attempt is made, and if all of them fail, the error of the last attempt is returned.
+    fn reinitialize_with_retries(
+        &mut self,
+        attempts: usize,
+        delay: Duration,
+    ) -> Result<(), ArmError> {
+        retry(attempts, delay, || self.reinitialize())
+    }
+
     /// Returns a vector of all the access ports the current debug port has.
     ///
     /// If the target device has multiple debug ports, this will switch the active debug port
@@ -65,12 +109,71 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
         dp: DpAddress,
     ) -> Result<BTreeSet<FullyQualifiedApAddress>, ArmError>;
 
+    /// Like [`ArmProbeInterface::access_ports`], but stops probing further access ports once
+    /// `deadline` has passed, so that discovery on slow targets does not stall interactive tools.
+    ///
+    /// The deadline only bounds probing the access ports of an ADIv5 (APv1) debug port one by
+    /// one. Selecting and initializing the debug port, and walking the ROM table to find the
+    /// APv2 access ports of a DPv3 debug port, are not interrupted, so this can still take
+    /// longer than the deadline.
+    ///
+    /// The returned [`AccessPortScan`] contains the access ports found so far, and whether the
+    /// enumeration was cut short. Interfaces which can not interrupt the enumeration return
+    /// the complete result of [`ArmProbeInterface::access_ports`].
+    fn access_ports_with_deadline(
+        &mut self,
+        dp: DpAddress,
+        _deadline: Instant,
+    ) -> Result<AccessPortScan, ArmError> {
+        Ok(AccessPortScan {
+            access_ports: self.access_ports(dp)?,
+            truncated: false,
+        })
+    }
+
+    /// Returns the access ports of all the debug ports in `dps`.
+    ///
+    /// A debug port which can not be enumerated does not abort the scan, instead its error
+    /// is recorded in the returned map. After the scan, the previously selected debug port
+    /// is selected again, and an error is only returned if this fails.
+    fn all_access_ports(
+        &mut self,
+        dps: &[DpAddress],
+    ) -> Result<BTreeMap<DpAddress, Result<BTreeSet<FullyQualifiedApAddress>, ArmError>>, ArmError>
+    {
+        let original_dp = self.current_debug_port();
+
+        let access_ports = dps.iter().map(|&dp| (dp, self.access_ports(dp))).collect();
+
+        if self.current_debug_port() != original_dp {
+            // Accessing a register of the debug port selects it.
+            self.read_raw_dp_register(original_dp, DPIDR::ADDRESS)?;
+        }
+
+        Ok(access_ports)
+    }
+
     /// Closes the interface and returns back the generic probe it consumed.
     fn close(self: Box<Self>) -> Probe;
 
     /// Return the currently connected debug port.
     fn current_debug_port(&self) -> DpAddress;
 
+    /// Forget the cached value of the SELECT register of the debug port `dp`.
+    ///
+    /// The next register access which depends on SELECT will write it again. This is
+    /// required after the debug port was reset externally, e.g. by a line reset in a
+    /// debug sequence. Interfaces which don't cache SELECT don't have to do anything.
+    fn invalidate_select(&mut self, _dp: DpAddress) {}
+
+    /// Returns the version of the debug port `dp`, if it was already initialized.
+    ///
+    /// Unlike [`ArmProbeInterface::access_ports`], this never connects to the debug port,
+    /// and returns `None` if the version is not known yet.
+    fn debug_port_version(&self, _dp: DpAddress) -> Option<DebugPortVersion> {
+        None
+    }
+
     /// Returns a memory interface to access the target's memory.
     fn memory_interface(
         &mut self,
@@ -78,6 +181,39 @@ pub trait ArmProbeInterface: DapAccess + SwdSequence + SwoAccess + Send {
     ) -> Result<Box<dyn ArmMemoryInterface + '_>, ArmError>;
 }
 
+/// Call `f` until it succeeds, at most `attempts` times, waiting `delay` between the calls.
+fn retry<T>(
+    attempts: usize,
+    delay: Duration,
+    f: impl FnMut() -> Result<T, ArmError>,
+) -> Result<T, ArmError> {
+    retry_if(attempts, delay, |_| true, f)
+}
+
+/// Call `f` until it succeeds, at most `attempts` times, waiting `delay` between the calls.
+///
+/// Only errors for which `should_retry` returns `true` are retried, any other error is
+/// returned immediately.
+fn retry_if<T>(
+    attempts: usize,
+    delay: Duration,
+    should_retry: impl Fn(&ArmError) -> bool,
+    mut f: impl FnMut() -> Result<T, ArmError>,
+) -> Result<T, ArmError> {
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt >= attempts || !should_retry(&error) => return Err(error),
+            Err(error) => {
+                tracing::debug!("Attempt {} of {} failed: {}", attempt, attempts, error);
+                attempt += 1;
+                std::thread::sleep(delay);
+            }
+        }
+    }
+}
+
 /// Read chip information from the ROM tables
 pub fn read_chip_info_from_rom_table(
     probe: &mut dyn ArmProbeInterface,
@@ -102,11 +238,45 @@ pub fn read_chip_info_from_rom_table(
     Ok(None)
 }
 
+/// Read the peripheral IDs of all CoreSight components in the ROM tables of the access ports
+/// of `dp`, including the ROM tables themselves.
+///
+/// Unlike [`read_chip_info_from_rom_table`], this does not stop at the first ROM table with a
+/// JEP106 code, so tools can match the raw IDs of unknown targets against their own databases.
+/// Access ports without a memory interface are skipped.
+pub fn read_all_peripheral_ids(
+    probe: &mut dyn ArmProbeInterface,
+    dp: DpAddress,
+) -> Result<Vec<PeripheralID>, ArmError> {
+    let mut peripheral_ids = vec![];
+
+    for ap in probe.access_ports(dp)? {
+        if let Ok(mut memory) = probe.memory_interface(&ap) {
+            let base_address = memory.base_address()?;
+            let component = Component::try_parse(&mut *memory, base_address)?;
+
+            peripheral_ids.extend(
+                component
+                    .walk()
+                    .into_iter()
+                    .map(|entry| entry.component.id().peripheral_id().clone()),
+            );
+        }
+    }
+
+    Ok(peripheral_ids)
+}
+
 // TODO: Rename trait!
 pub trait SwdSequence {
     /// Corresponds to the DAP_SWJ_Sequence function from the ARM Debug sequences
     fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError>;
 
+    /// Send one of the sequences defined by the ARM Debug Interface, see [`SwjSequence`].
+    fn send_swj_sequence(&mut self, sequence: SwjSequence) -> Result<(), DebugProbeError> {
+        self.swj_sequence(sequence.bit_len, sequence.bits)
+    }
+
     /// Corresponds to the DAP_SWJ_Pins function from the ARM Debug sequences
     fn swj_pins(
         &mut self,
@@ -134,6 +304,9 @@ pub trait UninitializedArmProbe: SwdSequence + Debug {
     fn close(self: Box<Self>) -> Probe;
 }
 
+/// The delay before retrying to connect to a debug port which failed to be selected.
+const DP_CONNECT_RETRY_DELAY: Duration = Duration::from_millis(5);
+
 pub trait ArmDebugState {
     fn disconnect(&mut self, _probe: &mut dyn DapProbe) {}
 }
@@ -151,6 +324,7 @@ pub struct Initialized {
     dps: HashMap<DpAddress, DpState>,
     use_overrun_detect: bool,
     sequence: Arc<dyn ArmDebugSequence>,
+    wait_retry: WaitRetryPolicy,
 }
 
 impl Initialized {
@@ -164,6 +338,7 @@ impl Initialized {
             dps: HashMap::new(),
             use_overrun_detect,
             sequence,
+            wait_retry: WaitRetryPolicy::default(),
         }
     }
 }
@@ -175,18 +350,26 @@ impl ArmDebugState for Initialized {
         let stop_span = tracing::debug_span!("debug_port_stop").entered();
 
         // Stop the current DP, which may not be one of the known ones (i.e. RP2040 rescue DP).
-        self.sequence.debug_port_stop(probe, self.current_dp).ok();
+        if let Err(e) = self.sequence.debug_port_stop(probe, self.current_dp) {
+            tracing::warn!("Failed to stop DP {:x?}: {}", self.current_dp, e);
+        }
 
         // Stop all intentionally-connected DPs.
         for dp in self.dps.keys().filter(|dp| **dp != self.current_dp) {
             // Try to select the debug port we want to shut down.
-            if self.sequence.debug_port_connect(probe, *dp).is_ok() {
-                self.sequence.debug_port_stop(probe, *dp).ok();
-            } else {
-                tracing::warn!("Failed to stop DP {:x?}", dp);
+            let stopped = self
+                .sequence
+                .debug_port_connect(probe, *dp)
+                .and_then(|_| self.sequence.debug_port_stop(probe, *dp));
+
+            if let Err(e) = stopped {
+                tracing::warn!("Failed to stop DP {:x?}: {}", dp, e);
             }
         }
-        probe.raw_flush().ok();
+
+        if let Err(e) = probe.raw_flush() {
+            tracing::warn!("Failed to flush probe while disconnecting: {}", e);
+        }
         drop(stop_span);
     }
 }
@@ -215,6 +398,9 @@ pub(crate) struct DpState {
     pub debug_port_version: DebugPortVersion,
 
     pub(crate) current_select: SelectCache,
+
+    /// Set when the SELECT register of the target may no longer match `current_select`.
+    pub(crate) select_dirty: bool,
 }
 
 impl DpState {
@@ -222,6 +408,23 @@ impl DpState {
         Self {
             debug_port_version: DebugPortVersion::Unsupported(0xFF),
             current_select: SelectCache::DPv1(SelectV1(0)),
+            select_dirty: false,
+        }
+    }
+
+    /// Force the next access which depends on SELECT to write it again.
+    ///
+    /// This has to be called whenever the debug port may have been reset behind our back,
+    /// e.g. by a line reset, as the cached value is stale afterwards.
+    pub(crate) fn invalidate_select(&mut self) {
+        self.select_dirty = true;
+    }
+
+    /// The version of the debug port, if it was already read from DPIDR.
+    pub(crate) fn known_version(&self) -> Option<DebugPortVersion> {
+        match self.debug_port_version {
+            DebugPortVersion::Unsupported(_) => None,
+            version => Some(version),
         }
     }
 }
@@ -305,6 +508,16 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
         self.state.current_dp
     }
 
+    fn invalidate_select(&mut self, dp: DpAddress) {
+        if let Some(dp_state) = self.state.dps.get_mut(&dp) {
+            dp_state.invalidate_select();
+        }
+    }
+
+    fn debug_port_version(&self, dp: DpAddress) -> Option<DebugPortVersion> {
+        self.state.dps.get(&dp).and_then(DpState::known_version)
+    }
+
     fn close(self: Box<Self>) -> Probe {
         ArmCommunicationInterface::close(*self)
     }
@@ -318,7 +531,35 @@ impl ArmProbeInterface for ArmCommunicationInterface<Initialized> {
                 Ok(ap::v1::valid_access_ports(self, dp).into_iter().collect())
             }
             DebugPortVersion::DPv3 => ap::v2::enumerate_access_ports(self, dp),
-            DebugPortVersion::Unsupported(_) => unreachable!(),
+            DebugPortVersion::Unsupported(version) => {
+                Err(ArmError::UnsupportedDebugPortVersion(version))
+            }
+        }
+    }
+
+    fn access_ports_with_deadline(
+        &mut self,
+        dp: DpAddress,
+        deadline: Instant,
+    ) -> Result<AccessPortScan, ArmError> {
+        match self.select_dp(dp).map(|state| state.debug_port_version)? {
+            DebugPortVersion::DPv0 | DebugPortVersion::DPv1 | DebugPortVersion::DPv2 => {
+                let (access_ports, truncated) =
+                    ap::v1::valid_access_ports_with_deadline(self, dp, Some(deadline));
+
+                Ok(AccessPortScan {
+                    access_ports: access_ports.into_iter().collect(),
+                    truncated,
+                })
+            }
+            // APv2 access ports are found in the ROM table, not by probing each index.
+            DebugPortVersion::DPv3 => Ok(AccessPortScan {
+                access_ports: ap::v2::enumerate_access_ports(self, dp)?,
+                truncated: false,
+            }),
+            DebugPortVersion::Unsupported(version) => {
+                Err(ArmError::UnsupportedDebugPortVersion(version))
+            }
         }
     }
 }
@@ -410,6 +651,58 @@ impl ArmCommunicationInterface<Initialized> {
         self.probe_mut().core_status_notification(state).ok();
     }
 
+    /// Returns whether overrun detection is requested for the debug ports.
+    pub fn overrun_detect(&self) -> bool {
+        self.state.use_overrun_detect
+    }
+
+    /// Enable or disable overrun detection on the currently selected debug port.
+    ///
+    /// Debug ports which are selected for the first time afterwards also use the new setting.
+    /// The CTRL/STAT register is only written if the setting changes.
+    pub fn set_overrun_detect(&mut self, enable: bool) -> Result<(), ArmError> {
+        self.state.use_overrun_detect = enable;
+
+        let dp = self.state.current_dp;
+        let mut ctrl_reg: Ctrl = self.read_dp_register(dp)?;
+        if ctrl_reg.orun_detect() != enable {
+            tracing::debug!("Setting orun_detect: {}", enable);
+            ctrl_reg.set_orun_detect(enable);
+            self.write_dp_register(dp, ctrl_reg)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns how register accesses are retried on WAIT responses.
+    pub fn wait_retry_policy(&self) -> WaitRetryPolicy {
+        self.state.wait_retry
+    }
+
+    /// Configure how register accesses are retried on WAIT responses.
+    ///
+    /// This applies to single DP and AP register accesses. Block transfers are not retried,
+    /// as they can not be resumed after a partial transfer.
+    pub fn set_wait_retry_policy(&mut self, policy: WaitRetryPolicy) {
+        self.state.wait_retry = policy;
+    }
+
+    /// Perform a register access on the probe, retrying it according to the
+    /// [`WaitRetryPolicy`] as long as the target responds with WAIT.
+    fn retry_on_wait<T>(
+        &mut self,
+        mut access: impl FnMut(&mut dyn DapProbe) -> Result<T, ArmError>,
+    ) -> Result<T, ArmError> {
+        let policy = self.state.wait_retry;
+
+        retry_if(
+            policy.attempts,
+            policy.delay,
+            |error| matches!(error, ArmError::Dap(DapError::WaitResponse)),
+            || access(self.probe_mut()),
+        )
+    }
+
     fn select_dp(&mut self, dp: DpAddress) -> Result<&mut DpState, ArmError> {
         let mut switched_dp = false;
 
@@ -422,12 +715,22 @@ impl ArmCommunicationInterface<Initialized> {
 
             self.probe_mut().raw_flush()?;
 
-            // Try to switch to the new DP.
-            if let Err(e) = sequence.debug_port_connect(&mut *self.probe_mut(), dp) {
+            // Try to switch to the new DP. Selecting a DP on a multidrop bus can fail
+            // transiently, so retry once before falling back to the more involved setup.
+            let connected = retry(2, DP_CONNECT_RETRY_DELAY, || {
+                sequence.debug_port_connect(&mut *self.probe_mut(), dp)
+            });
+
+            if let Err(e) = connected {
                 tracing::warn!("Failed to switch to DP {:x?}: {}", dp, e);
 
                 // Try the more involved debug_port_setup sequence, which also handles dormant mode.
                 sequence.debug_port_setup(&mut *self.probe_mut(), dp)?;
+
+                // The setup sequence resets the line, so the cached SELECT value is stale.
+                if let Some(dp_state) = self.state.dps.get_mut(&dp) {
+                    dp_state.invalidate_select();
+                }
             }
 
             self.state.current_dp = dp;
@@ -505,13 +808,17 @@ impl ArmCommunicationInterface<Initialized> {
 
         let bank = bank.unwrap_or(0);
 
-        if bank != dp_state.current_select.dp_bank_sel() {
+        if dp_state.select_dirty || bank != dp_state.current_select.dp_bank_sel() {
             dp_state.current_select.set_dp_bank_sel(bank);
 
             tracing::debug!("Changing DP_BANK_SEL to {:x?}", dp_state.current_select);
 
             match dp_state.current_select {
-                SelectCache::DPv1(select) => self.write_dp_register(dp, select)?,
+                SelectCache::DPv1(select) => {
+                    dp_state.select_dirty = false;
+                    self.write_dp_register(dp, select)?
+                }
+                // SELECT1 is only restored by the next AP access, so the cache stays dirty.
                 SelectCache::DPv3(select, _) => self.write_dp_register(dp, select)?,
             }
         }
@@ -539,12 +846,12 @@ impl ArmCommunicationInterface<Initialized> {
                 s.set_addr(((address >> 4) & 0xFFFF_FFFF) as u32);
                 s1.set_addr((address >> 32) as u32);
             }
-            _ => unreachable!(
-                "Did not expect to be called with {ap:x?}. This is a bug, please report it."
-            ),
+            _ => return Err(ArmError::InconsistentSelectState(ap.clone())),
         }
 
-        if previous_select != dp_state.current_select {
+        if dp_state.select_dirty || previous_select != dp_state.current_select {
+            dp_state.select_dirty = false;
+
             tracing::debug!("Changing SELECT to {:x?}", dp_state.current_select);
 
             match dp_state.current_select {
@@ -571,11 +878,25 @@ impl FlushableArmAccess for ArmCommunicationInterface<Initialized> {
 impl SwoAccess for ArmCommunicationInterface<Initialized> {
     fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
         match self.probe_mut().get_swo_interface_mut() {
+            Some(interface) if !interface.supports_swo_mode(config.mode()) => {
+                Err(ArmError::SwoModeNotSupported(config.mode()))
+            }
             Some(interface) => interface.enable_swo(config),
             None => Err(ArmError::ArchitectureRequired(&["ARMv7", "ARMv8"])),
         }
     }
 
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        match self
+            .probe
+            .as_deref()
+            .and_then(|probe| probe.get_swo_interface())
+        {
+            Some(interface) => interface.supports_swo_mode(mode),
+            None => false,
+        }
+    }
+
     fn disable_swo(&mut self) -> Result<(), ArmError> {
         match self.probe_mut().get_swo_interface_mut() {
             Some(interface) => interface.disable_swo(),
@@ -589,6 +910,13 @@ impl SwoAccess for ArmCommunicationInterface<Initialized> {
             None => Err(ArmError::ArchitectureRequired(&["ARMv7", "ARMv8"])),
         }
     }
+
+    fn read_swo_status(&mut self, timeout: Duration) -> Result<SwoStatus, ArmError> {
+        match self.probe_mut().get_swo_interface_mut() {
+            Some(interface) => interface.read_swo_status(timeout),
+            None => Err(ArmError::ArchitectureRequired(&["ARMv7", "ARMv8"])),
+        }
+    }
 }
 
 impl DapAccess for ArmCommunicationInterface<Initialized> {
@@ -598,7 +926,7 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
         address: DpRegisterAddress,
     ) -> Result<u32, ArmError> {
         self.select_dp_and_dp_bank(dp, &address)?;
-        let result = self.probe_mut().raw_read_register(address.into())?;
+        let result = self.retry_on_wait(|probe| probe.raw_read_register(address.into()))?;
         Ok(result)
     }
 
@@ -609,7 +937,7 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
         value: u32,
     ) -> Result<(), ArmError> {
         self.select_dp_and_dp_bank(dp, &address)?;
-        self.probe_mut().raw_write_register(address.into(), value)?;
+        self.retry_on_wait(|probe| probe.raw_write_register(address.into(), value))?;
         Ok(())
     }
 
@@ -621,8 +949,10 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
         self.select_ap_and_ap_bank(ap, address)?;
 
         let result = self
-            .probe_mut()
-            .raw_read_register(RegisterAddress::ApRegister((address & 0xFF) as u8))?;
+            .retry_on_wait(|probe| {
+                probe.raw_read_register(RegisterAddress::ApRegister((address & 0xFF) as u8))
+            })
+            .map_err(|error| with_ap_register_context(error, ap, address))?;
 
         Ok(result)
     }
@@ -636,7 +966,8 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
         self.select_ap_and_ap_bank(ap, address)?;
 
         self.probe_mut()
-            .raw_read_block(RegisterAddress::ApRegister((address & 0xFF) as u8), values)?;
+            .raw_read_block(RegisterAddress::ApRegister((address & 0xFF) as u8), values)
+            .map_err(|error| with_ap_register_context(error, ap, address))?;
         Ok(())
     }
 
@@ -648,8 +979,10 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
     ) -> Result<(), ArmError> {
         self.select_ap_and_ap_bank(ap, address)?;
 
-        self.probe_mut()
-            .raw_write_register(RegisterAddress::ApRegister((address & 0xFF) as u8), value)?;
+        self.retry_on_wait(|probe| {
+            probe.raw_write_register(RegisterAddress::ApRegister((address & 0xFF) as u8), value)
+        })
+        .map_err(|error| with_ap_register_context(error, ap, address))?;
 
         Ok(())
     }
@@ -663,7 +996,8 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
         self.select_ap_and_ap_bank(ap, address)?;
 
         self.probe_mut()
-            .raw_write_block(RegisterAddress::ApRegister((address & 0xFF) as u8), values)?;
+            .raw_write_block(RegisterAddress::ApRegister((address & 0xFF) as u8), values)
+            .map_err(|error| with_ap_register_context(error, ap, address))?;
         Ok(())
     }
 
@@ -676,6 +1010,19 @@ impl DapAccess for ArmCommunicationInterface<Initialized> {
     }
 }
 
+/// Attach the access port and register address to a [`DapError`], so that it is clear which
+/// access failed. Other errors are returned unchanged.
+fn with_ap_register_context(
+    error: ArmError,
+    ap: &FullyQualifiedApAddress,
+    register: u64,
+) -> ArmError {
+    match error {
+        ArmError::Dap(error) => ArmError::from_dap_with_context(error, ap, register),
+        other => other,
+    }
+}
+
 /// Information about the chip target we are currently attached to.
 /// This can be used for discovery, tho, for now it does not work optimally,
 /// as some manufacturers (e.g. ST Microelectronics) violate the spec and thus need special discovery procedures.
@@ -690,16 +1037,84 @@ pub struct ArmChipInfo {
     pub part: u16,
 }
 
+impl ArmChipInfo {
+    /// The name of the manufacturer, resolved from its JEP106 code.
+    ///
+    /// Returns `None` if the code is not known.
+    pub fn manufacturer_name(&self) -> Option<&'static str> {
+        self.manufacturer.get()
+    }
+
+    /// The part number of the chip target.
+    ///
+    /// See [`ArmChipInfo::part`] for why this is not necessarily unique.
+    pub fn part_number(&self) -> u16 {
+        self.part
+    }
+
+    /// Check if this chip has the given manufacturer and part number.
+    ///
+    /// The manufacturer name is compared case-insensitively against the JEP106 name.
+    pub fn matches(&self, manufacturer_name: &str, part: u16) -> bool {
+        self.part == part
+            && self
+                .manufacturer_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(manufacturer_name))
+    }
+
+    /// Returns the names of all targets in `registry` which may be this chip.
+    ///
+    /// Only targets of the same manufacturer are considered. Targets with a matching part
+    /// number come first, followed by the targets which the chip detection data of their family
+    /// associates with the part number. Targets without any part number information are not
+    /// suggested, as they can't be told apart. As part numbers are not unique in practice (see
+    /// [`ArmChipInfo::part`]), more than one suggestion is to be expected.
+    pub fn suggest_targets(&self, registry: &Registry) -> Vec<String> {
+        let families = registry
+            .families()
+            .iter()
+            .filter(|family| family.manufacturer == Some(self.manufacturer));
+
+        let mut suggestions = Vec::new();
+        for family in families.clone() {
+            suggestions.extend(
+                family
+                    .variants()
+                    .iter()
+                    .filter(|chip| chip.part == Some(self.part))
+                    .map(|chip| chip.name.clone()),
+            );
+        }
+
+        for family in families {
+            let detected = family
+                .chip_detection
+                .iter()
+                .filter_map(|detection| detection.as_infineon_xmc_scu())
+                .filter(|detection| detection.part == self.part)
+                .flat_map(|detection| detection.variants.values());
+
+            for name in detected {
+                if !suggestions.contains(name) {
+                    suggestions.push(name.clone());
+                }
+            }
+        }
+
+        suggestions
+    }
+}
+
 impl std::fmt::Display for ArmChipInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let manu = match self.manufacturer.get() {
+        let manu = match self.manufacturer_name() {
             Some(name) => name.to_string(),
             None => format!(
                 "<unknown manufacturer (cc={:2x}, id={:2x})>",
                 self.manufacturer.cc, self.manufacturer.id
             ),
         };
-        write!(f, "{} 0x{:04x}", manu, self.part)
+        write!(f, "{} 0x{:04x}", manu, self.part_number())
     }
 }
 
@@ -708,3 +1123,803 @@ pub trait FlushableArmAccess {
     /// Flush all remaining commands if the target driver implements batching.
     fn flush(&mut self) -> Result<(), ArmError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        architecture::arm::{
+            ApV2Address,
+            dp::{Abort, DLPIDR, DebugPortError, DebugPowerStatus, DpRegister, TARGETID},
+        },
+        config::{Chip, ChipFamily, CoreType, TargetDescriptionSource},
+        probe::{WireProtocol, fake_probe::FakeProbe},
+    };
+
+    type RecordedWrites = Arc<Mutex<Vec<(RegisterAddress, u32)>>>;
+
+    /// Create an interface with an already started default debug port, which records all
+    /// register writes.
+    fn interface_with_ctrl(ctrl: Ctrl) -> (ArmCommunicationInterface<Initialized>, RecordedWrites) {
+        interface_with_probe(FakeProbe::new(), ctrl)
+    }
+
+    fn interface_with_probe(
+        probe: FakeProbe,
+        ctrl: Ctrl,
+    ) -> (ArmCommunicationInterface<Initialized>, RecordedWrites) {
+        let ctrl = u32::from(ctrl);
+
+        interface_with_dp_registers(probe, DpAddress::Default, move |address| {
+            if address == Ctrl::ADDRESS { ctrl } else { 0 }
+        })
+    }
+
+    /// Create an interface with the already started debug port `dp`, which reads its DP
+    /// registers from `read_dp_register`, and records all register writes.
+    fn interface_with_dp_registers(
+        mut probe: FakeProbe,
+        dp: DpAddress,
+        read_dp_register: impl Fn(DpRegisterAddress) -> u32 + Send + 'static,
+    ) -> (ArmCommunicationInterface<Initialized>, RecordedWrites) {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+
+        probe.set_dap_register_read_handler(Box::new(move |address| match address {
+            RegisterAddress::DpRegister(address) => Ok(read_dp_register(address)),
+            _ => Ok(0),
+        }));
+        let recorded_writes = writes.clone();
+        probe.set_dap_register_write_handler(Box::new(move |address, value| {
+            recorded_writes.lock().unwrap().push((address, value));
+            Ok(())
+        }));
+
+        let mut state = Initialized::new(DefaultArmSequence::create(), dp, false);
+        state.dps.insert(dp, DpState::new());
+
+        let interface = ArmCommunicationInterface {
+            probe: Some(Box::new(probe)),
+            state,
+        };
+
+        (interface, writes)
+    }
+
+    #[test]
+    fn set_overrun_detect_unchanged() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        interface.set_overrun_detect(false).unwrap();
+
+        assert!(!interface.overrun_detect());
+        assert!(writes.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn set_overrun_detect_changed() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        interface.set_overrun_detect(true).unwrap();
+
+        assert!(interface.overrun_detect());
+
+        let mut expected = Ctrl(0);
+        expected.set_orun_detect(true);
+        assert_eq!(
+            writes.lock().unwrap().as_slice(),
+            &[(
+                RegisterAddress::DpRegister(Ctrl::ADDRESS),
+                u32::from(expected)
+            )]
+        );
+    }
+
+    #[test]
+    fn select_written_once_per_transaction() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+        let ap = FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 1);
+
+        let select_writes = || {
+            writes
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(address, _)| *address == RegisterAddress::DpRegister(SelectV1::ADDRESS))
+                .count()
+        };
+
+        crate::architecture::arm::with_transaction(&mut interface, |interface| {
+            for _ in 0..4 {
+                interface.read_raw_ap_register(&ap, 0x0)?;
+                interface.read_raw_ap_register(&ap, 0x4)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(select_writes(), 1);
+
+        // Switching the bank requires a new SELECT write.
+        crate::architecture::arm::with_transaction(&mut interface, |interface| {
+            interface.read_raw_ap_register(&ap, 0x10)?;
+            interface.read_raw_ap_register(&ap, 0x14)
+        })
+        .unwrap();
+        assert_eq!(select_writes(), 2);
+    }
+
+    #[test]
+    fn transaction_returns_error_of_group() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+        let ap = FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 1);
+
+        let result: Result<(), _> =
+            crate::architecture::arm::with_transaction(&mut interface, |interface| {
+                interface.write_raw_ap_register(&ap, 0x0, 0x1234)?;
+                Err(ArmError::Timeout)
+            });
+
+        assert!(matches!(result, Err(ArmError::Timeout)));
+        // The access before the error is still performed.
+        assert!(
+            writes
+                .lock()
+                .unwrap()
+                .contains(&(RegisterAddress::ApRegister(0x0), 0x1234))
+        );
+    }
+
+    #[test]
+    fn select_written_again_after_invalidation() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+        let ap = FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 1);
+
+        let select_writes = || {
+            writes
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(address, _)| *address == RegisterAddress::DpRegister(SelectV1::ADDRESS))
+                .count()
+        };
+
+        interface.read_raw_ap_register(&ap, 0x0).unwrap();
+        interface.read_raw_ap_register(&ap, 0x0).unwrap();
+        assert_eq!(select_writes(), 1);
+
+        interface.invalidate_select(DpAddress::Default);
+
+        interface.read_raw_ap_register(&ap, 0x0).unwrap();
+        assert_eq!(select_writes(), 2);
+
+        // The cache is valid again after the write.
+        interface.read_raw_ap_register(&ap, 0x0).unwrap();
+        assert_eq!(select_writes(), 2);
+    }
+
+    #[test]
+    fn read_dp_registers_grouped_by_bank() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        let values = interface
+            .read_raw_dp_registers(
+                DpAddress::Default,
+                &[
+                    DPIDR::ADDRESS,
+                    TARGETID::ADDRESS,
+                    DLPIDR::ADDRESS,
+                    Ctrl::ADDRESS,
+                    TARGETID::ADDRESS,
+                ],
+            )
+            .unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[3], u32::from(Ctrl(0)));
+
+        // Bank 0 is already selected, then SELECT is written once for bank 2 and once for bank 3.
+        let select_writes: Vec<_> = writes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(address, _)| *address == RegisterAddress::DpRegister(SelectV1::ADDRESS))
+            .map(|(_, value)| SelectV1(*value).dp_bank_sel())
+            .collect();
+        assert_eq!(select_writes, [2, 3]);
+    }
+
+    #[test]
+    fn clear_sticky_errors_writes_abort() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        interface.clear_sticky_errors(DpAddress::Default).unwrap();
+
+        // STKERRCLR, WDERRCLR and ORUNERRCLR
+        assert_eq!(
+            writes.lock().unwrap().as_slice(),
+            &[(RegisterAddress::DpRegister(Abort::ADDRESS), 0b1_1100)]
+        );
+    }
+
+    #[test]
+    fn clear_sticky_errors_writes_ctrl_on_jtag() {
+        let mut probe = FakeProbe::new();
+        probe.select_protocol(WireProtocol::Jtag).unwrap();
+
+        // CDBGPWRUPREQ and STICKYERR, which is cleared by the first CTRL/STAT write.
+        let ctrl = Arc::new(Mutex::new(0x1000_0020));
+        let read_ctrl = ctrl.clone();
+        let (mut interface, writes) =
+            interface_with_dp_registers(probe, DpAddress::Default, move |address| {
+                assert_eq!(address, Ctrl::ADDRESS);
+                let mut ctrl = read_ctrl.lock().unwrap();
+                let value = *ctrl;
+                *ctrl &= !0x20;
+                value
+            });
+
+        interface.clear_sticky_errors(DpAddress::Default).unwrap();
+
+        // The flags are written back to CTRL/STAT together with the power-up request.
+        assert_eq!(
+            writes.lock().unwrap().as_slice(),
+            &[(RegisterAddress::DpRegister(Ctrl::ADDRESS), 0x1000_0020)]
+        );
+        assert_eq!(*ctrl.lock().unwrap(), 0x1000_0000);
+    }
+
+    #[test]
+    fn clear_sticky_errors_reports_remaining_flags() {
+        // STICKYERR is still set
+        let (mut interface, _) = interface_with_ctrl(Ctrl(1 << 5));
+
+        assert!(matches!(
+            interface.clear_sticky_errors(DpAddress::Default),
+            Err(ArmError::DebugPort(DebugPortError::StickyErrorsNotCleared(
+                0x20
+            )))
+        ));
+    }
+
+    #[test]
+    fn debug_power_status_without_system_power() {
+        // CDBGPWRUPACK, CDBGPWRUPREQ and CSYSPWRUPREQ
+        let (mut interface, _) = interface_with_ctrl(Ctrl(0x7000_0000));
+
+        assert_eq!(
+            interface.debug_power_status(DpAddress::Default).unwrap(),
+            DebugPowerStatus {
+                debug_power_up_requested: true,
+                debug_power_up_acknowledged: true,
+                system_power_up_requested: true,
+                system_power_up_acknowledged: false,
+            }
+        );
+    }
+
+    /// Create an interface for the multidrop debug port `targetsel`, which reads TARGETID as
+    /// `targetid`.
+    fn interface_with_targetid(
+        targetsel: u32,
+        targetid: u32,
+    ) -> ArmCommunicationInterface<Initialized> {
+        let dp = DpAddress::Multidrop(targetsel);
+        let (interface, _) = interface_with_dp_registers(FakeProbe::new(), dp, move |address| {
+            if address == TARGETID::ADDRESS {
+                targetid
+            } else {
+                0
+            }
+        });
+
+        interface
+    }
+
+    #[test]
+    fn verify_multidrop_targetid_of_second_instance() {
+        // RP2040 core 1, with the instance in the TARGETSEL value.
+        let mut interface = interface_with_targetid(0x1100_2927, 0x0100_2927);
+
+        assert!(
+            interface
+                .verify_multidrop_targetid(DpAddress::Multidrop(0x1100_2927))
+                .unwrap()
+        );
+        assert!(
+            !interface
+                .verify_multidrop_targetid(DpAddress::Default)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn verify_multidrop_targetid_reports_mismatch() {
+        let mut interface = interface_with_targetid(0x0100_3927, 0x0100_2927);
+
+        assert!(matches!(
+            interface.verify_multidrop_targetid(DpAddress::Multidrop(0x0100_3927)),
+            Err(ArmError::DebugPort(DebugPortError::TargetIdMismatch {
+                targetsel: 0x0100_3927,
+                targetid: 0x0100_2927,
+            }))
+        ));
+    }
+
+    #[test]
+    fn ap_read_retried_on_wait() {
+        let mut probe = FakeProbe::new();
+        let reads = Arc::new(Mutex::new(0));
+        let counted_reads = reads.clone();
+        probe.set_dap_register_read_handler(Box::new(move |_| {
+            let mut reads = counted_reads.lock().unwrap();
+            *reads += 1;
+            if *reads <= 2 {
+                Err(ArmError::Dap(DapError::WaitResponse))
+            } else {
+                Ok(0x1234_5678)
+            }
+        }));
+        probe.set_dap_register_write_handler(Box::new(|_, _| Ok(())));
+
+        let mut state = Initialized::new(DefaultArmSequence::create(), DpAddress::Default, false);
+        state.dps.insert(DpAddress::Default, DpState::new());
+        let mut interface = ArmCommunicationInterface {
+            probe: Some(Box::new(probe)),
+            state,
+        };
+        interface.set_wait_retry_policy(WaitRetryPolicy {
+            attempts: 3,
+            delay: Duration::ZERO,
+        });
+        let ap = FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 0);
+
+        assert_eq!(
+            interface.read_raw_ap_register(&ap, 0x0).unwrap(),
+            0x1234_5678
+        );
+        assert_eq!(*reads.lock().unwrap(), 3);
+
+        // The error is returned once all attempts are used up.
+        *reads.lock().unwrap() = 0;
+        interface.set_wait_retry_policy(WaitRetryPolicy {
+            attempts: 2,
+            delay: Duration::ZERO,
+        });
+        assert!(matches!(
+            interface.read_raw_ap_register(&ap, 0x0),
+            Err(ArmError::ApRegisterAccess {
+                source: DapError::WaitResponse,
+                ..
+            })
+        ));
+        assert_eq!(*reads.lock().unwrap(), 2);
+    }
+
+    /// A sequence which fails to connect to a debug port a given number of times, and counts
+    /// how often the debug port was connected and set up.
+    #[derive(Debug)]
+    struct FlakyConnectSequence {
+        failures: usize,
+        connects: Mutex<usize>,
+        setups: Mutex<usize>,
+    }
+
+    impl ArmDebugSequence for FlakyConnectSequence {
+        fn debug_port_connect(
+            &self,
+            _interface: &mut dyn DapProbe,
+            _dp: DpAddress,
+        ) -> Result<(), ArmError> {
+            let mut connects = self.connects.lock().unwrap();
+            *connects += 1;
+            if *connects <= self.failures {
+                Err(ArmError::Dap(DapError::NoAcknowledge))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn debug_port_setup(
+            &self,
+            _interface: &mut dyn DapProbe,
+            _dp: DpAddress,
+        ) -> Result<(), ArmError> {
+            *self.setups.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        fn debug_port_start(
+            &self,
+            _interface: &mut dyn DapAccess,
+            _dp: DpAddress,
+        ) -> Result<(), ArmError> {
+            Ok(())
+        }
+    }
+
+    fn switch_dp_with_connect_failures(failures: usize) -> (usize, usize) {
+        let multidrop = DpAddress::Multidrop(0x0100_2927);
+        let sequence = Arc::new(FlakyConnectSequence {
+            failures,
+            connects: Mutex::new(0),
+            setups: Mutex::new(0),
+        });
+
+        let (mut interface, _) = interface_with_ctrl(Ctrl(0));
+        interface.state.sequence = sequence.clone();
+        interface.state.dps.insert(multidrop, DpState::new());
+
+        interface.select_dp(multidrop).unwrap();
+        assert_eq!(interface.state.current_dp, multidrop);
+
+        let connects = *sequence.connects.lock().unwrap();
+        let setups = *sequence.setups.lock().unwrap();
+        (connects, setups)
+    }
+
+    #[test]
+    fn dp_connect_retried_before_setup() {
+        // A single failure is handled by retrying the connect.
+        assert_eq!(switch_dp_with_connect_failures(1), (2, 0));
+
+        // The setup sequence is only used if the retry fails as well.
+        assert_eq!(switch_dp_with_connect_failures(2), (2, 1));
+    }
+
+    #[test]
+    fn ap_fault_includes_register_context() {
+        let mut probe = FakeProbe::new();
+        probe.set_dap_register_read_handler(Box::new(|address| match address {
+            RegisterAddress::ApRegister(_) => Err(ArmError::Dap(DapError::FaultResponse)),
+            _ => Ok(0),
+        }));
+        probe.set_dap_register_write_handler(Box::new(|_, _| Ok(())));
+
+        let mut state = Initialized::new(DefaultArmSequence::create(), DpAddress::Default, false);
+        state.dps.insert(DpAddress::Default, DpState::new());
+        let mut interface = ArmCommunicationInterface {
+            probe: Some(Box::new(probe)),
+            state,
+        };
+        let ap = FullyQualifiedApAddress::v1_with_dp(DpAddress::Default, 2);
+
+        let error = interface.read_raw_ap_register(&ap, 0xFC).unwrap_err();
+
+        let ArmError::ApRegisterAccess {
+            address,
+            register,
+            source: DapError::FaultResponse,
+        } = &error
+        else {
+            panic!("Unexpected error: {error:?}");
+        };
+        assert_eq!(*address, ap);
+        assert_eq!(*register, 0xFC);
+    }
+
+    #[test]
+    fn debug_port_version_does_not_connect() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        // The state is inserted, but DPIDR was never read.
+        assert_eq!(interface.debug_port_version(DpAddress::Default), None);
+        assert_eq!(
+            interface.debug_port_version(DpAddress::Multidrop(0x01002927)),
+            None
+        );
+
+        interface
+            .state
+            .dps
+            .get_mut(&DpAddress::Default)
+            .unwrap()
+            .debug_port_version = DebugPortVersion::DPv2;
+        assert_eq!(
+            interface.debug_port_version(DpAddress::Default),
+            Some(DebugPortVersion::DPv2)
+        );
+
+        assert_eq!(interface.current_debug_port(), DpAddress::Default);
+        assert!(writes.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn drop_stops_debug_port() {
+        let (interface, writes) = interface_with_ctrl(Ctrl(0));
+
+        // Dropping without `close` must still power down the debug port.
+        drop(interface);
+
+        assert_eq!(
+            writes.lock().unwrap().as_slice(),
+            &[
+                (RegisterAddress::DpRegister(SelectV1::ADDRESS), 0),
+                (RegisterAddress::DpRegister(Ctrl::ADDRESS), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn enable_swo_checks_mode() {
+        let modes = Arc::new(Mutex::new(Vec::new()));
+
+        let mut probe = FakeProbe::new();
+        let recorded_modes = modes.clone();
+        probe.set_swo_enable_handler(
+            vec![SwoMode::Uart],
+            Box::new(move |config| {
+                recorded_modes.lock().unwrap().push(config.mode());
+                Ok(())
+            }),
+        );
+        let (mut interface, _) = interface_with_probe(probe, Ctrl(0));
+
+        interface.enable_swo(&SwoConfig::new(64_000_000)).unwrap();
+        assert_eq!(modes.lock().unwrap().as_slice(), &[SwoMode::Uart]);
+
+        let manchester = SwoConfig::new(64_000_000).set_mode_manchester();
+        assert!(matches!(
+            interface.enable_swo(&manchester),
+            Err(ArmError::SwoModeNotSupported(SwoMode::Manchester))
+        ));
+        assert_eq!(modes.lock().unwrap().as_slice(), &[SwoMode::Uart]);
+    }
+
+    #[test]
+    fn enable_swo_forwards_baud_override() {
+        let bauds = Arc::new(Mutex::new(Vec::new()));
+
+        let mut probe = FakeProbe::new();
+        let recorded_bauds = bauds.clone();
+        probe.set_swo_enable_handler(
+            vec![SwoMode::Uart],
+            Box::new(move |config| {
+                recorded_bauds
+                    .lock()
+                    .unwrap()
+                    .push((config.baud(), config.probe_baud()));
+                Ok(())
+            }),
+        );
+        let (mut interface, _) = interface_with_probe(probe, Ctrl(0));
+
+        let config = SwoConfig::new(64_000_000).set_baud(2_000_000);
+        interface.enable_swo(&config).unwrap();
+        interface
+            .enable_swo(&config.set_baud_override(1_500_000))
+            .unwrap();
+
+        assert_eq!(
+            bauds.lock().unwrap().as_slice(),
+            &[(2_000_000, 2_000_000), (2_000_000, 1_500_000)]
+        );
+    }
+
+    #[test]
+    fn read_swo_status_without_overflow_support() {
+        let (mut interface, _) = interface_with_ctrl(Ctrl(0));
+
+        // The fake probe can't report overflows, so the default is used.
+        assert_eq!(
+            interface.read_swo_status(Duration::ZERO).unwrap(),
+            SwoStatus::default()
+        );
+    }
+
+    #[test]
+    fn select_apv2_on_dpv1() {
+        let (mut interface, writes) = interface_with_ctrl(Ctrl(0));
+        let ap = FullyQualifiedApAddress::v2_with_dp(DpAddress::Default, ApV2Address::new(0x2000));
+
+        assert!(matches!(
+            interface.read_raw_ap_register(&ap, 0x0),
+            Err(ArmError::InconsistentSelectState(address)) if address == ap
+        ));
+        assert!(writes.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn access_ports_of_unsupported_debug_port_version() {
+        let (mut interface, _) = interface_with_ctrl(Ctrl(0));
+        interface
+            .state
+            .dps
+            .get_mut(&DpAddress::Default)
+            .unwrap()
+            .debug_port_version = DebugPortVersion::Unsupported(0x5);
+
+        assert!(matches!(
+            interface.access_ports(DpAddress::Default),
+            Err(ArmError::UnsupportedDebugPortVersion(0x5))
+        ));
+        assert!(matches!(
+            interface.access_ports_with_deadline(DpAddress::Default, Instant::now()),
+            Err(ArmError::UnsupportedDebugPortVersion(0x5))
+        ));
+    }
+
+    #[test]
+    fn chip_info_matches() {
+        let info = ArmChipInfo {
+            manufacturer: JEP106Code::new(0, 0x20),
+            part: 0x413,
+        };
+
+        assert!(info.matches("STMicroelectronics", 0x413));
+        assert!(info.matches("stmicroelectronics", 0x413));
+        assert!(!info.matches("STMicroelectronics", 0x419));
+        assert!(!info.matches("Nordic VLSI ASA", 0x413));
+    }
+
+    #[test]
+    fn suggest_targets_with_colliding_part_numbers() {
+        let st = JEP106Code::new(0, 0x20);
+
+        let chip = |name: &str, part: Option<u16>| {
+            let mut chip = Chip::generic_arm(name, CoreType::Armv7em);
+            chip.part = part;
+            chip
+        };
+
+        let family = |name: &str, manufacturer: JEP106Code, variants: Vec<Chip>| ChipFamily {
+            name: name.to_owned(),
+            manufacturer: Some(manufacturer),
+            generated_from_pack: false,
+            pack_file_release: None,
+            chip_detection: vec![],
+            variants,
+            flash_algorithms: vec![],
+            source: TargetDescriptionSource::Generic,
+        };
+
+        let mut registry = Registry::new();
+        registry
+            .add_target_family(family(
+                "STM32F4 Series",
+                st,
+                vec![
+                    chip("STM32F405RGTx", None),
+                    // ST uses the same part number for the whole STM32F405/407 line.
+                    chip("STM32F407VGTx", Some(0x413)),
+                    chip("STM32F417VGTx", Some(0x413)),
+                    chip("STM32F429ZITx", Some(0x419)),
+                ],
+            ))
+            .unwrap();
+        registry
+            .add_target_family(family(
+                "Other",
+                JEP106Code::new(2, 0x44),
+                vec![chip("nRF52840_xxAA", Some(0x413))],
+            ))
+            .unwrap();
+
+        let info = ArmChipInfo {
+            manufacturer: st,
+            part: 0x413,
+        };
+
+        assert_eq!(
+            info.suggest_targets(&registry),
+            ["STM32F407VGTx", "STM32F417VGTx"]
+        );
+    }
+
+    #[cfg(feature = "builtin-targets")]
+    #[test]
+    fn suggest_targets_from_builtin_registry() {
+        let registry = Registry::from_builtin_families();
+
+        // The built-in STM32 targets don't specify a part number, so they can't be suggested.
+        let stm32f4 = ArmChipInfo {
+            manufacturer: JEP106Code::new(0, 0x20),
+            part: 0x413,
+        };
+        assert!(stm32f4.suggest_targets(&registry).is_empty());
+
+        // The XMC4000 part numbers are only part of the chip detection data.
+        let xmc4100 = ArmChipInfo {
+            manufacturer: JEP106Code::new(0, 0x41),
+            part: 0x1dd,
+        };
+        assert_eq!(
+            xmc4100.suggest_targets(&registry),
+            ["XMC4104-Q48x64", "XMC4100-Q48x128", "XMC4200-Q48x256"]
+        );
+
+        let rp2350 = ArmChipInfo {
+            manufacturer: JEP106Code::new(4, 0x3b),
+            part: 0x4c9,
+        };
+        assert_eq!(rp2350.suggest_targets(&registry), ["RP235x"]);
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let mut calls = 0;
+        let result = retry(3, Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err(ArmError::Timeout)
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn retry_returns_last_error() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry(2, Duration::ZERO, || {
+            calls += 1;
+            Err(ArmError::Other(format!("attempt {calls}")))
+        });
+
+        assert!(matches!(result, Err(ArmError::Other(message)) if message == "attempt 2"));
+        assert_eq!(calls, 2);
+
+        // At least one attempt is always made.
+        let mut calls = 0;
+        let _ = retry(0, Duration::ZERO, || -> Result<(), _> {
+            calls += 1;
+            Err(ArmError::Timeout)
+        });
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn retry_if_returns_other_errors_immediately() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry_if(
+            5,
+            Duration::ZERO,
+            |error| matches!(error, ArmError::Dap(DapError::WaitResponse)),
+            || {
+                calls += 1;
+                if calls < 2 {
+                    Err(ArmError::Dap(DapError::WaitResponse))
+                } else {
+                    Err(ArmError::Timeout)
+                }
+            },
+        );
+
+        assert!(matches!(result, Err(ArmError::Timeout)));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn all_access_ports_records_errors_per_dp() {
+        use crate::test::FakeArmProbe;
+
+        let reachable = DpAddress::Multidrop(0x0100_2927);
+        let unreachable = DpAddress::Multidrop(0x1100_2927);
+
+        let mut interface = FakeArmProbe::new();
+        interface.set_dp_register(DPIDR::ADDRESS, 0x0BC1_2477);
+        interface.add_access_port(FullyQualifiedApAddress::v1_with_dp(reachable, 0));
+        interface.add_access_port(FullyQualifiedApAddress::v1_with_dp(reachable, 1));
+        interface.set_dp_unreachable(unreachable);
+
+        let access_ports = interface
+            .all_access_ports(&[reachable, unreachable])
+            .unwrap();
+
+        assert_eq!(access_ports.len(), 2);
+        assert_eq!(
+            access_ports[&reachable].as_ref().unwrap(),
+            &BTreeSet::from([
+                FullyQualifiedApAddress::v1_with_dp(reachable, 0),
+                FullyQualifiedApAddress::v1_with_dp(reachable, 1),
+            ])
+        );
+        assert!(matches!(
+            access_ports[&unreachable],
+            Err(ArmError::Other(_))
+        ));
+
+        assert_eq!(interface.current_debug_port(), DpAddress::Default);
+    }
+}
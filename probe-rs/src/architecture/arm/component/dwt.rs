@@ -78,9 +78,20 @@ impl<'a> Dwt<'a> {
         function.store_unit(self.component, self.interface, unit)
     }
 
+    /// Returns whether the DWT is able to emit trace packets.
+    pub fn supports_trace_packets(&mut self) -> Result<bool, ArmError> {
+        let ctrl = Ctrl::load(self.component, self.interface)?;
+        Ok(!ctrl.notrcpkt())
+    }
+
     /// Enable exception tracing.
+    ///
+    /// Returns an error if the DWT does not support emitting trace packets.
     pub fn enable_exception_trace(&mut self) -> Result<(), ArmError> {
         let mut ctrl = Ctrl::load(self.component, self.interface)?;
+        if ctrl.notrcpkt() {
+            return Err(ArmError::ExtensionRequired(&["DWT trace packets"]));
+        }
         ctrl.set_exctrcena(true);
         ctrl.store(self.component, self.interface)
     }
@@ -192,8 +203,12 @@ memory_mapped_bitfield_register! {
     pub lnk1ena, _: 9;
     pub datavmatch, set_datavmatch: 8;
     pub cycmatch, set_cycmatch: 7;
+    /// Only used by ARMv6-M and ARMv7-M.
     pub emitrange, set_emitrange: 5;
-    pub function, set_function: 3, 0;
+    /// The action on a match, only used by ARMv8-M.
+    pub u8, action, set_action: 5, 4;
+    /// The comparator function, called `MATCH` on ARMv8-M. Zero disables the comparator.
+    pub u8, function, set_function: 3, 0;
 }
 
 impl DebugComponentInterface for Function {}
@@ -1,6 +1,6 @@
 //! Types and functions for interacting with CoreSight Components
 
-mod dwt;
+pub(crate) mod dwt;
 mod itm;
 mod scs;
 mod swo;
@@ -202,7 +202,11 @@ pub(crate) fn setup_tracing(
     // Configure DWT
     let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
     dwt.enable()?;
-    dwt.enable_exception_trace()?;
+    if dwt.supports_trace_packets()? {
+        dwt.enable_exception_trace()?;
+    } else {
+        tracing::warn!("DWT does not support trace packets, exception tracing is not enabled");
+    }
 
     // Configure ITM
     let mut itm = Itm::new(interface, find_component(components, PeripheralType::Itm)?);
@@ -364,6 +368,28 @@ pub fn remove_swv_data_trace(
     dwt.disable_data_trace(unit)
 }
 
+/// Configures DWT to emit exception entry, exit and return trace packets.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub fn enable_exception_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.enable_exception_trace()
+}
+
+/// Configures DWT to stop emitting exception trace packets.
+///
+/// Expects to be given a list of all ROM table `components` as the second argument.
+pub fn disable_exception_trace(
+    interface: &mut dyn ArmProbeInterface,
+    components: &[CoresightComponent],
+) -> Result<(), ArmError> {
+    let mut dwt = Dwt::new(interface, find_component(components, PeripheralType::Dwt)?);
+    dwt.disable_exception_trace()
+}
+
 /// Sets TRCENA in DEMCR to begin trace generation.
 pub fn enable_tracing(core: &mut Core) -> Result<(), Error> {
     let mut demcr = Demcr(core.read_word_32(Demcr::get_mmio_address())?);
@@ -379,3 +405,74 @@ pub fn disable_swv(core: &mut Core) -> Result<(), Error> {
     core.write_word_32(Demcr::get_mmio_address(), demcr.into())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        architecture::arm::FullyQualifiedApAddress,
+        test::{FakeArmMemory, FakeArmProbe},
+    };
+
+    const DWT_ADDRESS: u64 = 0xE000_1000;
+
+    /// An interface with a Cortex-M3 DWT, whose CTRL register holds `ctrl`. Returns a clone of
+    /// the memory behind it, to check the register after writing it.
+    fn dwt_interface(ctrl: u32) -> (FakeArmProbe, FakeArmMemory, Vec<CoresightComponent>) {
+        let mut memory = FakeArmMemory::new();
+        memory.add_coresight_component(DWT_ADDRESS, 0xE, 0x002);
+        memory.add_word_range(DWT_ADDRESS, &[ctrl]);
+
+        let ap = FullyQualifiedApAddress::v1_with_default_dp(0);
+        let component = Component::try_parse(&mut memory, DWT_ADDRESS).unwrap();
+        let components = vec![CoresightComponent::new(component, ap.clone())];
+
+        let mut interface = FakeArmProbe::new();
+        interface.set_ap_memory(ap, memory.clone());
+
+        (interface, memory, components)
+    }
+
+    #[test]
+    fn exception_trace_toggles_exctrcena() {
+        // Four comparators, trace packets supported.
+        let (mut interface, mut memory, components) = dwt_interface(0x4000_0000);
+
+        let dwt = find_component(&components, PeripheralType::Dwt).unwrap();
+        let supported = Dwt::new(&mut interface, dwt).supports_trace_packets();
+        assert!(supported.unwrap());
+
+        enable_exception_trace(&mut interface, &components).unwrap();
+        assert_eq!(memory.read_word_32(DWT_ADDRESS).unwrap(), 0x4001_0000);
+
+        disable_exception_trace(&mut interface, &components).unwrap();
+        assert_eq!(memory.read_word_32(DWT_ADDRESS).unwrap(), 0x4000_0000);
+    }
+
+    #[test]
+    fn exception_trace_requires_trace_packets() {
+        // NOTRCPKT is set.
+        let (mut interface, mut memory, components) = dwt_interface(0x4800_0000);
+
+        let dwt = find_component(&components, PeripheralType::Dwt).unwrap();
+        let supported = Dwt::new(&mut interface, dwt).supports_trace_packets();
+        assert!(!supported.unwrap());
+
+        let result = enable_exception_trace(&mut interface, &components);
+        assert!(matches!(result, Err(ArmError::ExtensionRequired(_))));
+        assert_eq!(memory.read_word_32(DWT_ADDRESS).unwrap(), 0x4800_0000);
+    }
+
+    #[test]
+    fn exception_trace_requires_dwt() {
+        let mut interface = FakeArmProbe::new();
+
+        let result = enable_exception_trace(&mut interface, &[]);
+        assert!(matches!(
+            result,
+            Err(ArmError::RomTable(RomTableError::ComponentNotFound(
+                PeripheralType::Dwt
+            )))
+        ));
+    }
+}
@@ -1,13 +1,14 @@
 //! Register types and the core interface for armv6-M
 
-use super::{CortexMState, Dfsr, registers::cortex_m::*};
+use super::{CortexMState, Dfsr, HaltReasonDecoder, registers::cortex_m::*};
 use crate::{
     Architecture, BreakpointCause, CoreInformation, CoreInterface, CoreRegister, CoreStatus,
     CoreType, HaltReason, InstructionSet, MemoryInterface, MemoryMappedRegister,
     architecture::arm::{ArmError, memory::ArmMemoryInterface, sequences::ArmDebugSequence},
-    core::{CoreRegisters, RegisterId, RegisterValue, VectorCatchCondition},
+    core::{CoreRegisters, RegisterId, RegisterValue, VectorCatchCondition, WatchAccess},
     error::Error,
     memory::{CoreMemoryInterface, valid_32bit_address},
+    semihosting::SemihostingCommand,
 };
 use bitfield::bitfield;
 use std::{
@@ -456,7 +457,7 @@ impl<'probe> Armv6m<'probe> {
     }
 
     fn set_core_status(&mut self, new_status: CoreStatus) {
-        super::update_core_status(&mut self.memory, &mut self.state.current_state, new_status);
+        self.state.update_status(&mut self.memory, new_status);
     }
 }
 
@@ -515,6 +516,15 @@ impl CoreInterface for Armv6m<'_> {
 
             let mut reason = dfsr.halt_reason();
 
+            if reason == HaltReason::Watchpoint {
+                reason = super::cortex_m::watchpoint_halt_reason(&mut *self.memory).unwrap_or_else(
+                    |error| {
+                        tracing::debug!("Unable to identify the matched DWT comparator: {error}");
+                        HaltReason::Watchpoint
+                    },
+                );
+            }
+
             // Clear bits from Dfsr register
             self.memory
                 .write_word_32(Dfsr::get_mmio_address(), Dfsr::clear_all().into())?;
@@ -535,18 +545,22 @@ impl CoreInterface for Armv6m<'_> {
                     &self.state.current_state,
                     &reason
                 );
+                tracing::trace!("Status history: {:?}", self.state.status_history());
             }
 
             // Set the status so any semihosting operations will know we're halted
             self.set_core_status(CoreStatus::Halted(reason));
 
             if let HaltReason::Breakpoint(_) = reason {
-                self.state.semihosting_command = super::cortex_m::check_for_semihosting(
+                let (command, instruction) = super::cortex_m::check_for_semihosting(
                     self.state.semihosting_command.take(),
                     self,
                 )?;
-                if let Some(command) = self.state.semihosting_command {
+                self.state.set_semihosting_command(command);
+                if let Some(command) = command {
                     reason = HaltReason::Breakpoint(BreakpointCause::Semihosting(command));
+                } else if reason == HaltReason::Breakpoint(BreakpointCause::Unknown) {
+                    reason = HaltReason::Breakpoint(super::cortex_m::breakpoint_cause(instruction));
                 }
 
                 // Set it again if it's changed
@@ -566,9 +580,7 @@ impl CoreInterface for Armv6m<'_> {
         Ok(CoreStatus::Running)
     }
 
-    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
-        // TODO: Generic halt support
-
+    fn request_halt(&mut self) -> Result<(), Error> {
         let mut value = Dhcsr(0);
         value.set_c_halt(true);
         value.set_c_debugen(true);
@@ -576,7 +588,15 @@ impl CoreInterface for Armv6m<'_> {
 
         self.memory
             .write_word_32(Dhcsr::get_mmio_address(), value.into())?;
+        self.memory.flush()?;
 
+        Ok(())
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        // TODO: Generic halt support
+
+        self.request_halt()?;
         self.wait_for_core_halted(timeout)?;
 
         // try to read the program counter
@@ -607,7 +627,7 @@ impl CoreInterface for Armv6m<'_> {
     }
 
     fn reset(&mut self) -> Result<(), Error> {
-        self.state.semihosting_command = None;
+        self.state.set_semihosting_command(None);
 
         self.sequence
             .reset_system(&mut *self.memory, crate::CoreType::Armv6m, None)?;
@@ -691,7 +711,7 @@ impl CoreInterface for Armv6m<'_> {
             self.enable_breakpoints(true)?;
         }
 
-        self.state.semihosting_command = None;
+        self.state.set_semihosting_command(None);
 
         Ok(CoreInformation {
             pc: pc_after_step.try_into()?,
@@ -913,6 +933,51 @@ impl CoreInterface for Armv6m<'_> {
             .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
         Ok(())
     }
+
+    fn data_watchpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(super::cortex_m::data_watchpoints(&mut *self.memory)?)
+    }
+
+    fn set_data_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u64,
+        access: WatchAccess,
+    ) -> Result<(), Error> {
+        let address = valid_32bit_address(address)?;
+
+        super::cortex_m::set_data_watchpoint(
+            &mut *self.memory,
+            CoreType::Armv6m,
+            unit_index,
+            address,
+            size,
+            access,
+        )?;
+
+        Ok(())
+    }
+
+    fn clear_data_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        Ok(super::cortex_m::clear_data_watchpoint(
+            &mut *self.memory,
+            unit_index,
+        )?)
+    }
+
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.state.semihosting_command()
+    }
+
+    fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        // Without a DWT, there is no DWT_PCSR to sample from.
+        if !super::cortex_m::dwt_present(&mut *self.memory) {
+            return Ok(None);
+        }
+
+        Ok(super::cortex_m::sample_pc(&mut *self.memory)?)
+    }
 }
 
 impl CoreMemoryInterface for Armv6m<'_> {
@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv7-a
 
 use super::{
-    CortexAState,
+    CortexAState, HaltReasonDecoder,
     instructions::aarch32::{
         build_bx, build_ldc, build_mcr, build_mov, build_mrc, build_mrs, build_stc, build_vmov,
         build_vmrs,
@@ -215,7 +215,7 @@ impl<'probe> Armv7a<'probe> {
     }
 
     fn reset_register_cache(&mut self) {
-        self.state.register_cache = vec![None; 51];
+        self.state.invalidate_register_cache(51);
     }
 
     /// Sync any updated registers back to the core
@@ -265,6 +265,7 @@ impl<'probe> Armv7a<'probe> {
             }
         }
 
+        // The core is about to run, so the cached values will be stale.
         self.reset_register_cache();
 
         Ok(())
@@ -297,6 +298,34 @@ impl<'probe> Armv7a<'probe> {
     fn set_core_status(&mut self, new_status: CoreStatus) {
         super::update_core_status(&mut self.memory, &mut self.state.current_state, new_status);
     }
+
+    /// Read the current instruction set state of the core from the T bit in CPSR.
+    ///
+    /// The core has to be halted.
+    pub fn instruction_set_state(&mut self) -> Result<InstructionSet, Error> {
+        let cpsr: u32 = self.read_core_reg(RegisterId(16))?.try_into()?;
+
+        // CPSR bit 5 - T - Thumb mode
+        match (cpsr >> 5) & 1 {
+            1 => Ok(InstructionSet::Thumb2),
+            _ => Ok(InstructionSet::A32),
+        }
+    }
+}
+
+/// Calculate the DBGBVR value and byte address select for a breakpoint on `address`.
+///
+/// DBGBVR only holds word aligned addresses, so Thumb instructions on the upper halfword
+/// are selected with BAS instead. The LSB of `address` is ignored.
+fn breakpoint_value_and_bas(address: u32, thumb: bool) -> (u32, u32) {
+    let address = address & !1;
+    let word_address = address & !0b11;
+
+    match (thumb, address & 0b10) {
+        (true, 0) => (word_address, 0b0011),
+        (true, _) => (word_address, 0b1100),
+        (false, _) => (word_address, 0b1111),
+    }
 }
 
 impl CoreInterface for Armv7a<'_> {
@@ -472,6 +501,8 @@ impl CoreInterface for Armv7a<'_> {
         let current_pc: u32 = self
             .read_core_reg(self.program_counter().into())?
             .try_into()?;
+        let thumb = self.instruction_set_state()? == InstructionSet::Thumb2;
+        let (bp_value, bas) = breakpoint_value_and_bas(current_pc, thumb);
         let mut bp_control = Dbgbcr(0);
 
         // Breakpoint type - address mismatch
@@ -479,12 +510,12 @@ impl CoreInterface for Armv7a<'_> {
         // Match on all modes
         bp_control.set_hmc(true);
         bp_control.set_pmc(0b11);
-        // Match on all bytes
-        bp_control.set_bas(0b1111);
+        // Match on the bytes of the current instruction
+        bp_control.set_bas(bas);
         // Enable
         bp_control.set_e(true);
 
-        self.memory.write_word_32(bp_value_addr, current_pc)?;
+        self.memory.write_word_32(bp_value_addr, bp_value)?;
         self.memory
             .write_word_32(bp_control_addr, bp_control.into())?;
 
@@ -670,7 +701,9 @@ impl CoreInterface for Armv7a<'_> {
             let bp_control = Dbgbcr(self.memory.read_word_32(bp_control_addr)?);
 
             if bp_control.e() {
-                breakpoints.push(Some(bp_value as u64));
+                // A breakpoint on the upper halfword of the word has the address offset in BAS.
+                let offset = if bp_control.bas() == 0b1100 { 2 } else { 0 };
+                breakpoints.push(Some((bp_value + offset) as u64));
             } else {
                 breakpoints.push(None);
             }
@@ -685,6 +718,10 @@ impl CoreInterface for Armv7a<'_> {
 
     fn set_hw_breakpoint(&mut self, bp_unit_index: usize, addr: u64) -> Result<(), Error> {
         let addr = valid_32bit_address(addr)?;
+        // Interworking addresses have the LSB set for Thumb code, and A32 instructions are
+        // always word aligned, so a halfword aligned address is Thumb code as well.
+        let thumb = addr & 0b11 != 0;
+        let (bp_value, bas) = breakpoint_value_and_bas(addr, thumb);
 
         let bp_value_addr = Dbgbvr::get_mmio_address_from_base(self.base_address)?
             + (bp_unit_index * size_of::<u32>()) as u64;
@@ -697,12 +734,12 @@ impl CoreInterface for Armv7a<'_> {
         // Match on all modes
         bp_control.set_hmc(true);
         bp_control.set_pmc(0b11);
-        // Match on all bytes
-        bp_control.set_bas(0b1111);
+        // Match on the bytes of the instruction
+        bp_control.set_bas(bas);
         // Enable
         bp_control.set_e(true);
 
-        self.memory.write_word_32(bp_value_addr, addr)?;
+        self.memory.write_word_32(bp_value_addr, bp_value)?;
         self.memory
             .write_word_32(bp_control_addr, bp_control.into())?;
 
@@ -758,13 +795,7 @@ impl CoreInterface for Armv7a<'_> {
     }
 
     fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
-        let cpsr: u32 = self.read_core_reg(RegisterId(16))?.try_into()?;
-
-        // CPSR bit 5 - T - Thumb mode
-        match (cpsr >> 5) & 1 {
-            1 => Ok(InstructionSet::Thumb2),
-            _ => Ok(InstructionSet::A32),
-        }
+        self.instruction_set_state()
     }
 
     fn fpu_support(&mut self) -> Result<bool, Error> {
@@ -811,6 +842,20 @@ impl CoreInterface for Armv7a<'_> {
 
         Ok(())
     }
+
+    fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        let address = Dbgdidr::get_mmio_address_from_base(self.base_address)?;
+        let dbgdidr = Dbgdidr(self.memory.read_word_32(address)?);
+
+        if !dbgdidr.pcsr_imp() {
+            return Ok(None);
+        }
+
+        let address = Dbgpcsr::get_mmio_address_from_base(self.base_address)?;
+        let dbgpcsr = Dbgpcsr(self.memory.read_word_32(address)?);
+
+        Ok(dbgpcsr.sampled_address().map(u64::from))
+    }
 }
 
 impl MemoryInterface for Armv7a<'_> {
@@ -1726,6 +1771,67 @@ mod test {
         armv7a.run().unwrap();
     }
 
+    #[test]
+    fn armv7a_run_invalidates_register_cache() {
+        const REG_VALUE: u32 = 0xABCD;
+        const NEW_REG_VALUE: u32 = 0x1234;
+
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Read register
+        add_read_reg_expectations(&mut probe, 2, REG_VALUE);
+
+        // Writeback r0
+        add_set_r0_expectation(&mut probe, 0);
+
+        // Write resume request
+        let mut dbgdrcr = Dbgdrcr(0);
+        dbgdrcr.set_rrq(true);
+        probe.expected_write(
+            Dbgdrcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdrcr.into(),
+        );
+
+        // Wait for running
+        add_status_expectations(&mut probe, false);
+
+        // Read status
+        add_status_expectations(&mut probe, false);
+
+        // Read register again after the core ran
+        add_read_reg_expectations(&mut probe, 2, NEW_REG_VALUE);
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            RegisterValue::from(REG_VALUE),
+            armv7a.read_core_reg(RegisterId(2)).unwrap()
+        );
+
+        armv7a.run().unwrap();
+
+        // The cached value is gone, so this read hits the expectations
+        assert_eq!(
+            RegisterValue::from(NEW_REG_VALUE),
+            armv7a.read_core_reg(RegisterId(2)).unwrap()
+        );
+    }
+
     #[test]
     fn armv7a_available_breakpoint_units() {
         const BP_COUNT: u32 = 4;
@@ -1842,14 +1948,14 @@ mod test {
         // Match on all modes
         dbgbcr.set_hmc(true);
         dbgbcr.set_pmc(0b11);
-        // Match on all bytes
-        dbgbcr.set_bas(0b1111);
+        // Match on the lower halfword, the address is a Thumb address
+        dbgbcr.set_bas(0b0011);
         // Enable
         dbgbcr.set_e(true);
 
         probe.expected_write(
             Dbgbvr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
-            BP_VALUE as u32,
+            (BP_VALUE & !1) as u32,
         );
         probe.expected_write(
             Dbgbcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
@@ -1869,6 +1975,92 @@ mod test {
         armv7a.set_hw_breakpoint(0, BP_VALUE).unwrap();
     }
 
+    #[test]
+    fn armv7a_set_hw_breakpoint_thumb_upper_halfword() {
+        const BP_VALUE: u64 = 0x2346;
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // Update BP value and control
+        let mut dbgbcr = Dbgbcr(0);
+        // Match on all modes
+        dbgbcr.set_hmc(true);
+        dbgbcr.set_pmc(0b11);
+        // Match on the upper halfword
+        dbgbcr.set_bas(0b1100);
+        // Enable
+        dbgbcr.set_e(true);
+
+        probe.expected_write(
+            Dbgbvr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            0x2344,
+        );
+        probe.expected_write(
+            Dbgbcr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgbcr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        armv7a.set_hw_breakpoint(0, BP_VALUE).unwrap();
+    }
+
+    #[test]
+    fn armv7a_instruction_set_state() {
+        for (cpsr, expected) in [
+            (0x0000_01D3, InstructionSet::A32),
+            (0x0000_01F3, InstructionSet::Thumb2),
+        ] {
+            let mut probe = MockProbe::new();
+            let mut state = CortexAState::new();
+
+            // Add expectations
+            add_status_expectations(&mut probe, true);
+            add_enable_itr_expectations(&mut probe);
+            add_read_reg_expectations(&mut probe, 0, 0);
+            add_read_fp_count_expectations(&mut probe);
+
+            // Read CPSR
+            add_read_cpsr_expectations(&mut probe, cpsr);
+
+            let mock_mem = Box::new(probe) as _;
+
+            let mut armv7a = Armv7a::new(
+                mock_mem,
+                &mut state,
+                TEST_BASE_ADDRESS,
+                DefaultArmSequence::create(),
+            )
+            .unwrap();
+
+            assert_eq!(expected, armv7a.instruction_set_state().unwrap());
+        }
+    }
+
+    #[test]
+    fn breakpoint_value_and_bas_masks_thumb_address() {
+        assert_eq!(breakpoint_value_and_bas(0x1000, false), (0x1000, 0b1111));
+        assert_eq!(breakpoint_value_and_bas(0x1000, true), (0x1000, 0b0011));
+        assert_eq!(breakpoint_value_and_bas(0x1001, true), (0x1000, 0b0011));
+        assert_eq!(breakpoint_value_and_bas(0x1002, true), (0x1000, 0b1100));
+        assert_eq!(breakpoint_value_and_bas(0x1003, true), (0x1000, 0b1100));
+        assert_eq!(breakpoint_value_and_bas(0x2346, true), (0x2344, 0b1100));
+    }
+
     #[test]
     fn armv7a_clear_hw_breakpoint() {
         let mut probe = MockProbe::new();
@@ -1963,4 +2155,77 @@ mod test {
 
         assert_eq!(0xBA, armv7a.read_word_8(MEMORY_ADDRESS).unwrap());
     }
+
+    fn add_pcsr_expectations(probe: &mut MockProbe, pcsr: Option<u32>) {
+        let mut dbgdidr = Dbgdidr(0);
+        dbgdidr.set_pcsr_imp(pcsr.is_some());
+        probe.expected_read(
+            Dbgdidr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            dbgdidr.into(),
+        );
+
+        if let Some(pcsr) = pcsr {
+            probe.expected_read(
+                Dbgpcsr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+                pcsr,
+            );
+        }
+    }
+
+    #[test]
+    fn armv7a_sample_pc_running() {
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        // ARM state sample, Thumb state sample, and no sample available
+        add_pcsr_expectations(&mut probe, Some(0x8008));
+        add_pcsr_expectations(&mut probe, Some(0x8105));
+        add_pcsr_expectations(&mut probe, Some(0xFFFF_FFFF));
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(armv7a.sample_pc_running().unwrap(), Some(0x8000));
+        assert_eq!(armv7a.sample_pc_running().unwrap(), Some(0x8100));
+        assert_eq!(armv7a.sample_pc_running().unwrap(), None);
+    }
+
+    #[test]
+    fn armv7a_sample_pc_running_not_implemented() {
+        let mut probe = MockProbe::new();
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+        add_enable_itr_expectations(&mut probe);
+        add_read_reg_expectations(&mut probe, 0, 0);
+        add_read_fp_count_expectations(&mut probe);
+
+        add_pcsr_expectations(&mut probe, None);
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv7a = Armv7a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+
+        assert_eq!(armv7a.sample_pc_running().unwrap(), None);
+    }
 }
@@ -1,5 +1,6 @@
 //! Debug register definitions
 
+use super::HaltReasonDecoder;
 use crate::{HaltReason, core::BreakpointCause, memory_mapped_bitfield_register};
 
 memory_mapped_bitfield_register! {
@@ -211,9 +212,9 @@ memory_mapped_bitfield_register! {
     pub halted, set_halted: 0;
 }
 
-impl Dbgdscr {
+impl HaltReasonDecoder for Dbgdscr {
     /// Decode the MOE register into HaltReason
-    pub fn halt_reason(&self) -> HaltReason {
+    fn halt_reason(&self) -> HaltReason {
         if self.halted() {
             match self.moe() {
                 // Halt request from debugger
@@ -284,7 +285,7 @@ memory_mapped_bitfield_register! {
     pub nsuhd_imp, _: 14;
 
     /// Program Counter Sampling Register, DBGPCSR, implemented as register 33.
-    pub pcsr_imp, _: 13;
+    pub pcsr_imp, set_pcsr_imp: 13;
 
     /// Security Extensions implemented.
     pub se_imp, _: 12;
@@ -415,6 +416,33 @@ memory_mapped_bitfield_register! {
     pub value, set_value: 31, 0;
 }
 
+memory_mapped_bitfield_register! {
+    /// DBGPCSR - Program Counter Sampling Register
+    ///
+    /// Shares its address with DBGITR, which is write-only.
+    pub struct Dbgpcsr(u32);
+    0x084, "DBGPCSR",
+    impl From;
+
+    /// Sampled value
+    pub value, _: 31, 0;
+}
+
+impl Dbgpcsr {
+    /// Returns the address of the sampled instruction.
+    ///
+    /// The sampled value includes the offset of the instruction set state, which is 8 bytes in ARM
+    /// state and 4 bytes in Thumb state. Returns `None` if no sample is available, e.g. because
+    /// the core is halted.
+    pub fn sampled_address(&self) -> Option<u32> {
+        match self.value() {
+            0xFFFF_FFFF => None,
+            value if value & 0b1 == 0b1 => Some((value & !0b1).wrapping_sub(4)),
+            value => Some((value & !0b11).wrapping_sub(8)),
+        }
+    }
+}
+
 memory_mapped_bitfield_register! {
     /// DBGDTRTX - Target to Host data transfer register
     pub struct Dbgdtrtx(u32);
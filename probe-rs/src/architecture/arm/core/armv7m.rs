@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv7-M
 
 use super::{
-    CortexMState, Dfsr,
+    CortexMState, Dfsr, HaltReasonDecoder,
     cortex_m::Mvfr0,
     registers::cortex_m::{
         CORTEX_M_CORE_REGISTERS, CORTEX_M_WITH_FP_CORE_REGISTERS, FP, PC, RA, SP,
@@ -15,10 +15,11 @@ use crate::{
     },
     core::{
         Architecture, CoreInformation, CoreInterface, CoreRegisters, CoreStatus, HaltReason,
-        MemoryMappedRegister, RegisterId, RegisterValue, VectorCatchCondition,
+        MemoryMappedRegister, RegisterId, RegisterValue, VectorCatchCondition, WatchAccess,
     },
     error::Error,
     memory::{CoreMemoryInterface, valid_32bit_address},
+    semihosting::SemihostingCommand,
 };
 use bitfield::bitfield;
 use std::{
@@ -641,7 +642,7 @@ impl<'probe> Armv7m<'probe> {
     }
 
     fn set_core_status(&mut self, new_status: CoreStatus) {
-        super::update_core_status(&mut self.memory, &mut self.state.current_state, new_status);
+        self.state.update_status(&mut self.memory, new_status);
     }
 }
 
@@ -694,6 +695,15 @@ impl CoreInterface for Armv7m<'_> {
 
             let mut reason = dfsr.halt_reason();
 
+            if reason == HaltReason::Watchpoint {
+                reason = super::cortex_m::watchpoint_halt_reason(&mut *self.memory).unwrap_or_else(
+                    |error| {
+                        tracing::debug!("Unable to identify the matched DWT comparator: {error}");
+                        HaltReason::Watchpoint
+                    },
+                );
+            }
+
             // Clear bits from Dfsr register
             self.memory
                 .write_word_32(Dfsr::get_mmio_address(), Dfsr::clear_all().into())?;
@@ -714,18 +724,22 @@ impl CoreInterface for Armv7m<'_> {
                     &self.state.current_state,
                     &reason
                 );
+                tracing::trace!("Status history: {:?}", self.state.status_history());
             }
 
             // Set the status so any semihosting operations will know we're halted
             self.set_core_status(CoreStatus::Halted(reason));
 
             if let HaltReason::Breakpoint(_) = reason {
-                self.state.semihosting_command = super::cortex_m::check_for_semihosting(
+                let (command, instruction) = super::cortex_m::check_for_semihosting(
                     self.state.semihosting_command.take(),
                     self,
                 )?;
-                if let Some(command) = self.state.semihosting_command {
+                self.state.set_semihosting_command(command);
+                if let Some(command) = command {
                     reason = HaltReason::Breakpoint(BreakpointCause::Semihosting(command));
+                } else if reason == HaltReason::Breakpoint(BreakpointCause::Unknown) {
+                    reason = HaltReason::Breakpoint(super::cortex_m::breakpoint_cause(instruction));
                 }
 
                 // Set it again if it's changed
@@ -745,9 +759,7 @@ impl CoreInterface for Armv7m<'_> {
         Ok(CoreStatus::Running)
     }
 
-    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
-        // TODO: Generic halt support
-
+    fn request_halt(&mut self) -> Result<(), Error> {
         let mut value = Dhcsr(0);
         value.set_c_halt(true);
         value.set_c_debugen(true);
@@ -755,7 +767,15 @@ impl CoreInterface for Armv7m<'_> {
 
         self.memory
             .write_word_32(Dhcsr::get_mmio_address(), value.into())?;
+        self.memory.flush()?;
+
+        Ok(())
+    }
+
+    fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error> {
+        // TODO: Generic halt support
 
+        self.request_halt()?;
         self.wait_for_core_halted(timeout)?;
 
         // try to read the program counter
@@ -797,7 +817,7 @@ impl CoreInterface for Armv7m<'_> {
     }
 
     fn reset(&mut self) -> Result<(), Error> {
-        self.state.semihosting_command = None;
+        self.state.set_semihosting_command(None);
 
         self.sequence
             .reset_system(&mut *self.memory, crate::CoreType::Armv7m, None)?;
@@ -893,7 +913,7 @@ impl CoreInterface for Armv7m<'_> {
             self.enable_breakpoints(true)?;
         }
 
-        self.state.semihosting_command = None;
+        self.state.set_semihosting_command(None);
 
         Ok(CoreInformation {
             pc: pc_after_step.try_into()?,
@@ -1148,6 +1168,46 @@ impl CoreInterface for Armv7m<'_> {
             .write_word_32(Demcr::get_mmio_address(), demcr.into())?;
         Ok(())
     }
+
+    fn data_watchpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(super::cortex_m::data_watchpoints(&mut *self.memory)?)
+    }
+
+    fn set_data_watchpoint(
+        &mut self,
+        unit_index: usize,
+        address: u64,
+        size: u64,
+        access: WatchAccess,
+    ) -> Result<(), Error> {
+        let address = valid_32bit_address(address)?;
+
+        super::cortex_m::set_data_watchpoint(
+            &mut *self.memory,
+            CoreType::Armv7m,
+            unit_index,
+            address,
+            size,
+            access,
+        )?;
+
+        Ok(())
+    }
+
+    fn clear_data_watchpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        Ok(super::cortex_m::clear_data_watchpoint(
+            &mut *self.memory,
+            unit_index,
+        )?)
+    }
+
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.state.semihosting_command()
+    }
+
+    fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        Ok(super::cortex_m::sample_pc(&mut *self.memory)?)
+    }
 }
 
 impl CoreMemoryInterface for Armv7m<'_> {
@@ -1,7 +1,7 @@
 //! Register types and the core interface for armv8-a
 
 use super::{
-    CortexAState,
+    CortexAState, HaltReasonDecoder,
     instructions::{
         aarch64,
         thumb2::{build_ldr, build_mcr, build_mrc, build_str, build_vmov, build_vmrs},
@@ -244,10 +244,10 @@ impl<'probe> Armv8a<'probe> {
         if self.state.is_64_bit {
             // 31 general purpose regs, SP, PC, PSR, 31 FP registers, FPSR, FPCR
             // Numbers match what GDB defines for aarch64
-            self.state.register_cache = vec![None; 68];
+            self.state.invalidate_register_cache(68);
         } else {
             // 16 general purpose regs, CPSR, 32 FP registers, FPSR
-            self.state.register_cache = vec![None; 50];
+            self.state.invalidate_register_cache(50);
         }
     }
 
@@ -364,6 +364,7 @@ impl<'probe> Armv8a<'probe> {
             self.writeback_registers_aarch32()?;
         }
 
+        // The core is about to run, so the cached values will be stale.
         self.reset_register_cache();
 
         Ok(())
@@ -1056,7 +1057,14 @@ impl CoreInterface for Armv8a<'_> {
             let reason = edscr.halt_reason();
 
             self.set_core_status(CoreStatus::Halted(reason));
-            self.state.is_64_bit = edscr.currently_64_bit();
+
+            // The register layout depends on the execution state, so a cache sized for the
+            // other state can't be used.
+            let is_64_bit = edscr.currently_64_bit();
+            if is_64_bit != self.state.is_64_bit {
+                self.state.is_64_bit = is_64_bit;
+                self.reset_register_cache();
+            }
 
             return Ok(CoreStatus::Halted(reason));
         }
@@ -2294,6 +2302,41 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn armv8a_register_cache_follows_execution_state() {
+        let mut probe = MockProbe::new(false);
+        let mut state = CortexAState::new();
+
+        // Add expectations
+        add_status_expectations(&mut probe, true);
+
+        // Halted again, now in AArch64 state
+        let mut edscr = Edscr(0);
+        edscr.set_status(0b010011);
+        edscr.set_rw(0b1111);
+        probe.expected_read(
+            Edscr::get_mmio_address_from_base(TEST_BASE_ADDRESS).unwrap(),
+            edscr.into(),
+        );
+
+        let mock_mem = Box::new(probe) as _;
+
+        let mut armv8a = Armv8a::new(
+            mock_mem,
+            &mut state,
+            TEST_BASE_ADDRESS,
+            TEST_CTI_ADDRESS,
+            DefaultArmSequence::create(),
+        )
+        .unwrap();
+        assert_eq!(armv8a.state.register_cache.len(), 50);
+
+        armv8a.status().unwrap();
+
+        assert!(armv8a.state.is_64_bit);
+        assert_eq!(armv8a.state.register_cache.len(), 68);
+    }
+
     #[test]
     fn armv8a_status_running() {
         let mut probe = MockProbe::new(false);
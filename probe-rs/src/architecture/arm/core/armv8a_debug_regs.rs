@@ -1,5 +1,6 @@
 //! Debug register definitions for ARMv8-A
 
+use super::HaltReasonDecoder;
 use crate::{HaltReason, core::BreakpointCause, memory_mapped_bitfield_register};
 
 memory_mapped_bitfield_register! {
@@ -114,9 +115,11 @@ impl Edscr {
             _ => false,
         }
     }
+}
 
+impl HaltReasonDecoder for Edscr {
     /// Decode the MOE register into HaltReason
-    pub fn halt_reason(&self) -> HaltReason {
+    fn halt_reason(&self) -> HaltReason {
         match self.status() {
             // Breakpoint debug event
             // TODO: The DBGDSCR register will contain information about whether this was a Software or Hardware breakpoint.
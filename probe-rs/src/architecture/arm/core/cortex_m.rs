@@ -1,9 +1,10 @@
 //! Common functions and data types for Cortex-M core variants
 
 use crate::{
-    CoreInterface, Error, MemoryMappedRegister,
-    architecture::arm::{ArmError, memory::ArmMemoryInterface},
+    BreakpointCause, CoreInterface, CoreType, Error, HaltReason, MemoryMappedRegister, WatchAccess,
+    architecture::arm::{ArmError, component::dwt, memory::ArmMemoryInterface},
     core::RegisterId,
+    memory::MemoryNotAlignedError,
     memory_mapped_bitfield_register,
     semihosting::SemihostingCommand,
     semihosting::decode_semihosting_syscall,
@@ -62,6 +63,38 @@ memory_mapped_bitfield_register! {
     impl From;
 }
 
+memory_mapped_bitfield_register! {
+    /// DWT Program Counter Sample Register, DWT_PCSR (see armv7-M Architecture Reference Manual C1.8.9)
+    pub struct Pcsr(u32);
+    0xE000_101C, "DWT_PCSR",
+    impl From;
+    pub eiasample, _: 31, 0;
+}
+
+/// Base address of the DWT on Cortex-M cores.
+const DWT_BASE: u64 = 0xE000_1000;
+
+/// Address of the Debug Exception and Monitor Control Register, DEMCR.
+const DEMCR: u64 = 0xE000_EDFC;
+
+/// The `TRCENA` bit of DEMCR, which enables the DWT. Called `DWTENA` on ARMv6-M.
+const DEMCR_TRCENA: u32 = 1 << 24;
+
+/// The distance between the registers of two consecutive DWT comparators.
+const DWT_COMPARATOR_STRIDE: u64 = 0x10;
+
+/// Address of the DWT register `R` of comparator `comparator`.
+fn dwt_comparator_register<R: MemoryMappedRegister<u32>>(comparator: usize) -> u64 {
+    DWT_BASE + R::ADDRESS_OFFSET + comparator as u64 * DWT_COMPARATOR_STRIDE
+}
+
+/// Reads the DWT_CTRL register.
+fn dwt_ctrl(memory: &mut dyn ArmMemoryInterface) -> Result<dwt::Ctrl, ArmError> {
+    Ok(dwt::Ctrl::from(
+        memory.read_word_32(DWT_BASE + dwt::Ctrl::ADDRESS_OFFSET)?,
+    ))
+}
+
 memory_mapped_bitfield_register! {
     ///  Coprocessor Access Control Register
     pub struct Cpacr(u32);
@@ -152,6 +185,163 @@ impl IdPfr1 {
     }
 }
 
+/// Samples the program counter through DWT_PCSR without halting the core.
+///
+/// Returns `None` if PC sampling is not implemented, which reads as zero, or if the
+/// sample is not available, e.g. because the core is halted.
+pub(crate) fn sample_pc(memory: &mut dyn ArmMemoryInterface) -> Result<Option<u64>, ArmError> {
+    let pcsr = Pcsr(memory.read_word_32(Pcsr::get_mmio_address())?);
+
+    match pcsr.eiasample() {
+        0 | 0xFFFF_FFFF => Ok(None),
+        pc => Ok(Some(pc as u64)),
+    }
+}
+
+/// Checks whether the core implements a DWT.
+///
+/// The DWT is optional on ARMv6-M. Without it, DWT_CTRL reads as zero or cannot be read at
+/// all, while an implemented DWT always has at least one comparator.
+pub(crate) fn dwt_present(memory: &mut dyn ArmMemoryInterface) -> bool {
+    dwt_ctrl(memory).is_ok_and(|ctrl| ctrl.numcomp() != 0)
+}
+
+/// Identifies the DWT comparator which caused a watchpoint halt.
+///
+/// Returns [`HaltReason::WatchpointMatch`] for the first comparator with the `MATCHED` bit set,
+/// or [`HaltReason::Watchpoint`] if the core has no DWT comparators, or none of them matched.
+///
+/// Reading `DWT_FUNCTION` clears the `MATCHED` bit, so this only works once per halt.
+pub(crate) fn watchpoint_halt_reason(
+    memory: &mut dyn ArmMemoryInterface,
+) -> Result<HaltReason, ArmError> {
+    let ctrl = dwt_ctrl(memory)?;
+
+    for comparator in 0..ctrl.numcomp() as usize {
+        let function = dwt::Function::from(
+            memory.read_word_32(dwt_comparator_register::<dwt::Function>(comparator))?,
+        );
+        if function.matched() {
+            let address = memory.read_word_32(dwt_comparator_register::<dwt::Comp>(comparator))?;
+
+            return Ok(HaltReason::WatchpointMatch {
+                comparator,
+                address: address as u64,
+            });
+        }
+    }
+
+    Ok(HaltReason::Watchpoint)
+}
+
+/// Reads the addresses watched by all DWT comparators.
+///
+/// Comparators which are disabled are returned as `None`.
+pub(crate) fn data_watchpoints(
+    memory: &mut dyn ArmMemoryInterface,
+) -> Result<Vec<Option<u64>>, ArmError> {
+    let ctrl = dwt_ctrl(memory)?;
+
+    (0..ctrl.numcomp() as usize)
+        .map(|comparator| {
+            let function = dwt::Function::from(
+                memory.read_word_32(dwt_comparator_register::<dwt::Function>(comparator))?,
+            );
+            if function.function() == 0 {
+                return Ok(None);
+            }
+
+            Ok(Some(
+                memory.read_word_32(dwt_comparator_register::<dwt::Comp>(comparator))? as u64,
+            ))
+        })
+        .collect()
+}
+
+/// Programs DWT comparator `comparator` to halt the core on `access` to the `size` bytes at
+/// `address`.
+///
+/// `size` has to be a power of two and `address` has to be aligned to it. ARMv8-M only supports
+/// watching up to 4 bytes, on ARMv6-M and ARMv7-M the maximum size depends on the implementation.
+pub(crate) fn set_data_watchpoint(
+    memory: &mut dyn ArmMemoryInterface,
+    core_type: CoreType,
+    comparator: usize,
+    address: u32,
+    size: u64,
+    access: WatchAccess,
+) -> Result<(), ArmError> {
+    let max_size = match core_type {
+        CoreType::Armv8m => 4,
+        _ => u32::MAX as u64,
+    };
+    if !size.is_power_of_two() || size > max_size {
+        return Err(ArmError::UnsupportedWatchpointSize(size));
+    }
+    if address as u64 % size != 0 {
+        return Err(MemoryNotAlignedError {
+            address: address as u64,
+            alignment: size as usize,
+        }
+        .into());
+    }
+
+    let ctrl = dwt_ctrl(memory)?;
+    if comparator >= ctrl.numcomp() as usize {
+        return Err(ArmError::OutOfBounds);
+    }
+
+    let function_address = dwt_comparator_register::<dwt::Function>(comparator);
+    let mask_address = dwt_comparator_register::<dwt::Mask>(comparator);
+    let size_log2 = size.trailing_zeros();
+
+    let demcr = memory.read_word_32(DEMCR)?;
+    memory.write_word_32(DEMCR, demcr | DEMCR_TRCENA)?;
+
+    // Disable the comparator while it is being reprogrammed.
+    memory.write_word_32(function_address, 0)?;
+    memory.write_word_32(dwt_comparator_register::<dwt::Comp>(comparator), address)?;
+
+    let mut function = dwt::Function::from(0);
+    if core_type == CoreType::Armv8m {
+        // Generate a debug event on a data address match.
+        function.set_action(0b01);
+        function.set_datavsize(size_log2 as u8);
+        function.set_function(match access {
+            WatchAccess::ReadWrite => 0b0100,
+            WatchAccess::Write => 0b0101,
+            WatchAccess::Read => 0b0110,
+        });
+    } else {
+        // The mask register only implements as many bits as the hardware supports.
+        memory.write_word_32(mask_address, size_log2)?;
+        if memory.read_word_32(mask_address)? != size_log2 {
+            return Err(ArmError::UnsupportedWatchpointSize(size));
+        }
+
+        function.set_function(match access {
+            WatchAccess::Read => 0b0101,
+            WatchAccess::Write => 0b0110,
+            WatchAccess::ReadWrite => 0b0111,
+        });
+    }
+
+    memory.write_word_32(function_address, function.into())?;
+
+    Ok(())
+}
+
+/// Disables DWT comparator `comparator`.
+pub(crate) fn clear_data_watchpoint(
+    memory: &mut dyn ArmMemoryInterface,
+    comparator: usize,
+) -> Result<(), ArmError> {
+    memory.write_word_32(dwt_comparator_register::<dwt::Function>(comparator), 0)?;
+    memory.write_word_32(dwt_comparator_register::<dwt::Comp>(comparator), 0)?;
+
+    Ok(())
+}
+
 pub(crate) fn read_core_reg(
     memory: &mut dyn ArmMemoryInterface,
     addr: RegisterId,
@@ -192,10 +382,13 @@ pub(crate) fn write_core_reg(
 /// Check if the current breakpoint is a semihosting call.
 ///
 /// Call this if you get some kind of breakpoint. Works on ARMv6-M, ARMv7-M and ARMv8-M.
+///
+/// The instruction at the PC is returned as well, if it was read, so that it can be passed
+/// on to [`breakpoint_cause`].
 pub(crate) fn check_for_semihosting(
     cached_command: Option<SemihostingCommand>,
     core: &mut dyn CoreInterface,
-) -> Result<Option<SemihostingCommand>, Error> {
+) -> Result<(Option<SemihostingCommand>, Option<u16>), Error> {
     // The Arm Semihosting Specification, specificies that the instruction
     // "BKPT 0xAB" (encoded as 0xBEAB) triggers a semihosting call.
     // <https://github.com/ARM-software/abi-aa/blob/main/semihosting/semihosting.rst#the-semihosting-interface>
@@ -206,7 +399,7 @@ pub(crate) fn check_for_semihosting(
 
     // We only want to decode the semihosting command once, since answering it might change some of the registers
     if let Some(command) = cached_command {
-        return Ok(Some(command));
+        return Ok((Some(command), None));
     }
 
     let pc: u32 = core.read_core_reg(core.program_counter().id)?.try_into()?;
@@ -226,8 +419,25 @@ pub(crate) fn check_for_semihosting(
     } else {
         None
     };
+    let instruction = u16::from_le_bytes([actual_instruction[0], actual_instruction[1]]);
 
-    Ok(command)
+    Ok((command, Some(instruction)))
+}
+
+/// Distinguish a software from a hardware breakpoint, after the core halted on a breakpoint.
+///
+/// The DFSR does not tell them apart, so the `instruction` at the PC, as returned by
+/// [`check_for_semihosting`], is checked instead: a `BKPT` instruction is a software
+/// breakpoint, any other instruction was matched by the FPB.
+pub(crate) fn breakpoint_cause(instruction: Option<u16>) -> BreakpointCause {
+    // BKPT is encoded as 0xBExx, where xx is the immediate.
+    const BKPT_OPCODE: u16 = 0xBE00;
+
+    match instruction {
+        Some(instruction) if instruction & 0xFF00 == BKPT_OPCODE => BreakpointCause::Software,
+        Some(_) => BreakpointCause::Hardware,
+        None => BreakpointCause::Unknown,
+    }
 }
 
 fn wait_for_core_register_transfer(
@@ -247,3 +457,206 @@ fn wait_for_core_register_transfer(
     }
     Err(ArmError::Timeout)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{memory::MemoryInterface, test::FakeArmMemory};
+
+    /// A DWT with `count` disabled comparators.
+    fn dwt_with_comparators(count: u32) -> FakeArmMemory {
+        let mut memory = FakeArmMemory::new();
+        memory.add_word_range(DWT_BASE, &[count << 28]);
+        for comparator in 0..count as u64 {
+            // COMP, MASK and FUNCTION
+            memory.add_word_range(0xE000_1020 + comparator * DWT_COMPARATOR_STRIDE, &[0; 3]);
+        }
+        memory.add_word_range(DEMCR, &[0]);
+
+        memory
+    }
+
+    #[test]
+    fn watchpoint_halt_reason_finds_matched_comparator() {
+        // Four comparators, the third one matched.
+        let mut memory = dwt_with_comparators(4);
+        memory.add_word_range(0xE000_1040, &[0x2000_0100]);
+        memory.add_word_range(0xE000_1048, &[(1 << 24) | 0b0101]);
+
+        assert_eq!(
+            watchpoint_halt_reason(&mut memory).unwrap(),
+            HaltReason::WatchpointMatch {
+                comparator: 2,
+                address: 0x2000_0100
+            }
+        );
+    }
+
+    #[test]
+    fn set_data_watchpoint_for_word_write() {
+        let mut memory = dwt_with_comparators(4);
+
+        set_data_watchpoint(
+            &mut memory,
+            CoreType::Armv7m,
+            1,
+            0x2000_0104,
+            4,
+            WatchAccess::Write,
+        )
+        .unwrap();
+
+        // COMP1, MASK1 and FUNCTION1
+        let mut registers = [0; 3];
+        memory.read_32(0xE000_1030, &mut registers).unwrap();
+        assert_eq!(registers, [0x2000_0104, 2, 0b0110]);
+        assert_eq!(memory.read_word_32(DEMCR).unwrap(), 1 << 24);
+
+        assert_eq!(
+            data_watchpoints(&mut memory).unwrap(),
+            vec![None, Some(0x2000_0104), None, None]
+        );
+
+        clear_data_watchpoint(&mut memory, 1).unwrap();
+        assert_eq!(data_watchpoints(&mut memory).unwrap(), vec![None; 4]);
+    }
+
+    #[test]
+    fn set_data_watchpoint_on_armv8m() {
+        let mut memory = dwt_with_comparators(4);
+
+        set_data_watchpoint(
+            &mut memory,
+            CoreType::Armv8m,
+            0,
+            0x2000_0102,
+            2,
+            WatchAccess::Read,
+        )
+        .unwrap();
+
+        let function = dwt::Function::from(memory.read_word_32(0xE000_1028).unwrap());
+        assert_eq!(function.action(), 0b01);
+        assert_eq!(function.datavsize(), 1);
+        assert_eq!(function.function(), 0b0110);
+        // ARMv8-M has no mask register.
+        assert_eq!(memory.read_word_32(0xE000_1024).unwrap(), 0);
+
+        let result = set_data_watchpoint(
+            &mut memory,
+            CoreType::Armv8m,
+            0,
+            0x2000_0100,
+            8,
+            WatchAccess::Read,
+        );
+        assert!(matches!(
+            result,
+            Err(ArmError::UnsupportedWatchpointSize(8))
+        ));
+    }
+
+    #[test]
+    fn set_data_watchpoint_rejects_unaligned_address() {
+        let mut memory = dwt_with_comparators(4);
+
+        let result = set_data_watchpoint(
+            &mut memory,
+            CoreType::Armv7m,
+            0,
+            0x2000_0102,
+            4,
+            WatchAccess::Write,
+        );
+
+        assert!(matches!(result, Err(ArmError::MemoryNotAligned(_))));
+    }
+
+    #[test]
+    fn watchpoint_halt_reason_without_comparators() {
+        let mut memory = dwt_with_comparators(0);
+
+        assert_eq!(
+            watchpoint_halt_reason(&mut memory).unwrap(),
+            HaltReason::Watchpoint
+        );
+    }
+
+    #[test]
+    fn sample_pc_reads_dwt_pcsr() {
+        let mut memory = FakeArmMemory::new();
+
+        memory.add_word_range(0xE000_101C, &[0x0800_1234]);
+        assert_eq!(sample_pc(&mut memory).unwrap(), Some(0x0800_1234));
+
+        // Reads as zero if PC sampling is not implemented.
+        memory.add_word_range(0xE000_101C, &[0]);
+        assert_eq!(sample_pc(&mut memory).unwrap(), None);
+
+        // Reads as all ones while the core is halted.
+        memory.add_word_range(0xE000_101C, &[0xFFFF_FFFF]);
+        assert_eq!(sample_pc(&mut memory).unwrap(), None);
+    }
+
+    #[test]
+    fn dwt_present_checks_dwt_ctrl() {
+        // DWT_CTRL cannot be read.
+        assert!(!dwt_present(&mut FakeArmMemory::new()));
+
+        // DWT_CTRL reads as zero.
+        assert!(!dwt_present(&mut dwt_with_comparators(0)));
+
+        assert!(dwt_present(&mut dwt_with_comparators(2)));
+    }
+
+    #[test]
+    fn breakpoint_cause_from_instruction() {
+        use crate::{architecture::arm::core::registers::cortex_m::PC, test::FakeCore};
+
+        let mut core = FakeCore::new();
+        core.set_register(PC.id(), 0x1000);
+        // bkpt #0, followed by a nop
+        core.add_range(0x1000, &[0x00, 0xBE, 0x00, 0xBF]);
+
+        let (command, instruction) = check_for_semihosting(None, &mut core).unwrap();
+        assert!(command.is_none());
+        assert_eq!(instruction, Some(0xBE00));
+        assert_eq!(breakpoint_cause(instruction), BreakpointCause::Software);
+
+        // A nop can only be halted on by an FPB comparator.
+        core.set_register(PC.id(), 0x1002);
+        let (_, instruction) = check_for_semihosting(None, &mut core).unwrap();
+        assert_eq!(breakpoint_cause(instruction), BreakpointCause::Hardware);
+    }
+
+    #[test]
+    fn default_reset_catch_uses_vc_corereset() {
+        use crate::architecture::arm::{
+            core::armv7m::Demcr,
+            sequences::{ArmDebugSequence, DefaultArmSequence},
+        };
+
+        let mut memory = FakeArmMemory::new();
+        memory.add_word_range(Dhcsr::get_mmio_address(), &[0]);
+        // An unrelated vector catch, which has to be preserved.
+        let mut demcr = Demcr(0);
+        demcr.set_vc_harderr(true);
+        memory.add_word_range(Demcr::get_mmio_address(), &[demcr.into()]);
+
+        let sequence = DefaultArmSequence(());
+
+        sequence
+            .reset_catch_set(&mut memory, CoreType::Armv7m, None)
+            .unwrap();
+        let demcr = Demcr(memory.read_word_32(Demcr::get_mmio_address()).unwrap());
+        assert!(demcr.vc_corereset());
+        assert!(demcr.vc_harderr());
+
+        sequence
+            .reset_catch_clear(&mut memory, CoreType::Armv7m, None)
+            .unwrap();
+        let demcr = Demcr(memory.read_word_32(Demcr::get_mmio_address()).unwrap());
+        assert!(!demcr.vc_corereset());
+        assert!(demcr.vc_harderr());
+    }
+}
@@ -1,5 +1,7 @@
 //! The different ARM core implementations with all constants and custom handling.
 
+use std::{collections::VecDeque, time::Instant};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -28,8 +30,27 @@ pub mod registers;
 pub struct Dump {
     /// The register values at the time of the dump.
     pub regs: [u32; 16],
+    /// The floating point register values at the time of the dump, if the core has an FPU.
+    ///
+    /// These are the values of `S0` to `S31`, followed by `FPSCR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fp_regs: Option<Vec<u32>>,
     stack_addr: u32,
     stack: Vec<u8>,
+    /// Memory regions captured in addition to the stack.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    regions: Vec<DumpRegion>,
+}
+
+/// A named memory region of a [`Dump`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpRegion {
+    /// A name describing the region.
+    pub name: String,
+    /// The address of the first byte of the region.
+    pub address: u64,
+    /// The dumped memory.
+    pub data: Vec<u8>,
 }
 
 impl Dump {
@@ -37,10 +58,118 @@ impl Dump {
     pub fn new(stack_addr: u32, stack: Vec<u8>) -> Dump {
         Dump {
             regs: [0u32; 16],
+            fp_regs: None,
             stack_addr,
             stack,
+            regions: vec![],
+        }
+    }
+
+    /// Create a new dump from a SP, a stack dump and a snapshot of the floating point registers,
+    /// with zeroed out core registers.
+    ///
+    /// `fp_regs` contains the values of `S0` to `S31`, followed by `FPSCR`.
+    pub fn with_fp_registers(stack_addr: u32, stack: Vec<u8>, fp_regs: Vec<u32>) -> Dump {
+        Dump {
+            fp_regs: Some(fp_regs),
+            ..Dump::new(stack_addr, stack)
         }
     }
+
+    /// The address of the first byte of the stack dump, i.e. the stack pointer at the time of the dump.
+    pub fn stack_addr(&self) -> u32 {
+        self.stack_addr
+    }
+
+    /// The dumped stack memory.
+    pub fn stack(&self) -> &[u8] {
+        &self.stack
+    }
+
+    /// Add a memory region `data`, starting at `address`, to the dump.
+    pub fn add_region(&mut self, name: impl Into<String>, address: u64, data: Vec<u8>) {
+        self.regions.push(DumpRegion {
+            name: name.into(),
+            address,
+            data,
+        });
+    }
+
+    /// The memory regions captured in addition to the stack.
+    pub fn regions(&self) -> &[DumpRegion] {
+        &self.regions
+    }
+
+    /// Decode the exception frame stacked by a Cortex-M core on exception entry.
+    ///
+    /// The link register has to contain an `EXC_RETURN` value, and the frame is expected at the
+    /// start of the dumped stack, i.e. the dump has to be captured before the exception handler
+    /// modified the stack, e.g. by halting on exception entry. Returns `None` if this is not the
+    /// case, if the frame was stacked on the process stack, or if the stack dump is too short.
+    pub fn exception_frame(&self) -> Option<ExceptionFrame> {
+        let exc_return = self.regs[14];
+        if exc_return & 0xFF00_0000 != 0xFF00_0000 {
+            return None;
+        }
+
+        // SPSEL: the frame was stacked on the process stack, which is not the current stack.
+        if exc_return & (1 << 2) != 0 {
+            return None;
+        }
+
+        // FType: the frame is extended with the floating point context if the bit is clear.
+        let extended = exc_return & (1 << 4) == 0;
+        let frame_size = if extended { 0x68 } else { 0x20 };
+
+        let words: Vec<u32> = self
+            .stack
+            .get(..0x20)?
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        let xpsr = words[7];
+
+        // Bit 9 of the stacked xPSR signals that the stack was realigned by an additional word.
+        let padding = if xpsr & (1 << 9) != 0 { 4 } else { 0 };
+
+        Some(ExceptionFrame {
+            r0: words[0],
+            r1: words[1],
+            r2: words[2],
+            r3: words[3],
+            r12: words[4],
+            lr: words[5],
+            pc: words[6],
+            xpsr,
+            extended,
+            stack_pointer: self.stack_addr.wrapping_add(frame_size + padding),
+        })
+    }
+}
+
+/// The registers which a Cortex-M core stacks on exception entry, see [`Dump::exception_frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionFrame {
+    /// The value of `R0` when the exception was taken.
+    pub r0: u32,
+    /// The value of `R1` when the exception was taken.
+    pub r1: u32,
+    /// The value of `R2` when the exception was taken.
+    pub r2: u32,
+    /// The value of `R3` when the exception was taken.
+    pub r3: u32,
+    /// The value of `R12` when the exception was taken.
+    pub r12: u32,
+    /// The value of the link register when the exception was taken.
+    pub lr: u32,
+    /// The return address of the exception, which is the faulting instruction for most faults.
+    pub pc: u32,
+    /// The value of `xPSR` when the exception was taken.
+    pub xpsr: u32,
+    /// Whether the frame is extended with the floating point registers `S0` to `S15` and `FPSCR`.
+    pub extended: bool,
+    /// The stack pointer before the frame was stacked.
+    pub stack_pointer: u32,
 }
 
 memory_mapped_bitfield_register! {
@@ -75,40 +204,66 @@ impl Dfsr {
         Dfsr(0b11111)
     }
 
-    /// This only returns the correct halt_reason for armv(x)-m variants. The armv(x)-a variants have their own implementation.
-    // TODO: The different implementations between -m and -a can do with cleanup/refactoring.
+    /// Decode every debug event flagged in the register into its [`HaltReason`].
+    ///
+    /// The reasons are ordered by the priority used in [`Dfsr::halt_reason`], so a breakpoint
+    /// always comes first.
+    ///
+    /// This only returns the correct reasons for armv(x)-m variants.
+    pub fn active_reasons(&self) -> Vec<HaltReason> {
+        let mut reasons = Vec::new();
+
+        if self.bkpt() {
+            reasons.push(HaltReason::Breakpoint(BreakpointCause::Unknown));
+        }
+        if self.external() {
+            reasons.push(HaltReason::External);
+        }
+        if self.dwttrap() {
+            reasons.push(HaltReason::Watchpoint);
+        }
+        if self.halted() {
+            reasons.push(HaltReason::Request);
+        }
+        if self.vcatch() {
+            reasons.push(HaltReason::Exception);
+        }
+
+        reasons
+    }
+}
+
+/// Decode the reason for a halt from the debug status register of a core.
+///
+/// M-profile cores report the debug events in [`Dfsr`], while A-profile cores report them in
+/// `DBGDSCR` (ARMv7-A) or `EDSCR` (ARMv8-A).
+pub(crate) trait HaltReasonDecoder {
+    /// Decode the reason why the core halted.
+    fn halt_reason(&self) -> HaltReason;
+}
+
+impl HaltReasonDecoder for Dfsr {
     fn halt_reason(&self) -> HaltReason {
-        if self.0 == 0 {
+        match self.active_reasons().as_slice() {
             // No bit is set
-            HaltReason::Unknown
-        } else if self.0.count_ones() > 1 {
-            tracing::debug!("DFSR: {:?}", self);
-
-            // We cannot identify why the chip halted,
-            // it could be for multiple reasons.
-
-            // For debuggers, it's important to know if
-            // the core halted because of a breakpoint.
-            // Because of this, we still return breakpoint
-            // even if other reasons are possible as well.
-            if self.bkpt() {
-                HaltReason::Breakpoint(BreakpointCause::Unknown)
-            } else {
-                HaltReason::Multiple
+            [] => HaltReason::Unknown,
+            [reason] => *reason,
+            [first, ..] => {
+                tracing::debug!("DFSR: {:?}", self);
+
+                // We cannot identify why the chip halted,
+                // it could be for multiple reasons.
+
+                // For debuggers, it's important to know if
+                // the core halted because of a breakpoint.
+                // Because of this, we still return breakpoint
+                // even if other reasons are possible as well.
+                if matches!(first, HaltReason::Breakpoint(_)) {
+                    *first
+                } else {
+                    HaltReason::Multiple
+                }
             }
-        } else if self.bkpt() {
-            HaltReason::Breakpoint(BreakpointCause::Unknown)
-        } else if self.external() {
-            HaltReason::External
-        } else if self.dwttrap() {
-            HaltReason::Watchpoint
-        } else if self.halted() {
-            HaltReason::Request
-        } else if self.vcatch() {
-            HaltReason::Exception
-        } else {
-            // We check that exactly one bit is set, so we should hit one of the cases above.
-            panic!("This should not happen. Please open a bug report.")
         }
     }
 }
@@ -128,6 +283,9 @@ impl From<Dfsr> for u32 {
     }
 }
 
+/// The number of status transitions kept in the history of a [`CortexMState`].
+const STATUS_HISTORY_LEN: usize = 16;
+
 /// The state cache of a Cortex-M core.
 ///
 /// This state is used internally to not having to poll the core constantly.
@@ -139,6 +297,9 @@ pub struct CortexMState {
 
     current_state: CoreStatus,
 
+    /// The last status transitions of the core, oldest first.
+    status_history: VecDeque<(Instant, CoreStatus)>,
+
     fp_present: bool,
 
     /// The semihosting command that was decoded at the current program counter
@@ -151,11 +312,38 @@ impl CortexMState {
             initialized: false,
             hw_breakpoints_enabled: false,
             current_state: CoreStatus::Unknown,
+            status_history: VecDeque::with_capacity(STATUS_HISTORY_LEN),
             fp_present: false,
             semihosting_command: None,
         }
     }
 
+    /// The last status transitions of the core, oldest first.
+    ///
+    /// At most [`STATUS_HISTORY_LEN`] transitions are kept.
+    pub(crate) fn status_history(&self) -> &VecDeque<(Instant, CoreStatus)> {
+        &self.status_history
+    }
+
+    fn record_status(&mut self, status: CoreStatus) {
+        if self.status_history.len() == STATUS_HISTORY_LEN {
+            self.status_history.pop_front();
+        }
+
+        self.status_history.push_back((Instant::now(), status));
+    }
+
+    /// Update the cached core status, and record the transition if it changed.
+    fn update_status<P: ArmMemoryInterface + ?Sized, T: core::ops::DerefMut<Target = P>>(
+        &mut self,
+        probe: &mut T,
+        new_status: CoreStatus,
+    ) {
+        if update_core_status(probe, &mut self.current_state, new_status) {
+            self.record_status(new_status);
+        }
+    }
+
     fn initialize(&mut self) {
         self.initialized = true;
     }
@@ -163,6 +351,15 @@ impl CortexMState {
     fn initialized(&self) -> bool {
         self.initialized
     }
+
+    /// The semihosting command which was decoded when the core last halted.
+    pub(crate) fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.semihosting_command
+    }
+
+    pub(crate) fn set_semihosting_command(&mut self, command: Option<SemihostingCommand>) {
+        self.semihosting_command = command;
+    }
 }
 
 /// The state cache of a Cortex-A core.
@@ -201,6 +398,15 @@ impl CortexAState {
     fn initialized(&self) -> bool {
         self.initialized
     }
+
+    /// Forgets all cached register values, so that they are read from the core again, and sizes
+    /// the cache for `register_count` registers.
+    ///
+    /// Modified registers are discarded, so they have to be written back to the core first.
+    fn invalidate_register_cache(&mut self, register_count: usize) {
+        self.register_cache.clear();
+        self.register_cache.resize(register_count, None);
+    }
 }
 
 /// Core implementations should call this function when they
@@ -209,13 +415,278 @@ impl CortexAState {
 /// It will reflect the core status to the probe/memory interface if
 /// the status has changed, and will replace `current_status` with
 /// `new_status`.
+///
+/// Returns `true` if the status has changed.
 pub fn update_core_status<P: ArmMemoryInterface + ?Sized, T: core::ops::DerefMut<Target = P>>(
     probe: &mut T,
     current_status: &mut CoreStatus,
     new_status: CoreStatus,
-) {
-    if *current_status != new_status {
-        probe.deref_mut().update_core_status(new_status);
+) -> bool {
+    if *current_status == new_status {
+        return false;
+    }
+
+    if current_status.is_running() && new_status.is_halted() {
+        tracing::debug!("Core halted: {:?} -> {:?}", current_status, new_status);
     }
+
+    probe.deref_mut().update_core_status(new_status);
     *current_status = new_status;
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CortexMState, Dfsr, Dump, DumpRegion, ExceptionFrame, HaltReasonDecoder,
+        STATUS_HISTORY_LEN, armv7a_debug_regs::Dbgdscr, armv8a_debug_regs::Edscr,
+        update_core_status,
+    };
+    use crate::{CoreStatus, HaltReason, core::BreakpointCause, test::FakeArmMemory};
+
+    #[test]
+    fn update_core_status_notifies_on_change() {
+        let mut probe = Box::new(FakeArmMemory::new());
+        let mut current_status = CoreStatus::Running;
+
+        let halted = CoreStatus::Halted(HaltReason::Request);
+
+        assert!(update_core_status(&mut probe, &mut current_status, halted));
+        assert_eq!(current_status, halted);
+
+        // Same status again, no notification.
+        assert!(!update_core_status(&mut probe, &mut current_status, halted));
+
+        assert!(update_core_status(
+            &mut probe,
+            &mut current_status,
+            CoreStatus::Running
+        ));
+
+        assert_eq!(
+            probe.core_status_notifications(),
+            [halted, CoreStatus::Running]
+        );
+    }
+
+    #[test]
+    fn status_history_evicts_oldest() {
+        let mut state = CortexMState::new();
+        let mut probe = Box::new(FakeArmMemory::new());
+
+        let statuses: Vec<_> = (0..STATUS_HISTORY_LEN + 4)
+            .map(|comparator| {
+                CoreStatus::Halted(HaltReason::WatchpointMatch {
+                    comparator,
+                    address: 0x2000_0000,
+                })
+            })
+            .collect();
+        for &status in &statuses {
+            state.update_status(&mut probe, status);
+        }
+
+        // Setting the same status again is not a transition.
+        state.update_status(&mut probe, *statuses.last().unwrap());
+
+        let history = state.status_history();
+        let recorded: Vec<_> = history.iter().map(|&(_, status)| status).collect();
+        assert_eq!(recorded, statuses[4..]);
+        assert!(
+            history
+                .iter()
+                .zip(history.iter().skip(1))
+                .all(|(older, newer)| older.0 <= newer.0)
+        );
+    }
+
+    #[test]
+    fn dfsr_active_reasons() {
+        assert_eq!(Dfsr(0).active_reasons(), vec![]);
+        assert_eq!(
+            Dfsr(0b10010).active_reasons(),
+            vec![
+                HaltReason::Breakpoint(BreakpointCause::Unknown),
+                HaltReason::External
+            ]
+        );
+        assert_eq!(
+            Dfsr(0b00110).active_reasons(),
+            vec![
+                HaltReason::Breakpoint(BreakpointCause::Unknown),
+                HaltReason::Watchpoint
+            ]
+        );
+    }
+
+    #[test]
+    fn dfsr_halt_reason() {
+        assert_eq!(Dfsr(0).halt_reason(), HaltReason::Unknown);
+        assert_eq!(Dfsr(0b00100).halt_reason(), HaltReason::Watchpoint);
+        assert_eq!(
+            Dfsr(0b00110).halt_reason(),
+            HaltReason::Breakpoint(BreakpointCause::Unknown)
+        );
+        assert_eq!(Dfsr(0b01100).halt_reason(), HaltReason::Multiple);
+    }
+
+    #[test]
+    fn dbgdscr_halt_reason() {
+        // HALTED is bit 0, MOE bits 5 to 2.
+        let halted_with_moe = |moe: u32| Dbgdscr::from((moe << 2) | 1);
+
+        assert_eq!(halted_with_moe(0b0000).halt_reason(), HaltReason::Request);
+        assert_eq!(
+            halted_with_moe(0b0011).halt_reason(),
+            HaltReason::Breakpoint(BreakpointCause::Software)
+        );
+        assert_eq!(
+            halted_with_moe(0b1010).halt_reason(),
+            HaltReason::Watchpoint
+        );
+
+        // The MOE field is only valid while halted.
+        assert_eq!(
+            Dbgdscr::from(0b0011 << 2).halt_reason(),
+            HaltReason::Unknown
+        );
+    }
+
+    #[test]
+    fn edscr_halt_reason() {
+        assert_eq!(
+            Edscr::from(0b000111).halt_reason(),
+            HaltReason::Breakpoint(BreakpointCause::Unknown)
+        );
+        assert_eq!(Edscr::from(0b010011).halt_reason(), HaltReason::Request);
+        assert_eq!(Edscr::from(0b111011).halt_reason(), HaltReason::Step);
+        assert_eq!(
+            Edscr::from(0b101111).halt_reason(),
+            HaltReason::Breakpoint(BreakpointCause::Software)
+        );
+
+        // The PE is in Non-debug state.
+        assert_eq!(Edscr::from(0b000010).halt_reason(), HaltReason::Unknown);
+    }
+
+    #[test]
+    fn halt_reason_decoded_for_any_profile() {
+        let decoders: [&dyn HaltReasonDecoder; 3] =
+            [&Dfsr(0b00001), &Dbgdscr::from(1), &Edscr::from(0b010011)];
+
+        for decoder in decoders {
+            assert_eq!(decoder.halt_reason(), HaltReason::Request);
+        }
+    }
+
+    #[test]
+    fn dump_without_fp_registers_deserializes() {
+        let json = r#"{"regs":[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15],"stack_addr":536870912,"stack":[1,2,3,4]}"#;
+
+        let dump: Dump = serde_json::from_str(json).unwrap();
+
+        assert_eq!(dump.regs[15], 15);
+        assert_eq!(dump.fp_regs, None);
+        assert_eq!(dump.stack_addr(), 0x2000_0000);
+        assert_eq!(dump.stack(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dump_with_regions_roundtrips() {
+        let mut dump = Dump::new(0x2000_0000, vec![0xaa]);
+        dump.add_region("data", 0x2000_1000, vec![1, 2, 3, 4]);
+
+        let json = serde_json::to_string(&dump).unwrap();
+        let dump: Dump = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            dump.regions(),
+            [DumpRegion {
+                name: "data".to_string(),
+                address: 0x2000_1000,
+                data: vec![1, 2, 3, 4],
+            }]
+        );
+    }
+
+    #[test]
+    fn dump_with_fp_registers_roundtrips() {
+        let dump = Dump::with_fp_registers(0x2000_0000, vec![0xaa], vec![1; 33]);
+
+        let json = serde_json::to_string(&dump).unwrap();
+        let dump: Dump = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(dump.fp_regs, Some(vec![1; 33]));
+        assert_eq!(dump.stack(), &[0xaa]);
+    }
+
+    /// A dump with the stacked registers `R0`, `R1`, `R2`, `R3`, `R12`, `LR`, `PC` and `xPSR`,
+    /// followed by `extra_bytes` bytes of stack.
+    fn dump_with_frame(exc_return: u32, xpsr: u32, extra_bytes: usize) -> Dump {
+        let frame = [0, 1, 2, 3, 12, 0x0800_0101, 0x0800_0200, xpsr];
+        let mut stack: Vec<u8> = frame
+            .iter()
+            .flat_map(|word: &u32| word.to_le_bytes())
+            .collect();
+        stack.resize(stack.len() + extra_bytes, 0);
+
+        let mut dump = Dump::new(0x2000_0fe0, stack);
+        dump.regs[14] = exc_return;
+        dump
+    }
+
+    #[test]
+    fn basic_exception_frame() {
+        let dump = dump_with_frame(0xFFFF_FFF9, 0x0100_0003, 0);
+
+        assert_eq!(
+            dump.exception_frame(),
+            Some(ExceptionFrame {
+                r0: 0,
+                r1: 1,
+                r2: 2,
+                r3: 3,
+                r12: 12,
+                lr: 0x0800_0101,
+                pc: 0x0800_0200,
+                xpsr: 0x0100_0003,
+                extended: false,
+                stack_pointer: 0x2000_1000,
+            })
+        );
+
+        // The stack was realigned on exception entry.
+        let dump = dump_with_frame(0xFFFF_FFF9, 0x0100_0203, 0);
+        assert_eq!(dump.exception_frame().unwrap().stack_pointer, 0x2000_1004);
+    }
+
+    #[test]
+    fn extended_exception_frame() {
+        let dump = dump_with_frame(0xFFFF_FFE9, 0x0100_0003, 0x48);
+
+        let frame = dump.exception_frame().unwrap();
+        assert_eq!(frame.pc, 0x0800_0200);
+        assert_eq!(frame.lr, 0x0800_0101);
+        assert!(frame.extended);
+        assert_eq!(frame.stack_pointer, 0x2000_1048);
+    }
+
+    #[test]
+    fn no_exception_frame() {
+        // Not an EXC_RETURN value.
+        assert_eq!(
+            dump_with_frame(0x0800_0101, 0x0100_0003, 0).exception_frame(),
+            None
+        );
+        // The frame was stacked on the process stack.
+        assert_eq!(
+            dump_with_frame(0xFFFF_FFFD, 0x0100_0003, 0).exception_frame(),
+            None
+        );
+        // The dumped stack does not contain the frame.
+        let mut dump = dump_with_frame(0xFFFF_FFF9, 0x0100_0003, 0);
+        dump.stack.truncate(0x1c);
+        assert_eq!(dump.exception_frame(), None);
+    }
 }
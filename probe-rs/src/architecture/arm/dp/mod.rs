@@ -115,6 +115,21 @@ pub enum DebugPortError {
     /// An error occurred in the communication with an access port or debug port.
     #[error("An error occurred in the communication with an access port or debug port.")]
     Dap(#[from] DapError),
+
+    /// Sticky error flags are still set after writing the ABORT register.
+    #[error("Sticky error flags could not be cleared, CTRL/STAT is {0:#010x}")]
+    StickyErrorsNotCleared(u32),
+
+    /// The TARGETID of the debug port does not match the multidrop address it was selected with.
+    #[error(
+        "The debug port selected with TARGETSEL {targetsel:#010x} reports TARGETID {targetid:#010x}"
+    )]
+    TargetIdMismatch {
+        /// The `TARGETSEL` value of the multidrop address.
+        targetsel: u32,
+        /// The TARGETID read from the debug port.
+        targetid: u32,
+    },
 }
 /// A typed interface to be implemented on drivers that can control a debug port.
 pub trait DpAccess {
@@ -338,6 +353,30 @@ bitfield! {
 }
 impl_dpregister!(Ctrl, DebugPortVersion::DPv1, 0x4, "CTRL/STAT");
 
+/// The power-up request and acknowledge bits of the debug and system power domains, from CTRL/STAT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugPowerStatus {
+    /// Debug power-up is requested (CDBGPWRUPREQ).
+    pub debug_power_up_requested: bool,
+    /// The debug power domain is powered up (CDBGPWRUPACK).
+    pub debug_power_up_acknowledged: bool,
+    /// System power-up is requested (CSYSPWRUPREQ).
+    pub system_power_up_requested: bool,
+    /// The system power domain is powered up (CSYSPWRUPACK).
+    pub system_power_up_acknowledged: bool,
+}
+
+impl From<Ctrl> for DebugPowerStatus {
+    fn from(ctrl: Ctrl) -> Self {
+        DebugPowerStatus {
+            debug_power_up_requested: ctrl.cdbgpwrupreq(),
+            debug_power_up_acknowledged: ctrl.cdbgpwrupack(),
+            system_power_up_requested: ctrl.csyspwrupreq(),
+            system_power_up_acknowledged: ctrl.csyspwrupack(),
+        }
+    }
+}
+
 bitfield! {
     /// SELECT, AP Select register (see ADI v5.2 B2.2.9)
     #[derive(Clone, Copy, PartialEq, Eq)]
@@ -576,7 +576,9 @@ mod tests {
     use crate::{
         MemoryInterface,
         architecture::arm::{
-            FullyQualifiedApAddress, ap::memory_ap::mock::MockMemoryAp, memory::ADIMemoryInterface,
+            FullyQualifiedApAddress,
+            ap::memory_ap::mock::MockMemoryAp,
+            memory::{ADIMemoryInterface, ArmMemoryInterface},
         },
     };
 
@@ -869,6 +871,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn fill_32_in_multiple_chunks() {
+        let mut mock = MockMemoryAp::with_pattern_and_size(4096);
+        let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+        // More words than fit into a single chunk.
+        let word_count = 259;
+        let mut expected = Vec::from(mi.mock_memory());
+        expected[4..][..word_count * 4]
+            .copy_from_slice(&[0x00, 0x00, 0xad, 0xde].repeat(word_count));
+
+        mi.fill_32(4, 0xdead_0000, word_count)
+            .expect("fill_32 failed");
+
+        assert_eq!(mi.mock_memory(), expected.as_slice());
+    }
+
+    #[test]
+    fn fill_8_unaligned_length() {
+        for address in 0..4 {
+            let mut mock = MockMemoryAp::with_pattern_and_size(4096);
+            let mut mi = ADIMemoryInterface::new_mock(&mut mock);
+
+            let len = 1030;
+            let mut expected = Vec::from(mi.mock_memory());
+            expected[address as usize..][..len].fill(0);
+
+            mi.fill_8(address, 0, len)
+                .unwrap_or_else(|_| panic!("fill_8 failed, address = {address}"));
+
+            assert_eq!(mi.mock_memory(), expected.as_slice(), "address = {address}");
+        }
+    }
+
     #[test]
     fn write() {
         for address in 0..4 {
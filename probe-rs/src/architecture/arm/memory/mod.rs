@@ -11,7 +11,10 @@ use super::{
     ArmError, ArmProbeInterface, DapAccess, FullyQualifiedApAddress,
     communication_interface::SwdSequence,
 };
-pub use romtable::{Component, ComponentId, CoresightComponent, PeripheralType, RomTable};
+pub use romtable::{
+    Component, ComponentEntry, ComponentId, CoresightComponent, PeripheralID, PeripheralType,
+    RomTable,
+};
 
 /// An ArmMemoryInterface (ArmProbeInterface + MemoryAp)
 pub trait ArmMemoryInterface: ArmMemoryInterfaceShim {
@@ -39,8 +42,53 @@ pub trait ArmMemoryInterface: ArmMemoryInterfaceShim {
     // NOTE: this function should be infallible as it is usually only
     // a visual indication.
     fn update_core_status(&mut self, _state: CoreStatus) {}
+
+    /// Fill `word_count` 32-bit words starting at `address` with `value`.
+    ///
+    /// The default implementation splits the fill into block writes of at most 1 KiB,
+    /// to limit the size of the transfers. Probes which can fill memory natively may override it.
+    fn fill_32(&mut self, mut address: u64, value: u32, word_count: usize) -> Result<(), ArmError> {
+        let chunk = vec![value; word_count.min(FILL_CHUNK_SIZE / 4)];
+
+        let mut remaining = word_count;
+        while remaining > 0 {
+            let len = remaining.min(chunk.len());
+            self.write_32(address, &chunk[..len])?;
+
+            address += len as u64 * 4;
+            remaining -= len;
+        }
+
+        Ok(())
+    }
+
+    /// Fill `byte_count` bytes starting at `address` with `value`.
+    ///
+    /// Neither `address` nor `byte_count` have to be aligned, the unaligned parts are written
+    /// with 8-bit accesses as in [`MemoryInterface::write`].
+    fn fill_8(&mut self, mut address: u64, value: u8, byte_count: usize) -> Result<(), ArmError> {
+        let chunk = vec![value; byte_count.min(FILL_CHUNK_SIZE)];
+
+        let mut remaining = byte_count;
+        while remaining > 0 {
+            // Keep the following chunks aligned, so only the first and last chunk
+            // need 8-bit accesses.
+            let alignment = (4 - (address % 4) as usize) % 4;
+            let len = remaining.min(FILL_CHUNK_SIZE - alignment);
+            self.write(address, &chunk[..len])?;
+
+            address += len as u64;
+            remaining -= len;
+        }
+
+        Ok(())
+    }
 }
 
+/// The maximum number of bytes written at once by [`ArmMemoryInterface::fill_32`] and
+/// [`ArmMemoryInterface::fill_8`].
+const FILL_CHUNK_SIZE: usize = 1024;
+
 /// Implementation detail to allow trait upcasting-like behaviour.
 //
 // TODO: replace with trait upcasting once stable
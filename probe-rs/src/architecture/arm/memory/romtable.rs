@@ -1,5 +1,7 @@
 //! CoreSight ROM table parsing and handling.
 
+use std::collections::HashSet;
+
 use crate::architecture::arm::{
     ArmError, FullyQualifiedApAddress, ap::AccessPortError,
     communication_interface::ArmProbeInterface, memory::ArmMemoryInterface,
@@ -124,6 +126,18 @@ impl RomTable {
     pub fn try_parse(
         memory: &mut dyn ArmMemoryInterface,
         base_address: u64,
+    ) -> Result<RomTable, RomTableError> {
+        Self::parse(memory, base_address, &mut HashSet::from([base_address]))
+    }
+
+    /// Parse the ROM table, skipping all entries which point to a component in `visited`.
+    ///
+    /// ROM tables can contain entries which point to an already parsed ROM table, which
+    /// would otherwise lead to an endless recursion.
+    fn parse(
+        memory: &mut dyn ArmMemoryInterface,
+        base_address: u64,
+        visited: &mut HashSet<u64>,
     ) -> Result<RomTable, RomTableError> {
         // This is required for the collect down below.
         let mut entries = vec![];
@@ -144,8 +158,17 @@ impl RomTable {
 
             tracing::debug!("Parsing entry at {:#010x}", entry_base_addr);
 
-            if raw_entry.entry_present {
-                let component = Component::try_parse(memory, u64::from(entry_base_addr))?;
+            if !raw_entry.entry_present {
+                tracing::debug!("Entry #{} is not present, skipping.", i);
+            } else if !visited.insert(u64::from(entry_base_addr)) {
+                tracing::warn!(
+                    "Entry #{} of the ROM table at {:#010x} points to the already parsed component at {:#010x}, skipping.",
+                    i,
+                    base_address,
+                    entry_base_addr
+                );
+            } else {
+                let component = Component::parse(memory, u64::from(entry_base_addr), visited)?;
 
                 // Finally remember the entry.
                 entries.push(RomTableEntry {
@@ -154,8 +177,6 @@ impl RomTable {
                     power_domain_valid: raw_entry.power_domain_valid,
                     component: CoresightComponent::new(component, memory.fully_qualified_address()),
                 });
-            } else {
-                tracing::debug!("Entry #{} is not present, skipping.", i);
             }
         }
 
@@ -447,6 +468,15 @@ impl Component {
     pub fn try_parse<'probe: 'memory, 'memory>(
         memory: &'memory mut (dyn ArmMemoryInterface + 'probe),
         baseaddr: u64,
+    ) -> Result<Component, RomTableError> {
+        Self::parse(memory, baseaddr, &mut HashSet::from([baseaddr]))
+    }
+
+    /// Parse the component, and the components of nested ROM tables which are not in `visited`.
+    fn parse<'probe: 'memory, 'memory>(
+        memory: &'memory mut (dyn ArmMemoryInterface + 'probe),
+        baseaddr: u64,
+        visited: &mut HashSet<u64>,
     ) -> Result<Component, RomTableError> {
         tracing::debug!("\tReading component data at: {:#010x}", baseaddr);
 
@@ -480,7 +510,7 @@ impl Component {
                 Component::GenericVerificationComponent(component_id)
             }
             RawComponent::RomTable => {
-                let rom_table = RomTable::try_parse(memory, component_id.component_address)?;
+                let rom_table = RomTable::parse(memory, component_id.component_address, visited)?;
 
                 Component::Class1RomTable(component_id, rom_table)
             }
@@ -506,6 +536,40 @@ impl Component {
             Component::CoreLinkOrPrimeCellOrSystemComponent(component_id) => component_id,
         }
     }
+
+    /// Collect this component and all components of nested ROM tables, depth first.
+    ///
+    /// Every component is listed before the entries of its ROM table, if it is one.
+    pub fn walk(&self) -> Vec<ComponentEntry<'_>> {
+        let mut entries = vec![];
+        self.walk_into(0, &mut entries);
+        entries
+    }
+
+    fn walk_into<'a>(&'a self, depth: usize, entries: &mut Vec<ComponentEntry<'a>>) {
+        entries.push(ComponentEntry {
+            depth,
+            component: self,
+        });
+
+        if let Component::Class1RomTable(_, rom_table) = self {
+            for entry in rom_table.entries() {
+                entry.component().walk_into(depth + 1, entries);
+            }
+        }
+    }
+}
+
+/// A component found by [`Component::walk`].
+///
+/// The class of the component is given by its variant, its base address and peripheral ID
+/// by its [`ComponentId`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComponentEntry<'a> {
+    /// The number of ROM tables between the component and the start of the walk.
+    pub depth: usize,
+    /// The component.
+    pub component: &'a Component,
 }
 
 /// A Coresight debug component that can be configured with the Probe.
@@ -898,3 +962,83 @@ impl std::fmt::Display for PeripheralType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Component, PeripheralType};
+    use crate::{
+        architecture::arm::{FullyQualifiedApAddress, dp::DpAddress, read_all_peripheral_ids},
+        test::{FakeArmMemory, FakeArmProbe},
+    };
+
+    /// Zeroed memory of `size` bytes, which can only be read with 32-bit accesses.
+    fn rom_memory(size: usize) -> FakeArmMemory {
+        let mut memory = FakeArmMemory::new();
+        memory.set_supports_8bit_transfers(false);
+        memory.add_range(0, &vec![0; size]);
+        memory
+    }
+
+    #[test]
+    fn walk_nested_rom_table_with_cycle() {
+        let mut memory = rom_memory(0x4000);
+
+        // The root ROM table at 0x0 points to a component at 0x1000 and a ROM table at 0x2000.
+        memory.add_rom_table_entries(0x0, &[1, 2]);
+        memory.add_coresight_component(0x0, 1, 0x4c4);
+        memory.add_coresight_component(0x1000, 9, 0x00c);
+
+        // The nested ROM table points to a component at 0x3000, and back to the root ROM table.
+        memory.add_rom_table_entries(0x2000, &[1, -2]);
+        memory.add_coresight_component(0x2000, 1, 0x4c4);
+        memory.add_coresight_component(0x3000, 9, 0x002);
+
+        let root = Component::try_parse(&mut memory, 0x0).unwrap();
+
+        let entries: Vec<_> = root
+            .walk()
+            .into_iter()
+            .map(|entry| (entry.depth, entry.component.id().component_address()))
+            .collect();
+        assert_eq!(entries, [(0, 0x0), (1, 0x1000), (1, 0x2000), (2, 0x3000)]);
+
+        let walk = root.walk();
+        assert!(matches!(walk[0].component, Component::Class1RomTable(..)));
+        assert!(
+            walk[1]
+                .component
+                .id()
+                .peripheral_id()
+                .is_of_type(PeripheralType::Scs)
+        );
+        assert!(matches!(
+            walk[3].component,
+            Component::CoresightComponent(_)
+        ));
+    }
+
+    #[test]
+    fn read_peripheral_ids_of_all_components() {
+        let mut memory = rom_memory(0x3000);
+
+        // The ROM table at 0x0 points to components at 0x1000 and 0x2000.
+        memory.add_rom_table_entries(0x0, &[1, 2]);
+        memory.add_coresight_component(0x0, 1, 0x4c4);
+        memory.add_coresight_component(0x1000, 9, 0x00c);
+        memory.add_coresight_component(0x2000, 9, 0x002);
+
+        // A probe with a single memory AP, which has the ROM table behind it.
+        let mut probe = FakeArmProbe::new();
+        probe.set_ap_memory(FullyQualifiedApAddress::v1_with_default_dp(0), memory);
+
+        let peripheral_ids = read_all_peripheral_ids(&mut probe, DpAddress::Default).unwrap();
+
+        let parts: Vec<_> = peripheral_ids.iter().map(|id| id.part()).collect();
+        assert_eq!(parts, [0x4c4, 0x00c, 0x002]);
+        assert!(
+            peripheral_ids
+                .iter()
+                .all(|id| id.jep106() == Some(jep106::JEP106Code::new(0x4, 0x3b)))
+        );
+    }
+}
@@ -11,9 +11,10 @@ pub mod dp;
 pub mod memory;
 pub mod sequences;
 pub mod swo;
+mod target_description;
 mod traits;
 
-pub use self::core::{Dump, armv6m, armv7a, armv7m, armv8a, armv8m};
+pub use self::core::{Dump, DumpRegion, ExceptionFrame, armv6m, armv7a, armv7m, armv8a, armv8m};
 use self::{
     ap::AccessPortError,
     dp::DebugPortError,
@@ -27,9 +28,11 @@ use crate::{
     probe::DebugProbeError,
 };
 pub use communication_interface::{
-    ArmChipInfo, ArmCommunicationInterface, ArmProbeInterface, DapError,
+    AccessPortScan, ArmChipInfo, ArmCommunicationInterface, ArmProbeInterface, DapError,
+    WaitRetryPolicy, read_all_peripheral_ids,
 };
-pub use swo::{SwoAccess, SwoConfig, SwoMode, SwoReader};
+pub use swo::{SwoAccess, SwoConfig, SwoMode, SwoReader, SwoStatus};
+pub use target_description::{AccessPortDescription, TargetDescription, describe_target};
 pub use traits::*;
 
 /// A error that occured while parsing a raw register value.
@@ -91,6 +94,16 @@ pub enum ArmError {
     /// An error occurred in the communication with an access port or debug port.
     Dap(#[from] DapError),
 
+    /// Error accessing register {register:#x} of access port {address:?}.
+    ApRegisterAccess {
+        /// Address of the access port
+        address: FullyQualifiedApAddress,
+        /// Address of the register in the access port
+        register: u64,
+        /// Source of the error.
+        source: DapError,
+    },
+
     /// The debug probe encountered an error.
     Probe(#[from] DebugProbeError),
 
@@ -117,6 +130,12 @@ pub enum ArmError {
     /// at addresses < 0x2000_0000.
     UnsupportedBreakpointAddress(u32),
 
+    /// Unable to watch {0} bytes with a single DWT comparator on this core.
+    UnsupportedWatchpointSize(u64),
+
+    /// The debug port reports the unsupported version {0:#x}.
+    UnsupportedDebugPortVersion(u8),
+
     /// ARMv8a specific error occurred.
     Armv8a(#[from] Armv8aError),
 
@@ -147,6 +166,12 @@ pub enum ArmError {
     /// Some required functionality is not implemented: {0}
     NotImplemented(&'static str),
 
+    /// The probe does not support the SWO mode {0:?}.
+    SwoModeNotSupported(SwoMode),
+
+    /// The access port {0:?} does not match the SELECT register layout of its debug port version.
+    InconsistentSelectState(FullyQualifiedApAddress),
+
     /// Invalid data length error: {0}
     InvalidDataLength(#[from] InvalidDataLengthError),
 
@@ -163,6 +188,20 @@ impl ArmError {
         }
     }
 
+    /// Constructs [`ArmError::ApRegisterAccess`] from a [`DapError`] which occurred while
+    /// accessing `register` of the access port at `ap_address`.
+    pub fn from_dap_with_context(
+        err: DapError,
+        ap_address: &FullyQualifiedApAddress,
+        register: u64,
+    ) -> Self {
+        ArmError::ApRegisterAccess {
+            address: ap_address.clone(),
+            register,
+            source: err,
+        }
+    }
+
     /// Constructs a [`ArmError::MemoryNotAligned`] from the address and the required alignment.
     pub fn alignment_error(address: u64, alignment: usize) -> Self {
         ArmError::MemoryNotAligned(MemoryNotAlignedError { address, alignment })
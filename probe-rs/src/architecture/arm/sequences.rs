@@ -21,7 +21,7 @@ use crate::{
 };
 
 use super::{
-    ArmError, DapAccess, FullyQualifiedApAddress, Pins,
+    ArmError, DapAccess, FullyQualifiedApAddress, Pins, SwjSequence,
     ap::AccessPortError,
     armv6m::Demcr,
     communication_interface::DapProbe,
@@ -499,14 +499,9 @@ pub trait ArmDebugSequence: Send + Sync + Debug {
         fn alert_sequence(interface: &mut dyn DapProbe) -> Result<(), ArmError> {
             tracing::trace!("Sending Selection Alert sequence");
 
-            // Ensure target is not in the middle of detecting a selection alert
-            interface.swj_sequence(8, 0xFF)?;
-
-            // Alert Sequence Bits  0.. 63
-            interface.swj_sequence(64, 0x86852D956209F392)?;
-
-            // Alert Sequence Bits 64..127
-            interface.swj_sequence(64, 0x19BC0EA2E3DDAFE9)?;
+            for sequence in SwjSequence::selection_alert() {
+                interface.send_swj_sequence(sequence)?;
+            }
 
             Ok(())
         }
@@ -525,20 +520,20 @@ pub trait ArmDebugSequence: Send + Sync + Debug {
                 Some(WireProtocol::Jtag) => {
                     if has_dormant {
                         tracing::debug!("Select Dormant State (from SWD)");
-                        interface.swj_sequence(16, 0xE3BC)?;
+                        interface.send_swj_sequence(SwjSequence::swd_to_dormant())?;
 
                         // Send alert sequence
                         alert_sequence(interface)?;
 
                         // 4 cycles SWDIO/TMS LOW + 8-Bit JTAG Activation Code (0x0A)
-                        interface.swj_sequence(12, 0x0A0)?;
+                        interface.send_swj_sequence(SwjSequence::jtag_activation())?;
                     } else {
                         // Execute SWJ-DP Switch Sequence SWD to JTAG (0xE73C).
-                        interface.swj_sequence(16, 0xE73C)?;
+                        interface.send_swj_sequence(SwjSequence::swd_to_jtag())?;
                     }
 
                     // Execute at least >5 TCK cycles with TMS high to enter the Test-Logic-Reset state
-                    interface.swj_sequence(6, 0x3F)?;
+                    interface.send_swj_sequence(SwjSequence::jtag_test_logic_reset())?;
 
                     // Enter Run-Test-Idle state, as required by the DAP_Transfer command when using JTAG
                     interface.jtag_sequence(1, false, 0x01)?;
@@ -550,17 +545,17 @@ pub trait ArmDebugSequence: Send + Sync + Debug {
                     if has_dormant {
                         // Select Dormant State (from JTAG)
                         tracing::debug!("SelectV1 Dormant State (from JTAG)");
-                        interface.swj_sequence(31, 0x33BBBBBA)?;
+                        interface.send_swj_sequence(SwjSequence::jtag_to_dormant())?;
 
                         // Leave dormant state
                         alert_sequence(interface)?;
 
                         // 4 cycles SWDIO/TMS LOW + 8-Bit SWD Activation Code (0x1A)
-                        interface.swj_sequence(12, 0x1A0)?;
+                        interface.send_swj_sequence(SwjSequence::swd_activation())?;
                     } else {
                         // Execute SWJ-DP Switch Sequence JTAG to SWD (0xE79E).
                         // Change if SWJ-DP uses deprecated switch code (0xEDB6).
-                        interface.swj_sequence(16, 0xE79E)?;
+                        interface.send_swj_sequence(SwjSequence::jtag_to_swd())?;
 
                         // > 50 cycles SWDIO/TMS High, at least 2 idle cycles (SWDIO/TMS Low).
                         // -> done in debug_port_connect
@@ -710,6 +705,10 @@ pub trait ArmDebugSequence: Send + Sync + Debug {
     /// out of reset. This is based on the `ResetCatchSet` function from
     /// the [ARM SVD Debug Description].
     ///
+    /// On Cortex-M cores, the default implementation sets `DEMCR.VC_CORERESET`, so the core halts
+    /// before the first instruction after reset. Chips which run a ROM bootloader before the
+    /// reset handler of the application override this, e.g. to halt once the ROM hands over.
+    ///
     /// [ARM SVD Debug Description]: https://open-cmsis-pack.github.io/Open-CMSIS-Pack-Spec/main/html/debug_description.html#resetCatchSet
     #[doc(alias = "ResetCatchSet")]
     fn reset_catch_set(
@@ -1110,11 +1109,12 @@ pub trait DebugEraseSequence: Send + Sync {
 /// Perform a SWD line reset (SWDIO high for 50 clock cycles)
 ///
 /// After the line reset, SWDIO will be kept low for `swdio_low_cycles` cycles.
-fn swd_line_reset(interface: &mut dyn DapProbe, swdio_low_cycles: u8) -> Result<(), ArmError> {
-    assert!(swdio_low_cycles + 51 <= 64);
-
+pub(crate) fn swd_line_reset(
+    interface: &mut dyn DapProbe,
+    swdio_low_cycles: u8,
+) -> Result<(), ArmError> {
     tracing::debug!("Performing SWD line reset");
-    interface.swj_sequence(51 + swdio_low_cycles, 0x0007_FFFF_FFFF_FFFF)?;
+    interface.send_swj_sequence(SwjSequence::line_reset().with_idle_cycles(swdio_low_cycles))?;
 
     Ok(())
 }
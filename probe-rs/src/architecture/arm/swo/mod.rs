@@ -1,13 +1,13 @@
 //! SWO tracing related functions.
 
-use std::time::Duration;
+use std::{ops::ControlFlow, time::Duration};
 
 use crate::architecture::arm::communication_interface::ArmProbeInterface;
 
 use super::ArmError;
 
 /// The protocol the SWO pin should use for data transmission.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SwoMode {
     /// UART
     Uart,
@@ -28,6 +28,9 @@ pub struct SwoConfig {
     /// so must be a baud rate supported by both target and probe.
     baud: u32,
 
+    /// Baud rate the probe receives at, in Hz, overriding `baud`.
+    baud_override: Option<u32>,
+
     /// Clock input to TPIU in Hz. This is often the system clock (HCLK/SYSCLK etc).
     tpiu_clk: u32,
 
@@ -46,17 +49,50 @@ impl SwoConfig {
         SwoConfig {
             mode: SwoMode::Uart,
             baud: 1_000_000,
+            baud_override: None,
             tpiu_clk,
             tpiu_continuous_formatting: false,
         }
     }
 
+    /// Create a SwoConfig for DWT/ITM data only, in UART mode at `baud` Hz.
+    ///
+    /// TPIU continuous formatting is disabled, as there is only a single trace source
+    /// and the formatter would only add overhead.
+    pub fn itm_only(tpiu_clk: u32, baud: u32) -> Self {
+        Self::new(tpiu_clk)
+            .set_mode_uart()
+            .set_baud(baud)
+            .set_continuous_formatting(false)
+    }
+
+    /// Create a SwoConfig for ETM and DWT/ITM data, in UART mode at `baud` Hz.
+    ///
+    /// TPIU continuous formatting is enabled, which is required to interleave the ETM and
+    /// ITM trace streams on the SWO pin.
+    pub fn etm_with_itm(tpiu_clk: u32, baud: u32) -> Self {
+        Self::new(tpiu_clk)
+            .set_mode_uart()
+            .set_baud(baud)
+            .set_continuous_formatting(true)
+    }
+
     /// Set the baud rate in Hz.
     pub fn set_baud(mut self, baud: u32) -> Self {
         self.baud = baud;
         self
     }
 
+    /// Force the probe to receive SWO data at `baud` Hz.
+    ///
+    /// This bypasses the baud rate used to configure the target, which is still computed from
+    /// [`SwoConfig::baud`] and [`SwoConfig::tpiu_clk`]. Use this if the actual TPIU clock differs
+    /// from the configured one, e.g. after the target changed its clock configuration at runtime.
+    pub fn set_baud_override(mut self, baud: u32) -> Self {
+        self.baud_override = Some(baud);
+        self
+    }
+
     /// Set the mode in this SwoConfig.
     pub fn set_mode(mut self, mode: SwoMode) -> Self {
         self.mode = mode;
@@ -81,7 +117,7 @@ impl SwoConfig {
         self
     }
 
-    /// The SWO mode.
+    /// The SWO mode. Defaults to [`SwoMode::Uart`].
     pub fn mode(&self) -> SwoMode {
         self.mode
     }
@@ -95,6 +131,21 @@ impl SwoConfig {
         self.baud
     }
 
+    /// The baud rate the probe is forced to receive at, if set.
+    ///
+    /// See [`SwoConfig::set_baud_override`].
+    pub fn baud_override(&self) -> Option<u32> {
+        self.baud_override
+    }
+
+    /// Baud rate the probe should receive SWO data at, in Hz.
+    ///
+    /// This is the [override](SwoConfig::set_baud_override) if set, and
+    /// [`SwoConfig::baud`] otherwise.
+    pub fn probe_baud(&self) -> u32 {
+        self.baud_override.unwrap_or(self.baud)
+    }
+
     /// Clock input to TPIU in Hz. This is often the system clock (HCLK/SYSCLK etc).
     pub fn tpiu_clk(&self) -> u32 {
         self.tpiu_clk
@@ -107,11 +158,34 @@ impl SwoConfig {
     }
 }
 
+/// SWO data read from a probe, together with the state of the probe's SWO buffer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SwoStatus {
+    /// The received SWO bytes.
+    pub data: Vec<u8>,
+
+    /// Whether the probe's SWO buffer overflowed since the last read, so that trace
+    /// data was lost.
+    ///
+    /// This is always `false` for probes which can't report overflows.
+    pub overflowed: bool,
+
+    /// The number of bytes which were dropped, if known.
+    pub dropped_bytes: Option<usize>,
+}
+
 /// An interface to operate SWO to be implemented on drivers that support SWO.
 pub trait SwoAccess {
     /// Configure a SwoAccess interface for reading SWO data.
     fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError>;
 
+    /// Check if the interface can receive SWO data in the given mode.
+    ///
+    /// The default implementation assumes that all modes are supported.
+    fn supports_swo_mode(&self, _mode: SwoMode) -> bool {
+        true
+    }
+
     /// Disable SWO reading on this SwoAccess interface.
     fn disable_swo(&mut self) -> Result<(), ArmError>;
 
@@ -129,6 +203,46 @@ pub trait SwoAccess {
     /// May return earlier than `timeout` if the receive buffer fills up.
     fn read_swo_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, ArmError>;
 
+    /// Read SWO data for up to `timeout` duration, and report whether data was lost.
+    ///
+    /// Behaves like [`SwoAccess::read_swo_timeout`]. The default implementation can't detect
+    /// overflows and always reports the buffer as not overflowed.
+    fn read_swo_status(&mut self, timeout: Duration) -> Result<SwoStatus, ArmError> {
+        Ok(SwoStatus {
+            data: self.read_swo_timeout(timeout)?,
+            overflowed: false,
+            dropped_bytes: None,
+        })
+    }
+
+    /// Continuously read SWO data, and pass it to `sink`, until the sink returns
+    /// [`ControlFlow::Break`].
+    ///
+    /// Every read waits up to 10 ms for data, see [`SwoAccess::read_swo_status`]. The sink is
+    /// also called when no data was received, so that it can stop the stream at any time.
+    /// Overflows of the probe's SWO buffer are reported to the sink, and don't stop the stream.
+    ///
+    /// Returns the first error of a read, e.g. when the probe was disconnected.
+    fn stream_swo(
+        &mut self,
+        sink: &mut dyn FnMut(&SwoStatus) -> ControlFlow<()>,
+    ) -> Result<(), ArmError> {
+        loop {
+            let status = self.read_swo_status(Duration::from_millis(10))?;
+
+            if status.overflowed {
+                match status.dropped_bytes {
+                    Some(dropped) => tracing::warn!("SWO buffer overflowed, {dropped} bytes lost"),
+                    None => tracing::warn!("SWO buffer overflowed, data was lost"),
+                }
+            }
+
+            if sink(&status).is_break() {
+                return Ok(());
+            }
+        }
+    }
+
     /// Request an estimated best time to wait between polls of `read_swo`.
     ///
     /// A probe can implement this if it can work out a sensible time to
@@ -155,11 +269,11 @@ pub trait SwoAccess {
 pub(crate) fn poll_interval_from_buf_size(config: &SwoConfig, buf_size: usize) -> Option<Duration> {
     let time_to_full_ms = match config.mode() {
         // In UART, the output data is at the baud rate with 10 clocks per byte.
-        SwoMode::Uart => (1000 * buf_size as u32) / (config.baud() / 10),
+        SwoMode::Uart => (1000 * buf_size as u32) / (config.probe_baud() / 10),
 
         // In Manchester, the output data is at half the baud rate with
         // between 8.25 and 10 clocks per byte, so use a conservative 8 clocks/byte.
-        SwoMode::Manchester => (500 * buf_size as u32) / (config.baud() / 8),
+        SwoMode::Manchester => (500 * buf_size as u32) / (config.probe_baud() / 8),
     };
 
     // Poll frequently enough to catch the buffer at 1/4 full
@@ -201,3 +315,147 @@ impl std::io::Read for SwoReader<'_> {
         Ok(swo.len())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::FakeArmProbe;
+
+    /// A SWO source with three reads, the last of which reports an overflow.
+    fn fake_swo() -> FakeArmProbe {
+        let mut swo = FakeArmProbe::new();
+        swo.add_swo_read(Ok(SwoStatus {
+            data: vec![1, 2],
+            ..Default::default()
+        }));
+        swo.add_swo_read(Ok(SwoStatus::default()));
+        swo.add_swo_read(Ok(SwoStatus {
+            data: vec![3],
+            overflowed: true,
+            dropped_bytes: Some(5),
+        }));
+        swo
+    }
+
+    #[test]
+    fn stream_swo_until_source_fails() {
+        let mut swo = fake_swo();
+        swo.add_swo_read(Err(ArmError::Timeout));
+
+        let mut data = Vec::new();
+        let mut overflows = 0;
+        let result = swo.stream_swo(&mut |status| {
+            data.extend_from_slice(&status.data);
+            overflows += status.overflowed as usize;
+            ControlFlow::Continue(())
+        });
+
+        assert!(matches!(result, Err(ArmError::Timeout)));
+        assert_eq!(data, [1, 2, 3]);
+        assert_eq!(overflows, 1);
+    }
+
+    #[test]
+    fn stream_swo_stopped_by_sink() {
+        let mut swo = fake_swo();
+
+        let mut calls = 0;
+        swo.stream_swo(&mut |_status| {
+            calls += 1;
+            if calls == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(calls, 2);
+
+        // The stream stopped without reading further.
+        let next = swo.read_swo_status(Duration::ZERO).unwrap();
+        assert_eq!(next.data, [3]);
+    }
+
+    #[test]
+    fn stream_swo_ends_at_eof() {
+        let mut swo = FakeArmProbe::new();
+        swo.add_swo_read(Ok(SwoStatus {
+            data: vec![1, 2],
+            ..Default::default()
+        }));
+        swo.add_swo_read(Ok(SwoStatus {
+            data: vec![3],
+            ..Default::default()
+        }));
+
+        // The sink stops at the first read without data, once the source has nothing left.
+        let mut data = Vec::new();
+        swo.stream_swo(&mut |status| {
+            if status.data.is_empty() {
+                return ControlFlow::Break(());
+            }
+            data.extend_from_slice(&status.data);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(data, [1, 2, 3]);
+    }
+
+    #[test]
+    fn stream_swo_reports_overflow_without_data() {
+        let mut swo = FakeArmProbe::new();
+        swo.add_swo_read(Ok(SwoStatus {
+            data: vec![],
+            overflowed: true,
+            dropped_bytes: Some(5),
+        }));
+
+        // The overflow is passed to the sink, even though no data was received with it.
+        let mut statuses = Vec::new();
+        swo.stream_swo(&mut |status| {
+            statuses.push(status.clone());
+            if status.overflowed {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(
+            statuses,
+            [
+                SwoStatus {
+                    data: vec![],
+                    overflowed: true,
+                    dropped_bytes: Some(5),
+                },
+                SwoStatus::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn itm_only_preset() {
+        let config = SwoConfig::itm_only(64_000_000, 2_000_000);
+
+        assert_eq!(config.mode(), SwoMode::Uart);
+        assert_eq!(config.tpiu_clk(), 64_000_000);
+        assert_eq!(config.baud(), 2_000_000);
+        assert_eq!(config.baud_override(), None);
+        assert!(!config.tpiu_continuous_formatting());
+    }
+
+    #[test]
+    fn etm_with_itm_preset() {
+        let config = SwoConfig::etm_with_itm(64_000_000, 2_000_000);
+
+        assert_eq!(config.mode(), SwoMode::Uart);
+        assert_eq!(config.tpiu_clk(), 64_000_000);
+        assert_eq!(config.baud(), 2_000_000);
+        assert_eq!(config.baud_override(), None);
+        assert!(config.tpiu_continuous_formatting());
+    }
+}
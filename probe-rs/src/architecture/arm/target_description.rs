@@ -0,0 +1,253 @@
+//! A consolidated description of an ARM target, for diagnostics and bug reports.
+
+use crate::architecture::arm::{
+    ApAddress, ArmProbeInterface, FullyQualifiedApAddress,
+    ap::{ApRegister, IDR},
+    component::Scs,
+    dp::{DPIDR, DebugPortId, DpAddress, DpRegister},
+    memory::{Component, CoresightComponent, PeripheralType},
+};
+
+/// Everything that can be read about an ARM target through a debug port.
+///
+/// Pieces which could not be read are `None`, so that a description can be produced
+/// even for partially accessible targets.
+#[derive(Debug)]
+pub struct TargetDescription {
+    /// The debug port which was described.
+    pub debug_port: DpAddress,
+    /// The ID of the debug port, read from DPIDR.
+    pub debug_port_id: Option<DebugPortId>,
+    /// The access ports of the debug port.
+    pub access_ports: Option<Vec<AccessPortDescription>>,
+}
+
+/// Description of a single access port.
+#[derive(Debug)]
+pub struct AccessPortDescription {
+    /// The address of the access port.
+    pub address: FullyQualifiedApAddress,
+    /// The raw value of the IDR register. Only available for APv1 access ports.
+    pub idr: Option<u32>,
+    /// The ROM table tree behind the access port, if it is a memory AP.
+    pub rom_table: Option<Component>,
+    /// The raw CPUID values of all the cores found in the ROM table.
+    pub cpuids: Vec<u32>,
+}
+
+/// Describe the target behind the debug port `dp`.
+///
+/// This reads the DPIDR, the IDR of every access port, the ROM tables and the CPUID of every
+/// core found in them. Errors are logged and the affected parts of the description are left
+/// empty, instead of failing the whole description.
+pub fn describe_target(interface: &mut dyn ArmProbeInterface, dp: DpAddress) -> TargetDescription {
+    let debug_port_id = match interface.read_raw_dp_register(dp, DPIDR::ADDRESS) {
+        Ok(dpidr) => Some(DebugPortId::from(DPIDR(dpidr))),
+        Err(error) => {
+            tracing::warn!("Failed to read DPIDR of {:x?}: {}", dp, error);
+            None
+        }
+    };
+
+    let access_ports = match interface.access_ports(dp) {
+        Ok(access_ports) => Some(
+            access_ports
+                .into_iter()
+                .map(|address| describe_access_port(interface, address))
+                .collect(),
+        ),
+        Err(error) => {
+            tracing::warn!("Failed to enumerate access ports of {:x?}: {}", dp, error);
+            None
+        }
+    };
+
+    TargetDescription {
+        debug_port: dp,
+        debug_port_id,
+        access_ports,
+    }
+}
+
+fn describe_access_port(
+    interface: &mut dyn ArmProbeInterface,
+    address: FullyQualifiedApAddress,
+) -> AccessPortDescription {
+    let idr = match address.ap() {
+        ApAddress::V1(_) => match interface.read_raw_ap_register(&address, IDR::ADDRESS) {
+            Ok(idr) => Some(idr),
+            Err(error) => {
+                tracing::warn!("Failed to read IDR of {:x?}: {}", address, error);
+                None
+            }
+        },
+        ApAddress::V2(_) => None,
+    };
+
+    let rom_table = match read_rom_table(interface, &address) {
+        Ok(rom_table) => rom_table,
+        Err(error) => {
+            tracing::warn!("Failed to read ROM table of {:x?}: {}", address, error);
+            None
+        }
+    };
+
+    let cpuids = match &rom_table {
+        Some(rom_table) => read_cpuids(interface, rom_table, &address),
+        None => vec![],
+    };
+
+    AccessPortDescription {
+        address,
+        idr,
+        rom_table,
+        cpuids,
+    }
+}
+
+fn read_rom_table(
+    interface: &mut dyn ArmProbeInterface,
+    address: &FullyQualifiedApAddress,
+) -> Result<Option<Component>, crate::architecture::arm::ArmError> {
+    let mut memory = interface.memory_interface(address)?;
+
+    match memory.base_address()? {
+        0 => Ok(None),
+        base_address => Ok(Some(Component::try_parse(&mut *memory, base_address)?)),
+    }
+}
+
+fn read_cpuids(
+    interface: &mut dyn ArmProbeInterface,
+    rom_table: &Component,
+    address: &FullyQualifiedApAddress,
+) -> Vec<u32> {
+    let root = CoresightComponent::new(rom_table.clone(), address.clone());
+
+    root.iter()
+        .filter(|component| {
+            component
+                .component
+                .id()
+                .peripheral_id()
+                .is_of_type(PeripheralType::Scs)
+        })
+        .filter_map(|component| match Scs::new(interface, component).cpuid() {
+            Ok(cpuid) => Some(cpuid.into()),
+            Err(error) => {
+                tracing::warn!("Failed to read CPUID: {}", error);
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use jep106::JEP106Code;
+
+    use super::*;
+    use crate::{
+        architecture::arm::dp::DebugPortVersion,
+        test::{FakeArmMemory, FakeArmProbe},
+    };
+
+    const DPIDR_VALUE: u32 = 0x0BC1_2477;
+    const IDR_VALUE: u32 = 0x2477_0011;
+    /// A Cortex-M4 r0p1.
+    const CPUID_VALUE: u32 = 0x410F_C241;
+
+    /// An interface with three APv1 access ports:
+    ///
+    /// - AP 0 is a memory AP with a ROM table at 0xE00F_F000, which points to the SCS of a core.
+    /// - AP 1 is a memory AP without a ROM table.
+    /// - AP 2 can not be read.
+    fn fake_interface(dp_readable: bool) -> FakeArmProbe {
+        let mut interface = FakeArmProbe::new();
+        if dp_readable {
+            interface.set_dp_register(DPIDR::ADDRESS, DPIDR_VALUE);
+        }
+
+        let mut memory = FakeArmMemory::new();
+        memory.add_range(0xE000_E000, &[0; 0x1000]);
+        memory.add_coresight_component(0xE000_E000, 9, 0x00c);
+        memory.add_word_range(0xE000_ED00, &[CPUID_VALUE]);
+        memory.add_range(0xE00F_F000, &[0; 0x1000]);
+        memory.add_rom_table_entries(0xE00F_F000, &[-0xF1]);
+        memory.add_coresight_component(0xE00F_F000, 1, 0x4c4);
+        memory.set_base_address(0xE00F_F000);
+
+        let ap0 = FullyQualifiedApAddress::v1_with_default_dp(0);
+        interface.set_ap_register(ap0.clone(), IDR::ADDRESS, IDR_VALUE);
+        interface.set_ap_memory(ap0, memory);
+
+        let ap1 = FullyQualifiedApAddress::v1_with_default_dp(1);
+        interface.set_ap_register(ap1.clone(), IDR::ADDRESS, IDR_VALUE);
+        interface.set_ap_memory(ap1, FakeArmMemory::new());
+
+        interface.add_access_port(FullyQualifiedApAddress::v1_with_default_dp(2));
+
+        interface
+    }
+
+    #[test]
+    fn describe_mocked_target() {
+        let mut interface = fake_interface(true);
+
+        let description = describe_target(&mut interface, DpAddress::Default);
+
+        let debug_port_id = description.debug_port_id.unwrap();
+        assert_eq!(debug_port_id.version, DebugPortVersion::DPv2);
+        assert_eq!(debug_port_id.part_no, 0xBC);
+        assert_eq!(debug_port_id.designer, JEP106Code::new(0x4, 0x3B));
+
+        let access_ports = description.access_ports.unwrap();
+        assert_eq!(access_ports.len(), 3);
+
+        // The ROM table is read, and the CPUID of the core it points to.
+        assert_eq!(access_ports[0].idr, Some(IDR_VALUE));
+        let rom_table = access_ports[0].rom_table.as_ref().unwrap();
+        assert!(matches!(rom_table, Component::Class1RomTable(..)));
+        assert_eq!(rom_table.id().component_address(), 0xE00F_F000);
+        assert_eq!(access_ports[0].cpuids, [CPUID_VALUE]);
+
+        // A BASE address of zero means there is no ROM table.
+        assert_eq!(access_ports[1].idr, Some(IDR_VALUE));
+        assert!(access_ports[1].rom_table.is_none());
+        assert!(access_ports[1].cpuids.is_empty());
+
+        // An unreadable access port does not fail the whole description.
+        assert_eq!(access_ports[2].idr, None);
+        assert!(access_ports[2].rom_table.is_none());
+    }
+
+    #[test]
+    fn describe_target_with_unreadable_debug_port() {
+        let mut interface = fake_interface(false);
+
+        let description = describe_target(&mut interface, DpAddress::Default);
+
+        assert!(description.debug_port_id.is_none());
+        assert_eq!(description.access_ports.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn describe_target_with_unreadable_cpuid() {
+        let mut interface = fake_interface(true);
+
+        // The ROM table points to the SCS of a core, but its CPUID can not be read.
+        let mut memory = FakeArmMemory::new();
+        memory.add_coresight_component(0xE000_E000, 9, 0x00c);
+        memory.add_range(0xE00F_F000, &[0; 0x1000]);
+        memory.add_rom_table_entries(0xE00F_F000, &[-0xF1]);
+        memory.add_coresight_component(0xE00F_F000, 1, 0x4c4);
+        memory.set_base_address(0xE00F_F000);
+        interface.set_ap_memory(FullyQualifiedApAddress::v1_with_default_dp(0), memory);
+
+        let description = describe_target(&mut interface, DpAddress::Default);
+
+        let access_ports = description.access_ports.unwrap();
+        assert!(access_ports[0].rom_table.is_some());
+        assert!(access_ports[0].cpuids.is_empty());
+    }
+}
@@ -1,12 +1,15 @@
 use crate::{
     CoreStatus,
-    probe::{DebugProbe, DebugProbeError},
+    probe::{DebugProbe, DebugProbeError, WireProtocol},
 };
 
 use super::{
     ArmError,
     communication_interface::DapProbe,
-    dp::{DpAddress, DpRegisterAddress},
+    dp::{
+        Abort, Ctrl, DebugPortError, DebugPowerStatus, DpAccess, DpAddress, DpRegisterAddress,
+        TARGETID,
+    },
 };
 
 /// Specifies the address of register to access in a debug or access port.
@@ -161,6 +164,14 @@ impl FullyQualifiedApAddress {
         }
     }
 
+    /// Create the addresses of the first `count` access ports of the given debug port, using
+    /// Ap Addresses in the version 1 format.
+    ///
+    /// This allows accessing a known number of access ports, without enumerating them first.
+    pub fn range(dp: DpAddress, count: u8) -> impl Iterator<Item = Self> {
+        (0..count).map(move |ap| Self::v1_with_dp(dp, ap))
+    }
+
     /// Create a new `FullyQualifiedApAddress` belonging to the default debug port.
     pub const fn v2_with_default_dp(ap: ApV2Address) -> Self {
         Self {
@@ -203,6 +214,86 @@ impl FullyQualifiedApAddress {
     }
 }
 
+/// Formats the address as `dp=<dp>,ap=<port>` for version 1 access ports, and as
+/// `dp=<dp>,apv2=<base>` for version 2 access ports.
+///
+/// The debug port is either `default`, or the `TARGETSEL` value of a multidrop debug port.
+/// The base of a version 2 access port is `root` for the root component.
+impl std::fmt::Display for FullyQualifiedApAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.dp {
+            DpAddress::Default => write!(f, "dp=default,")?,
+            DpAddress::Multidrop(targetsel) => write!(f, "dp={targetsel:#010x},")?,
+        }
+
+        match &self.ap {
+            ApAddress::V1(port) => write!(f, "ap={port}"),
+            ApAddress::V2(ApV2Address(None)) => write!(f, "apv2=root"),
+            ApAddress::V2(ApV2Address(Some(base))) => write!(f, "apv2={base:#x}"),
+        }
+    }
+}
+
+/// Parses the format written by the [`Display`](std::fmt::Display) implementation.
+///
+/// Numbers can be given in decimal, or in hexadecimal with a `0x` prefix.
+impl std::str::FromStr for FullyQualifiedApAddress {
+    type Err = ApAddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ApAddressParseError::InvalidFormat(s.to_string());
+
+        let (dp, ap) = s.split_once(',').ok_or_else(invalid)?;
+
+        let dp = match dp.trim().strip_prefix("dp=").ok_or_else(invalid)? {
+            "default" => DpAddress::Default,
+            targetsel => {
+                let (digits, radix) = number_radix(targetsel);
+                DpAddress::Multidrop(u32::from_str_radix(digits, radix)?)
+            }
+        };
+
+        let ap = ap.trim();
+        let ap = if let Some(port) = ap.strip_prefix("ap=") {
+            let (digits, radix) = number_radix(port);
+            ApAddress::V1(u8::from_str_radix(digits, radix)?)
+        } else if let Some(base) = ap.strip_prefix("apv2=") {
+            match base {
+                "root" => ApAddress::V2(ApV2Address::root()),
+                base => {
+                    let (digits, radix) = number_radix(base);
+                    ApAddress::V2(ApV2Address::new(u64::from_str_radix(digits, radix)?))
+                }
+            }
+        } else {
+            return Err(invalid());
+        };
+
+        Ok(Self { dp, ap })
+    }
+}
+
+/// Split a number into its digits and radix, treating numbers with a `0x` prefix as hexadecimal.
+fn number_radix(number: &str) -> (&str, u32) {
+    match number
+        .strip_prefix("0x")
+        .or_else(|| number.strip_prefix("0X"))
+    {
+        Some(digits) => (digits, 16),
+        None => (number, 10),
+    }
+}
+
+/// An error which occurred while parsing a [`FullyQualifiedApAddress`].
+#[derive(Debug, thiserror::Error, docsplay::Display)]
+pub enum ApAddressParseError {
+    /// The access port address {0:?} does not have the format `dp=<dp>,ap=<port>` or `dp=<dp>,apv2=<base>`.
+    InvalidFormat(String),
+
+    /// Invalid number in the access port address.
+    InvalidNumber(#[from] std::num::ParseIntError),
+}
+
 /// Low-level DAP register access.
 ///
 /// Operations on this trait closely match the transactions on the wire. Implementors
@@ -282,6 +373,11 @@ pub trait RawDapAccess {
     /// the initial reset sequence, for example.
     fn swj_sequence(&mut self, bit_len: u8, bits: u64) -> Result<(), DebugProbeError>;
 
+    /// Send one of the sequences defined by the ARM Debug Interface, see [`SwjSequence`].
+    fn send_swj_sequence(&mut self, sequence: SwjSequence) -> Result<(), DebugProbeError> {
+        self.swj_sequence(sequence.bit_len, sequence.bits)
+    }
+
     /// Set the state of debugger output pins directly.
     ///
     /// The bits have the following meaning:
@@ -306,6 +402,143 @@ pub trait RawDapAccess {
     fn core_status_notification(&mut self, state: CoreStatus) -> Result<(), DebugProbeError>;
 }
 
+/// Perform a group of register accesses on `dap`, and flush them once at the end.
+///
+/// Implementations already skip redundant SELECT writes between accesses to the same access
+/// port and bank, so grouping accesses mainly saves round-trips on probes which batch
+/// operations until they are flushed.
+///
+/// If `f` fails, the outstanding operations are still flushed, and the error of `f` is returned.
+pub fn with_transaction<D, T>(
+    dap: &mut D,
+    f: impl FnOnce(&mut D) -> Result<T, ArmError>,
+) -> Result<T, ArmError>
+where
+    D: DapAccess + ?Sized,
+{
+    let result = f(dap);
+    let flushed = dap.flush();
+
+    let value = result?;
+    flushed?;
+
+    Ok(value)
+}
+
+/// A sequence of bits on SWDIO/TMS, as sent by [`RawDapAccess::swj_sequence`].
+///
+/// The constructors produce the sequences defined by the ARM Debug Interface Architecture
+/// Specification, so they don't have to be encoded by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwjSequence {
+    /// The number of bits in the sequence, at most 64.
+    pub bit_len: u8,
+    /// The bits of the sequence, sent least significant bit first.
+    pub bits: u64,
+}
+
+impl SwjSequence {
+    /// SWD line reset, SWDIO/TMS high for 51 cycles.
+    pub const fn line_reset() -> Self {
+        Self {
+            bit_len: 51,
+            bits: 0x0007_FFFF_FFFF_FFFF,
+        }
+    }
+
+    /// Appends `cycles` cycles with SWDIO/TMS low, e.g. the idle cycles after a line reset.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence would be longer than 64 bits.
+    pub const fn with_idle_cycles(self, cycles: u8) -> Self {
+        assert!(self.bit_len as u32 + cycles as u32 <= 64);
+
+        Self {
+            bit_len: self.bit_len + cycles,
+            bits: self.bits,
+        }
+    }
+
+    /// SWJ-DP switch sequence from JTAG to SWD.
+    pub const fn jtag_to_swd() -> Self {
+        Self {
+            bit_len: 16,
+            bits: 0xE79E,
+        }
+    }
+
+    /// SWJ-DP switch sequence from SWD to JTAG.
+    pub const fn swd_to_jtag() -> Self {
+        Self {
+            bit_len: 16,
+            bits: 0xE73C,
+        }
+    }
+
+    /// Selects the dormant state from SWD.
+    pub const fn swd_to_dormant() -> Self {
+        Self {
+            bit_len: 16,
+            bits: 0xE3BC,
+        }
+    }
+
+    /// Selects the dormant state from JTAG.
+    pub const fn jtag_to_dormant() -> Self {
+        Self {
+            bit_len: 31,
+            bits: 0x33BB_BBBA,
+        }
+    }
+
+    /// Selection alert sequence, which has to be sent in the dormant state before an activation
+    /// code.
+    ///
+    /// The 128 bit sequence is preceded by 8 cycles with SWDIO/TMS high, which ensure that the
+    /// target is not in the middle of detecting a selection alert.
+    pub const fn selection_alert() -> [Self; 3] {
+        [
+            Self {
+                bit_len: 8,
+                bits: 0xFF,
+            },
+            Self {
+                bit_len: 64,
+                bits: 0x8685_2D95_6209_F392,
+            },
+            Self {
+                bit_len: 64,
+                bits: 0x19BC_0EA2_E3DD_AFE9,
+            },
+        ]
+    }
+
+    /// Leaves the dormant state to SWD: 4 cycles with SWDIO/TMS low and the SWD activation code.
+    pub const fn swd_activation() -> Self {
+        Self {
+            bit_len: 12,
+            bits: 0x1A0,
+        }
+    }
+
+    /// Leaves the dormant state to JTAG: 4 cycles with SWDIO/TMS low and the JTAG activation code.
+    pub const fn jtag_activation() -> Self {
+        Self {
+            bit_len: 12,
+            bits: 0x0A0,
+        }
+    }
+
+    /// Moves the JTAG TAP state machine to Test-Logic-Reset, with TMS high for 6 cycles.
+    pub const fn jtag_test_logic_reset() -> Self {
+        Self {
+            bit_len: 6,
+            bits: 0x3F,
+        }
+    }
+}
+
 /// High-level DAP register access.
 ///
 /// Operations on this trait perform logical register reads/writes. Implementations
@@ -326,6 +559,26 @@ pub trait DapAccess {
         addr: DpRegisterAddress,
     ) -> Result<u32, ArmError>;
 
+    /// Read multiple Debug Port registers.
+    ///
+    /// The registers are read grouped by their bank, so the bank only has to be switched once
+    /// for all registers sharing it. The values are returned in the order of `addresses`.
+    fn read_raw_dp_registers(
+        &mut self,
+        dp: DpAddress,
+        addresses: &[DpRegisterAddress],
+    ) -> Result<Vec<u32>, ArmError> {
+        let mut order: Vec<usize> = (0..addresses.len()).collect();
+        order.sort_by_key(|&index| addresses[index].bank.unwrap_or(0));
+
+        let mut values = vec![0; addresses.len()];
+        for index in order {
+            values[index] = self.read_raw_dp_register(dp, addresses[index])?;
+        }
+
+        Ok(values)
+    }
+
     /// Write a Debug Port register.
     ///
     /// Highest 4 bits of `addr` are interpreted as the bank number, implementations
@@ -412,6 +665,80 @@ pub trait DapAccess {
         Ok(())
     }
 
+    /// Clear the sticky error flags of the debug port `dp`.
+    ///
+    /// After a fault or an overrun, the DP rejects all transactions until its sticky error flags
+    /// are cleared. On SW-DP, this writes the ABORT register to clear STICKYERR, WDATAERR and
+    /// STICKYORUN. On JTAG-DP, where these ABORT fields are reserved, the set flags are written
+    /// back to CTRL/STAT, which clears them. CTRL/STAT is read back afterwards to confirm that
+    /// the flags are cleared.
+    fn clear_sticky_errors(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+        let protocol = self
+            .try_dap_probe()
+            .and_then(|probe| probe.active_protocol());
+
+        if protocol == Some(WireProtocol::Jtag) {
+            // The sticky flags are write-one-to-clear, and all other fields are written back
+            // unchanged.
+            let ctrl: Ctrl = self.read_dp_register(dp)?;
+            if ctrl.sticky_err() || ctrl.stick_cmp() || ctrl.sticky_orun() {
+                self.write_dp_register(dp, ctrl)?;
+            }
+        } else {
+            let mut abort = Abort(0);
+            abort.set_stkerrclr(true);
+            abort.set_wderrclr(true);
+            abort.set_orunerrclr(true);
+            self.write_dp_register(dp, abort)?;
+        }
+
+        let ctrl: Ctrl = self.read_dp_register(dp)?;
+        if ctrl.sticky_err() || ctrl.w_data_err() || ctrl.sticky_orun() {
+            return Err(DebugPortError::StickyErrorsNotCleared(ctrl.into()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Read the power-up request and acknowledge bits of the debug port `dp` from CTRL/STAT.
+    ///
+    /// This only reads the status, e.g. to diagnose targets which gate the power of the debug
+    /// or system domain. The domains are powered up by
+    /// [`ArmDebugSequence::debug_port_start`](crate::architecture::arm::sequences::ArmDebugSequence::debug_port_start).
+    fn debug_power_status(&mut self, dp: DpAddress) -> Result<DebugPowerStatus, ArmError> {
+        let ctrl: Ctrl = self.read_dp_register(dp)?;
+
+        Ok(ctrl.into())
+    }
+
+    /// Verify that the debug port selected by the multidrop address `dp` is the expected one.
+    ///
+    /// TARGETID is read from the debug port, and its TPARTNO and TDESIGNER fields are compared
+    /// with the `TARGETSEL` value of `dp`. The TINSTANCE field of `TARGETSEL` is not part of
+    /// TARGETID, and is not checked.
+    ///
+    /// Returns `Ok(true)` if the debug port matches, and `Ok(false)` if `dp` is not a multidrop
+    /// address, so there is nothing to verify.
+    fn verify_multidrop_targetid(&mut self, dp: DpAddress) -> Result<bool, ArmError> {
+        // TPARTNO, TDESIGNER and the RAO bit 0
+        const TARGETID_MASK: u32 = 0x0FFF_FFFF;
+
+        let DpAddress::Multidrop(targetsel) = dp else {
+            return Ok(false);
+        };
+
+        let targetid: u32 = self.read_dp_register::<TARGETID>(dp)?.into();
+        if targetid & TARGETID_MASK != targetsel & TARGETID_MASK {
+            return Err(DebugPortError::TargetIdMismatch {
+                targetsel,
+                targetid,
+            }
+            .into());
+        }
+
+        Ok(true)
+    }
+
     /// Flush any outstanding operations.
     ///
     /// For performance, debug probe implementations may choose to batch writes;
@@ -425,3 +752,115 @@ pub trait DapAccess {
     /// Gain access to the Probe that implements this trait
     fn try_dap_probe(&self) -> Option<&dyn DapProbe>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swj_sequences_match_specification() {
+        let line_reset = SwjSequence::line_reset();
+        assert_eq!(line_reset.bit_len, 51);
+        assert_eq!(line_reset.bits, (1 << 51) - 1);
+
+        assert_eq!(
+            SwjSequence::line_reset().with_idle_cycles(3),
+            SwjSequence {
+                bit_len: 54,
+                bits: (1 << 51) - 1
+            }
+        );
+
+        let sequences = [
+            (SwjSequence::jtag_to_swd(), 16, 0xE79E),
+            (SwjSequence::swd_to_jtag(), 16, 0xE73C),
+            (SwjSequence::swd_to_dormant(), 16, 0xE3BC),
+            (SwjSequence::jtag_to_dormant(), 31, 0x33BB_BBBA),
+            (SwjSequence::swd_activation(), 12, 0x1A0),
+            (SwjSequence::jtag_activation(), 12, 0x0A0),
+            (SwjSequence::jtag_test_logic_reset(), 6, 0x3F),
+        ];
+        for (sequence, bit_len, bits) in sequences {
+            assert_eq!(sequence, SwjSequence { bit_len, bits });
+        }
+
+        let [prefix, low, high] = SwjSequence::selection_alert();
+        assert_eq!((prefix.bit_len, prefix.bits), (8, 0xFF));
+        assert_eq!(low.bit_len + high.bit_len, 128);
+        assert_eq!(
+            ((high.bits as u128) << 64) | low.bits as u128,
+            0x19BC_0EA2_E3DD_AFE9_8685_2D95_6209_F392
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn swj_sequence_longer_than_64_bits() {
+        SwjSequence::line_reset().with_idle_cycles(14);
+    }
+
+    #[test]
+    fn ap_address_range() {
+        let dp = DpAddress::Multidrop(0x0100_2927);
+
+        let addresses: Vec<_> = FullyQualifiedApAddress::range(dp, 3).collect();
+
+        assert_eq!(
+            addresses,
+            [
+                FullyQualifiedApAddress::v1_with_dp(dp, 0),
+                FullyQualifiedApAddress::v1_with_dp(dp, 1),
+                FullyQualifiedApAddress::v1_with_dp(dp, 2),
+            ]
+        );
+        assert_eq!(FullyQualifiedApAddress::range(dp, 0).count(), 0);
+    }
+
+    #[test]
+    fn ap_address_display_round_trip() {
+        let multidrop = DpAddress::Multidrop(0x0100_2927);
+
+        let addresses = [
+            (
+                FullyQualifiedApAddress::v1_with_default_dp(2),
+                "dp=default,ap=2",
+            ),
+            (
+                FullyQualifiedApAddress::v1_with_dp(multidrop, 2),
+                "dp=0x01002927,ap=2",
+            ),
+            (
+                FullyQualifiedApAddress::v2_with_default_dp(ApV2Address::root()),
+                "dp=default,apv2=root",
+            ),
+            (
+                FullyQualifiedApAddress::v2_with_dp(multidrop, ApV2Address::new(0xe000_2000)),
+                "dp=0x01002927,apv2=0xe0002000",
+            ),
+        ];
+
+        for (address, text) in addresses {
+            assert_eq!(address.to_string(), text);
+            assert_eq!(text.parse::<FullyQualifiedApAddress>().unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn ap_address_parse() {
+        assert_eq!(
+            "dp=0x1002927, ap=0x3"
+                .parse::<FullyQualifiedApAddress>()
+                .unwrap(),
+            FullyQualifiedApAddress::v1_with_dp(DpAddress::Multidrop(0x0100_2927), 3)
+        );
+
+        assert!(matches!(
+            "ap=2".parse::<FullyQualifiedApAddress>(),
+            Err(ApAddressParseError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            "dp=default,ap=256".parse::<FullyQualifiedApAddress>(),
+            Err(ApAddressParseError::InvalidNumber(_))
+        ));
+    }
+}
@@ -69,6 +69,30 @@ pub enum RiscvError {
     /// The hart is unavailable
     #[error("The requested hart is unavailable.")]
     HartUnavailable,
+    /// The hart did not halt within the timeout after being reset.
+    #[error(
+        "The hart did not halt after reset, {}. dmstatus: {dmstatus:?}, dmcontrol: {dmcontrol:?}",
+        reset_halt_state(.dmstatus, .dmcontrol)
+    )]
+    ResetHaltTimeout {
+        /// The last value read from the `dmstatus` register.
+        dmstatus: Dmstatus,
+        /// The value of the `dmcontrol` register, read after the timeout.
+        dmcontrol: Dmcontrol,
+    },
+}
+
+/// Describe why a hart did not halt after a reset, based on the debug module registers.
+fn reset_halt_state(dmstatus: &Dmstatus, dmcontrol: &Dmcontrol) -> &'static str {
+    if dmcontrol.hartreset() || dmcontrol.ndmreset() || dmstatus.anyunavail() {
+        "it is stuck in reset"
+    } else if !dmstatus.anyhavereset() {
+        "it never acknowledged the reset"
+    } else if dmstatus.anyrunning() {
+        "it is running"
+    } else {
+        "its state is unknown"
+    }
 }
 
 impl From<RiscvError> for ProbeRsError {
@@ -254,6 +278,9 @@ pub struct RiscvCommunicationInterfaceState {
     /// though the spec says it should not be necessary.
     sysbus_requires_halting: bool,
 
+    /// How long to wait for a system bus write to complete.
+    sysbus_timeout: Duration,
+
     /// Whether the core is currently halted.
     is_halted: bool,
 
@@ -306,6 +333,7 @@ impl RiscvCommunicationInterfaceState {
             last_selected_hart: 0,
             hasresethaltreq: None,
             sysbus_requires_halting: false,
+            sysbus_timeout: RISCV_TIMEOUT,
             is_halted: false,
 
             current_dmcontrol: Dmcontrol(0),
@@ -1175,9 +1203,21 @@ impl<'state> RiscvCommunicationInterface<'state> {
             self.schedule_write_large_dtm_register::<V, Sbdata>(*value)?;
         }
 
-        // Check that the write was succesful
-        let sbcs = self.read_dm_register::<Sbcs>()?;
+        // Wait for the write to complete, so a stuck bus does not go unnoticed.
+        let start = Instant::now();
+        let sbcs = loop {
+            let sbcs = self.read_dm_register::<Sbcs>()?;
 
+            if !sbcs.sbbusy() {
+                break sbcs;
+            }
+
+            if start.elapsed() > self.state.sysbus_timeout {
+                return Err(RiscvError::Timeout);
+            }
+        };
+
+        // Check that the write was succesful
         if sbcs.sberror() != 0 {
             Err(RiscvError::SystemBusAccess)
         } else {
@@ -1803,7 +1843,11 @@ impl<'state> RiscvCommunicationInterface<'state> {
             }
 
             if start.elapsed() > timeout {
-                return Err(RiscvError::RequestNotAcknowledged);
+                let dmcontrol: Dmcontrol = self.read_dm_register()?;
+                return Err(RiscvError::ResetHaltTimeout {
+                    dmstatus: readback,
+                    dmcontrol,
+                });
             }
         }
 
@@ -1840,6 +1884,21 @@ impl<'state> RiscvCommunicationInterface<'state> {
         }
     }
 
+    /// Runs `f` with `timeout` as the maximum time to wait for a system bus write to complete.
+    ///
+    /// By default, system bus writes time out after 5 seconds.
+    pub(crate) fn with_sysbus_timeout<T>(
+        &mut self,
+        timeout: Duration,
+        f: impl FnOnce(&mut Self) -> Result<T, crate::Error>,
+    ) -> Result<T, crate::Error> {
+        let previous_timeout = std::mem::replace(&mut self.state.sysbus_timeout, timeout);
+        let result = f(self);
+        self.state.sysbus_timeout = previous_timeout;
+
+        result
+    }
+
     pub(crate) fn sysbus_requires_halting(&mut self, en: bool) {
         self.state.sysbus_requires_halting = en;
     }
@@ -2499,3 +2558,161 @@ memory_mapped_bitfield_register! { pub struct Confstrptr0(u32); 0x19, "confstrpt
 memory_mapped_bitfield_register! { pub struct Confstrptr1(u32); 0x1a, "confstrptr1", impl From; }
 memory_mapped_bitfield_register! { pub struct Confstrptr2(u32); 0x1b, "confstrptr2", impl From; }
 memory_mapped_bitfield_register! { pub struct Confstrptr3(u32); 0x1c, "confstrptr3", impl From; }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::probe::{CommandResult, DeferredResultSet, JtagCommandQueue, ShiftDrCommand};
+
+    /// A DTM with fixed debug module register values. Writes are accepted, but ignored.
+    #[derive(Debug, Default)]
+    struct FakeDtm {
+        registers: HashMap<u64, u32>,
+        queue: JtagCommandQueue,
+        results: DeferredResultSet,
+    }
+
+    impl FakeDtm {
+        fn with_registers(registers: impl IntoIterator<Item = (u64, u32)>) -> Self {
+            Self {
+                registers: registers.into_iter().collect(),
+                ..Self::default()
+            }
+        }
+
+        fn register(&self, address: u64) -> u32 {
+            *self
+                .registers
+                .get(&address)
+                .unwrap_or_else(|| panic!("Unexpected read of DM register {address:#x}"))
+        }
+    }
+
+    impl DtmAccess for FakeDtm {
+        fn target_reset_assert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
+            Ok(())
+        }
+
+        fn clear_error_state(&mut self) -> Result<(), RiscvError> {
+            Ok(())
+        }
+
+        fn read_deferred_result(
+            &mut self,
+            index: DeferredResultIndex,
+        ) -> Result<CommandResult, RiscvError> {
+            Ok(self
+                .results
+                .take(index)
+                .unwrap_or_else(|index| panic!("No result for {index:?}")))
+        }
+
+        fn execute(&mut self) -> Result<(), RiscvError> {
+            Ok(())
+        }
+
+        fn schedule_write(
+            &mut self,
+            _address: u64,
+            _value: u32,
+        ) -> Result<Option<DeferredResultIndex>, RiscvError> {
+            Ok(None)
+        }
+
+        fn schedule_read(&mut self, address: u64) -> Result<DeferredResultIndex, RiscvError> {
+            let index = self.queue.schedule(ShiftDrCommand {
+                data: vec![],
+                len: 0,
+                transform: |_, _| Ok(CommandResult::None),
+            });
+            self.results
+                .push(&index, CommandResult::U32(self.register(address)));
+            Ok(index)
+        }
+
+        fn read_with_timeout(
+            &mut self,
+            address: u64,
+            _timeout: Duration,
+        ) -> Result<u32, RiscvError> {
+            Ok(self.register(address))
+        }
+
+        fn write_with_timeout(
+            &mut self,
+            _address: u64,
+            _value: u32,
+            _timeout: Duration,
+        ) -> Result<Option<u32>, RiscvError> {
+            Ok(None)
+        }
+
+        fn read_idcode(&mut self) -> Result<Option<u32>, DebugProbeError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn stuck_sysbus_write_times_out() {
+        let mut state = RiscvCommunicationInterfaceState::new();
+        state
+            .memory_access_info
+            .insert(RiscvBusAccess::A32, MemoryAccessMethod::SystemBus);
+
+        // System bus accesses never complete, `sbbusy` is always set.
+        let dtm = FakeDtm::with_registers([(Sbcs::ADDRESS_OFFSET, 1 << 21)]);
+
+        let mut interface = RiscvCommunicationInterface::new(Box::new(dtm), &mut state);
+
+        let result = interface.with_sysbus_timeout(Duration::from_millis(10), |interface| {
+            interface.write_word_32(0x6000_8048, 0)
+        });
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::Riscv(RiscvError::Timeout))
+        ));
+    }
+
+    #[test]
+    fn reset_and_halt_timeout_reports_status() {
+        // The hart is reset, but keeps running instead of halting.
+        let mut dmstatus = 0;
+        dmstatus |= 1 << 19; // allhavereset
+        dmstatus |= 1 << 18; // anyhavereset
+        dmstatus |= 1 << 11; // allrunning
+        dmstatus |= 1 << 10; // anyrunning
+
+        let dtm = FakeDtm::with_registers([
+            (Dmstatus::ADDRESS_OFFSET, dmstatus),
+            // Only `dmactive` is set, `hartreset` is not supported.
+            (Dmcontrol::ADDRESS_OFFSET, 1),
+        ]);
+
+        let mut state = RiscvCommunicationInterfaceState::new();
+        let mut interface = RiscvCommunicationInterface::new(Box::new(dtm), &mut state);
+
+        let error = interface
+            .reset_hart_and_halt(Duration::from_millis(10))
+            .unwrap_err();
+
+        let RiscvError::ResetHaltTimeout {
+            dmstatus,
+            dmcontrol,
+        } = &error
+        else {
+            panic!("Unexpected error: {error:?}");
+        };
+        assert!(dmstatus.allhavereset());
+        assert!(dmstatus.allrunning());
+        assert!(!dmstatus.allhalted());
+        assert!(dmcontrol.dmactive());
+        assert!(!dmcontrol.ndmreset());
+
+        assert!(error.to_string().contains("it is running"));
+    }
+}
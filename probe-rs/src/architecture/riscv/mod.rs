@@ -637,6 +637,10 @@ impl CoreInterface for Riscv32<'_> {
         self.interface.disable_debug_module()?;
         Ok(())
     }
+
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.state.semihosting_command
+    }
 }
 
 impl CoreMemoryInterface for Riscv32<'_> {
@@ -461,4 +461,8 @@ impl CoreInterface for Xtensa<'_> {
         self.interface.leave_debug_mode()?;
         Ok(())
     }
+
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.state.semihosting_command
+    }
 }
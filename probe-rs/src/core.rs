@@ -1,19 +1,33 @@
 use crate::{
     CoreType, InstructionSet, MemoryInterface, Target,
     architecture::{
-        arm::sequences::ArmDebugSequence, riscv::sequences::RiscvDebugSequence,
+        arm::{
+            Dump,
+            core::{
+                cortex_m::Vtor,
+                registers::cortex_m::{CORTEX_M_WITH_FP_CORE_REGISTERS, XPSR},
+            },
+            sequences::ArmDebugSequence,
+        },
+        riscv::sequences::RiscvDebugSequence,
         xtensa::sequences::XtensaDebugSequence,
     },
     config::DebugSequence,
     error::Error,
-    memory::CoreMemoryInterface,
+    memory::{CoreMemoryInterface, valid_32bit_address},
+    semihosting::SemihostingCommand,
 };
 pub use probe_rs_target::{Architecture, CoreAccessOptions};
 use probe_rs_target::{
     ArmCoreAccessOptions, MemoryRegion, RiscvCoreAccessOptions, XtensaCoreAccessOptions,
 };
-use std::{sync::Arc, time::Duration};
+use std::{
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+pub(crate) mod conditional_breakpoints;
 pub mod core_state;
 pub mod core_status;
 pub mod dump;
@@ -25,6 +39,8 @@ pub use core_status::*;
 pub use memory_mapped_registers::MemoryMappedRegister;
 pub use registers::*;
 
+use conditional_breakpoints::ConditionalBreakpoints;
+
 /// An struct for storing the current state of a core.
 #[derive(Debug, Clone)]
 pub struct CoreInformation {
@@ -32,6 +48,15 @@ pub struct CoreInformation {
     pub pc: u64,
 }
 
+/// The result of [`Core::step_n`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SteppedInstructions {
+    /// The number of steps which were completed.
+    pub completed: usize,
+    /// The status of the core after the last completed step.
+    pub status: CoreStatus,
+}
+
 /// A generic interface to control a MCU core.
 pub trait CoreInterface: MemoryInterface + CoreMemoryInterfaceShim {
     /// Wait until the core is halted. If the core does not halt on its own,
@@ -49,6 +74,18 @@ pub trait CoreInterface: MemoryInterface + CoreMemoryInterfaceShim {
     /// returns a [`DebugProbeError::Timeout`](crate::probe::DebugProbeError::Timeout) otherwise.
     fn halt(&mut self, timeout: Duration) -> Result<CoreInformation, Error>;
 
+    /// Request the core to halt, without waiting for it to do so.
+    ///
+    /// This allows halting multiple cores at almost the same time, see
+    /// [`Session::halt_all_cores`](crate::Session::halt_all_cores). Use
+    /// [`CoreInterface::wait_for_core_halted`] to wait for the core to halt afterwards.
+    ///
+    /// Returns [`Error::NotImplemented`] by default, for cores which can only be halted with
+    /// [`CoreInterface::halt`].
+    fn request_halt(&mut self) -> Result<(), Error> {
+        Err(Error::NotImplemented("halt requests"))
+    }
+
     /// Continue to execute instructions.
     fn run(&mut self) -> Result<(), Error>;
 
@@ -172,10 +209,47 @@ pub trait CoreInterface: MemoryInterface + CoreMemoryInterfaceShim {
         Err(Error::NotImplemented("vector catch"))
     }
 
+    /// Read the data watchpoints from the watchpoint comparators.
+    /// A value of None in any position of the Vector indicates that the comparator is unused.
+    fn data_watchpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Err(Error::NotImplemented("data watchpoints"))
+    }
+
+    /// Watches `size` bytes at `address` for `access`, using comparator `unit_index`.
+    fn set_data_watchpoint(
+        &mut self,
+        _unit_index: usize,
+        _address: u64,
+        _size: u64,
+        _access: WatchAccess,
+    ) -> Result<(), Error> {
+        Err(Error::NotImplemented("data watchpoints"))
+    }
+
+    /// Clears the data watchpoint configured in comparator `unit_index`.
+    fn clear_data_watchpoint(&mut self, _unit_index: usize) -> Result<(), Error> {
+        Err(Error::NotImplemented("data watchpoints"))
+    }
+
     /// Check if the integer size is 64-bit
     fn is_64_bit(&self) -> bool {
         false
     }
+
+    /// The semihosting command which was decoded when the core last halted, if any.
+    ///
+    /// The command is cleared when the core is resumed.
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        None
+    }
+
+    /// Sample the program counter without halting the core.
+    ///
+    /// Returns `None` if the core does not support sampling the program counter while
+    /// running, or if no sample is currently available.
+    fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        Ok(None)
+    }
 }
 
 /// Implementation detail to allow trait upcasting-like behaviour.
@@ -216,6 +290,9 @@ pub struct Core<'probe> {
     target: &'probe Target,
 
     inner: Box<dyn CoreInterface + 'probe>,
+
+    /// The conditions of the conditional breakpoints, stored in the core state of the session.
+    conditional_breakpoints: Option<&'probe mut ConditionalBreakpoints>,
 }
 
 impl CoreMemoryInterface for Core<'_> {
@@ -248,9 +325,21 @@ impl<'probe> Core<'probe> {
             name,
             target,
             inner: Box::new(core),
+            conditional_breakpoints: None,
         }
     }
 
+    /// Use `conditional_breakpoints` to store the conditions of conditional breakpoints.
+    ///
+    /// Without this, conditional breakpoints can not be set.
+    pub(crate) fn with_conditional_breakpoints(
+        mut self,
+        conditional_breakpoints: &'probe mut ConditionalBreakpoints,
+    ) -> Self {
+        self.conditional_breakpoints = Some(conditional_breakpoints);
+        self
+    }
+
     /// Returns the memory regions associated with this core.
     pub fn memory_regions(&self) -> impl Iterator<Item = &MemoryRegion> {
         self.target
@@ -275,6 +364,7 @@ impl<'probe> Core<'probe> {
             id,
             core_state: CoreState::new(ResolvedCoreOptions::new(target, options)),
             specific_state: SpecificCoreState::from_core_type(core_type),
+            conditional_breakpoints: Default::default(),
         }
     }
 
@@ -285,9 +375,21 @@ impl<'probe> Core<'probe> {
 
     /// Wait until the core is halted. If the core does not halt on its own,
     /// a [`DebugProbeError::Timeout`](crate::probe::DebugProbeError::Timeout) error will be returned.
+    ///
+    /// Halts at a conditional breakpoint whose condition is not met are resumed, and do not
+    /// count as a halt, see [`Core::set_conditional_breakpoint`].
     #[tracing::instrument(skip(self))]
     pub fn wait_for_core_halted(&mut self, timeout: Duration) -> Result<(), Error> {
-        self.inner.wait_for_core_halted(timeout)
+        let start = Instant::now();
+
+        loop {
+            self.inner
+                .wait_for_core_halted(timeout.saturating_sub(start.elapsed()))?;
+
+            if !self.evaluate_conditional_breakpoint()? {
+                return Ok(());
+            }
+        }
     }
 
     /// Check if the core is halted. If the core does not halt on its own,
@@ -303,10 +405,23 @@ impl<'probe> Core<'probe> {
         self.inner.halt(timeout)
     }
 
+    /// Request the core to halt, without waiting for it to do so.
+    ///
+    /// Use [`Core::wait_for_core_halted`] to wait for the core to halt afterwards. Returns
+    /// [`Error::NotImplemented`] for cores other than ARMv6-M, ARMv7-M and ARMv8-M cores, which
+    /// have to be halted with [`Core::halt`] instead.
+    #[tracing::instrument(skip(self))]
+    pub fn request_halt(&mut self) -> Result<(), Error> {
+        self.inner.request_halt()
+    }
+
     /// Continue to execute instructions.
     #[tracing::instrument(skip(self))]
     pub fn run(&mut self) -> Result<(), Error> {
-        self.inner.run()
+        self.inner.run()?;
+        self.set_resumed();
+
+        Ok(())
     }
 
     /// Reset the core, and then continue to execute instructions. If the core
@@ -315,7 +430,10 @@ impl<'probe> Core<'probe> {
     /// [`reset_and_halt`]: Core::reset_and_halt
     #[tracing::instrument(skip(self))]
     pub fn reset(&mut self) -> Result<(), Error> {
-        self.inner.reset()
+        self.inner.reset()?;
+        self.set_resumed();
+
+        Ok(())
     }
 
     /// Reset the core, and then immediately halt. To continue execution after
@@ -333,6 +451,43 @@ impl<'probe> Core<'probe> {
         self.inner.step()
     }
 
+    /// Steps `count` instructions, stopping early if the core halts for any other reason
+    /// than the step, e.g. because a breakpoint was hit or a fault occurred.
+    ///
+    /// The returned [`SteppedInstructions`] contains the number of steps which were actually
+    /// completed, including the one which caused the early stop.
+    #[tracing::instrument(skip(self))]
+    pub fn step_n(&mut self, count: usize) -> Result<SteppedInstructions, Error> {
+        // A step which lands on a hardware breakpoint is still reported as a step by the core,
+        // so the program counter is compared against the breakpoints instead.
+        let breakpoints: Vec<u64> = if self.inner.hw_breakpoints_enabled() {
+            self.inner.hw_breakpoints()?.into_iter().flatten().collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut status = self.status()?;
+
+        for completed in 1..=count {
+            let pc = self.step()?.pc;
+            status = self.status()?;
+
+            if status == CoreStatus::Halted(HaltReason::Step) && breakpoints.contains(&pc) {
+                status = CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware));
+            }
+
+            if status != CoreStatus::Halted(HaltReason::Step) {
+                tracing::debug!("Stopped stepping after {completed} steps: {status:?}");
+                return Ok(SteppedInstructions { completed, status });
+            }
+        }
+
+        Ok(SteppedInstructions {
+            completed: count,
+            status,
+        })
+    }
+
     /// Returns the current status of the core.
     #[tracing::instrument(level = "trace", skip(self))]
     pub fn status(&mut self) -> Result<CoreStatus, Error> {
@@ -371,6 +526,36 @@ impl<'probe> Core<'probe> {
         value.try_into().into_crate_error()
     }
 
+    /// Read the general purpose registers, the stack pointer, the return address, the program
+    /// counter and the processor status register.
+    ///
+    /// The registers are read one by one, this only collects their values in a
+    /// [`CoreRegisterValues`](registers::CoreRegisterValues).
+    #[tracing::instrument(skip(self))]
+    pub fn read_core_registers(&mut self) -> Result<registers::CoreRegisterValues, Error> {
+        let registers = self.registers();
+
+        let general_purpose = registers
+            .general_purpose_registers()
+            .map(|register| self.inner.read_core_reg(register.id()))
+            .collect::<Result<_, _>>()?;
+
+        let stack_pointer = self.stack_pointer().id();
+        let return_address = self.return_address().id();
+        let program_counter = self.program_counter().id();
+
+        Ok(registers::CoreRegisterValues {
+            general_purpose,
+            stack_pointer: self.inner.read_core_reg(stack_pointer)?,
+            return_address: self.inner.read_core_reg(return_address)?,
+            program_counter: self.inner.read_core_reg(program_counter)?,
+            processor_status: registers
+                .psr()
+                .map(|psr| self.inner.read_core_reg(psr.id()))
+                .transpose()?,
+        })
+    }
+
     /// Write the value of a core register.
     ///
     /// # Errors
@@ -431,6 +616,53 @@ impl<'probe> Core<'probe> {
         self.inner.return_address()
     }
 
+    /// Read the program counter of the core.
+    ///
+    /// On RISC-V this is the `dpc` register, which holds the address the core resumes at.
+    ///
+    /// The core has to be halted.
+    pub fn read_pc(&mut self) -> Result<u64, Error> {
+        if !self.core_halted()? {
+            return Err(Error::GenericCoreError(
+                "The core has to be halted to read the program counter".to_string(),
+            ));
+        }
+
+        self.read_core_reg(self.program_counter())
+    }
+
+    /// Set the program counter of the core, e.g. to continue execution at a different statement.
+    ///
+    /// On Cortex-M cores, the Thumb bit of the address is cleared before it is written to the
+    /// program counter, and the T bit in xPSR is set, so that the core is able to resume execution.
+    ///
+    /// The core has to be halted.
+    #[tracing::instrument(skip(self))]
+    pub fn write_pc(&mut self, address: u64) -> Result<(), Error> {
+        if !self.core_halted()? {
+            return Err(Error::GenericCoreError(
+                "The core has to be halted to write the program counter".to_string(),
+            ));
+        }
+
+        if self.core_type().is_cortex_m() {
+            let address = valid_32bit_address(address)?;
+
+            // Cortex-M cores can only execute Thumb code, resuming with a cleared T bit results
+            // in a UsageFault.
+            const XPSR_THUMB: u32 = 1 << 24;
+
+            let xpsr_value: u32 = self.read_core_reg(XPSR.id())?;
+            if xpsr_value & XPSR_THUMB == 0 {
+                self.write_core_reg(XPSR.id(), xpsr_value | XPSR_THUMB)?;
+            }
+
+            return self.write_core_reg(self.program_counter(), address & !1);
+        }
+
+        self.write_core_reg(self.program_counter(), address)
+    }
+
     /// Find the index of the next available HW breakpoint comparator.
     fn find_free_breakpoint_comparator_index(&mut self) -> Result<usize, Error> {
         let mut next_available_hw_breakpoint = 0;
@@ -498,6 +730,10 @@ impl<'probe> Core<'probe> {
             address
         );
 
+        if let Some(conditional_breakpoints) = self.conditional_breakpoints.as_mut() {
+            conditional_breakpoints.remove(address);
+        }
+
         match bp_position {
             Some(bp_position) => {
                 self.inner.clear_hw_breakpoint(bp_position)?;
@@ -510,6 +746,109 @@ impl<'probe> Core<'probe> {
         }
     }
 
+    /// Set a conditional breakpoint at `address`.
+    ///
+    /// A hardware breakpoint is set at `address`, and the `condition` is evaluated on the host
+    /// whenever the core halts at it. The condition receives the register values, as read with
+    /// [`Core::read_core_registers`], and the memory of the core. If it returns `false`, the core
+    /// steps over the breakpoint and resumes.
+    ///
+    /// The condition is evaluated by [`Core::wait_for_core_halted`]. Debuggers which poll
+    /// [`Core::status`] instead have to call [`Core::evaluate_conditional_breakpoint`] before
+    /// each poll.
+    ///
+    /// Every hit of the breakpoint is a full halt, register read and resume round trip over the
+    /// debug probe, which takes milliseconds. Placing a conditional breakpoint in a hot loop
+    /// slows down the target considerably.
+    ///
+    /// The condition is stored with the session, and is removed when the breakpoint is cleared.
+    #[tracing::instrument(skip(self, condition))]
+    pub fn set_conditional_breakpoint(
+        &mut self,
+        address: u64,
+        condition: impl Fn(&CoreRegisterValues, &mut dyn MemoryInterface) -> bool + Send + 'static,
+    ) -> Result<(), Error> {
+        if self.conditional_breakpoints.is_none() {
+            return Err(Error::NotImplemented("conditional breakpoints"));
+        }
+
+        self.set_hw_breakpoint(address)?;
+
+        if let Some(conditional_breakpoints) = self.conditional_breakpoints.as_mut() {
+            conditional_breakpoints.insert(address, Box::new(condition));
+        }
+
+        Ok(())
+    }
+
+    /// Clear the conditional breakpoint at `address`, and its condition.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_conditional_breakpoint(&mut self, address: u64) -> Result<(), Error> {
+        self.clear_hw_breakpoint(address)
+    }
+
+    /// Evaluate the condition of the conditional breakpoint the core is halted at, if any.
+    ///
+    /// If the condition is not met, the core steps over the breakpoint and resumes, and `true`
+    /// is returned. Otherwise the core stays halted, and `false` is returned.
+    ///
+    /// Only the first halt after the core was resumed with [`Core::run`] or [`Core::reset`] is
+    /// evaluated, so this can be called whenever the status of the core is polled.
+    #[tracing::instrument(skip(self))]
+    pub fn evaluate_conditional_breakpoint(&mut self) -> Result<bool, Error> {
+        if self
+            .conditional_breakpoints
+            .as_ref()
+            .is_none_or(|conditional_breakpoints| {
+                conditional_breakpoints.is_empty() || !conditional_breakpoints.is_resumed()
+            })
+        {
+            return Ok(false);
+        }
+
+        let status = self.status()?;
+        if !status.is_halted() {
+            return Ok(false);
+        }
+
+        if let Some(conditional_breakpoints) = self.conditional_breakpoints.as_mut() {
+            conditional_breakpoints.set_halted();
+        }
+
+        if !matches!(status, CoreStatus::Halted(HaltReason::Breakpoint(_))) {
+            return Ok(false);
+        }
+
+        let pc = self.read_pc()?;
+        let registers = self.read_core_registers()?;
+
+        let Some(condition) = self
+            .conditional_breakpoints
+            .as_ref()
+            .and_then(|conditional_breakpoints| conditional_breakpoints.get(pc))
+        else {
+            return Ok(false);
+        };
+
+        if condition(&registers, self.inner.as_memory_interface_mut()) {
+            return Ok(false);
+        }
+
+        tracing::debug!("Condition of the breakpoint at {pc:#010x} is not met, resuming");
+        self.step()?;
+        self.run()?;
+
+        Ok(true)
+    }
+
+    /// Record that the core was resumed, so that its next halt is checked for a conditional
+    /// breakpoint.
+    fn set_resumed(&mut self) {
+        if let Some(conditional_breakpoints) = self.conditional_breakpoints.as_mut() {
+            conditional_breakpoints.set_resumed();
+        }
+    }
+
     /// Clear all hardware breakpoints
     ///
     /// This function will clear all HW breakpoints which are configured on the target,
@@ -523,6 +862,60 @@ impl<'probe> Core<'probe> {
         Ok(())
     }
 
+    /// Set a data watchpoint
+    ///
+    /// This function will try to halt the core on any `access` to the `size` bytes at `address`.
+    /// A watchpoint which is already set at `address` is reprogrammed, otherwise the next free
+    /// comparator is used.
+    ///
+    /// Which sizes and alignments are supported is core specific.
+    #[tracing::instrument(skip(self))]
+    pub fn set_data_watchpoint(
+        &mut self,
+        address: u64,
+        size: u64,
+        access: WatchAccess,
+    ) -> Result<(), Error> {
+        let watchpoints = self.inner.data_watchpoints()?;
+
+        let comparator_index = match watchpoints.iter().position(|&wp| wp == Some(address)) {
+            Some(comparator_index) => comparator_index,
+            None => watchpoints
+                .iter()
+                .position(Option::is_none)
+                .ok_or_else(|| Error::Other("No available data watchpoints".to_string()))?,
+        };
+
+        tracing::debug!(
+            "Trying to set data watchpoint #{} with comparator address {:#08x}",
+            comparator_index,
+            address
+        );
+
+        self.inner
+            .set_data_watchpoint(comparator_index, address, size, access)
+    }
+
+    /// Clear a data watchpoint
+    ///
+    /// This function will try to clear the data watchpoint at `address` if there exists one.
+    #[tracing::instrument(skip(self))]
+    pub fn clear_data_watchpoint(&mut self, address: u64) -> Result<(), Error> {
+        let position = self
+            .inner
+            .data_watchpoints()?
+            .iter()
+            .position(|&wp| wp == Some(address));
+
+        match position {
+            Some(position) => self.inner.clear_data_watchpoint(position),
+            None => Err(Error::Other(format!(
+                "No data watchpoint found at address {:#010x}",
+                address
+            ))),
+        }
+    }
+
     /// Returns the architecture of the core.
     pub fn architecture(&self) -> Architecture {
         self.inner.architecture()
@@ -579,6 +972,232 @@ impl<'probe> Core<'probe> {
     pub fn is_64_bit(&self) -> bool {
         self.inner.is_64_bit()
     }
+
+    /// The semihosting command which was decoded when the core last halted, if any.
+    ///
+    /// This allows acting on the command without decoding it again, e.g. to implement
+    /// custom semihosting operations. The command is cleared when the core is resumed.
+    pub fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.inner.semihosting_command()
+    }
+
+    /// Sample the program counter without halting the core.
+    ///
+    /// This is a best-effort operation for lightweight statistical profiling. It is supported
+    /// on Cortex-M cores implementing `DWT_PCSR` and on ARMv7-A cores implementing `DBGPCSR`.
+    /// Returns `None` if the core does not support it, or no sample is currently available,
+    /// e.g. because the core is halted.
+    pub fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        self.inner.sample_pc_running()
+    }
+
+    /// Read the base address of the vector table from the `VTOR` register.
+    ///
+    /// VTOR is optional on ARMv6-M. Cores without it have the vector table fixed at address 0,
+    /// in which case the register reads as zero, or fails to read, and 0 is returned.
+    ///
+    /// Only Cortex-M cores are supported.
+    pub fn vector_table_base(&mut self) -> Result<u64, Error> {
+        if !self.core_type().is_cortex_m() {
+            return Err(Error::NotImplemented("vector table base"));
+        }
+
+        match self.read_word_32(Vtor::get_mmio_address()) {
+            Ok(value) => Ok(u64::from(Vtor(value).tbloff()) << 7),
+            Err(error) if self.core_type() == CoreType::Armv6m => {
+                tracing::debug!("Failed to read VTOR, assuming a fixed vector table: {error}");
+                Ok(0)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Read entry `index` of the vector table, see [`Core::vector_table_base`].
+    ///
+    /// Entry 0 is the initial stack pointer, entry 1 the reset handler, followed by the
+    /// exception and interrupt handlers. Handler addresses are returned as stored, i.e. with
+    /// the Thumb bit set.
+    pub fn read_vector(&mut self, index: u32) -> Result<u64, Error> {
+        let address = self.vector_table_base()? + u64::from(index) * 4;
+
+        Ok(u64::from(self.read_word_32(address)?))
+    }
+
+    /// Capture a [`Dump`] of the core, containing the core registers and `stack_bytes` bytes
+    /// of the stack, starting at the current stack pointer.
+    ///
+    /// If reading the stack faults partway, e.g. because the stack pointer is close to the end
+    /// of RAM, the dump contains the part of the stack which could be read.
+    ///
+    /// On Cortex-M cores with an FPU, the floating point registers are captured as well.
+    ///
+    /// The core has to be halted, and only 32-bit ARM cores are supported.
+    #[tracing::instrument(skip(self))]
+    pub fn capture_dump(&mut self, stack_bytes: usize) -> Result<Dump, Error> {
+        if self.architecture() != Architecture::Arm || self.is_64_bit() {
+            return Err(Error::NotImplemented("dump capture"));
+        }
+
+        let mut regs = [0u32; 16];
+        for (index, value) in regs.iter_mut().enumerate() {
+            *value = self.read_core_reg(registers::RegisterId(index as u16))?;
+        }
+
+        let stack_addr: u32 = self.read_core_reg(self.stack_pointer())?;
+
+        let mut stack = vec![0u8; stack_bytes];
+        let readable = read_until_fault(self, stack_addr as u64, &mut stack);
+        if readable < stack_bytes {
+            tracing::warn!(
+                "Only {readable} of {stack_bytes} stack bytes could be read from {stack_addr:#010x}"
+            );
+        }
+        stack.truncate(readable);
+
+        let mut dump = if self.core_type().is_cortex_m() && self.fpu_support()? {
+            let fp_registers = &*CORTEX_M_WITH_FP_CORE_REGISTERS;
+
+            let mut fp_regs = vec![];
+            for register in fp_registers.fpu_registers().into_iter().flatten() {
+                fp_regs.push(self.read_core_reg(register)?);
+            }
+            if let Some(fpscr) = fp_registers.fpsr() {
+                fp_regs.push(self.read_core_reg(fpscr)?);
+            }
+
+            Dump::with_fp_registers(stack_addr, stack, fp_regs)
+        } else {
+            Dump::new(stack_addr, stack)
+        };
+        dump.regs = regs;
+
+        Ok(dump)
+    }
+
+    /// Capture a [`Dump`] like [`Core::capture_dump`], which additionally contains the memory
+    /// of each range in `regions`.
+    ///
+    /// The regions are named after their address range. Unlike the stack, a region which can
+    /// not be read completely fails the dump, and so does a region which ends before it starts.
+    pub fn capture_dump_with_regions(
+        &mut self,
+        stack_bytes: usize,
+        regions: &[Range<u64>],
+    ) -> Result<Dump, Error> {
+        if let Some(region) = regions.iter().find(|region| region.start > region.end) {
+            return Err(Error::Other(format!(
+                "Invalid dump region {:#010x}..{:#010x}, it ends before it starts",
+                region.start, region.end
+            )));
+        }
+
+        let mut dump = self.capture_dump(stack_bytes)?;
+
+        for region in regions {
+            let mut data = vec![0u8; (region.end - region.start) as usize];
+            self.read(region.start, &mut data)?;
+
+            dump.add_region(
+                format!("{:#010x}..{:#010x}", region.start, region.end),
+                region.start,
+                data,
+            );
+        }
+
+        Ok(dump)
+    }
+
+    /// Write a [`Dump`] back to the core, e.g. to replay a captured crash on hardware.
+    ///
+    /// The stack and the memory regions are written to the addresses they were captured from,
+    /// followed by the core registers and, if the dump contains them, the floating point
+    /// registers.
+    ///
+    /// This is destructive: the current stack contents and register values of the core are
+    /// overwritten. The core has to be halted, and only 32-bit ARM cores are supported.
+    #[tracing::instrument(skip_all)]
+    pub fn restore_dump(&mut self, dump: &Dump) -> Result<(), Error> {
+        if self.architecture() != Architecture::Arm || self.is_64_bit() {
+            return Err(Error::NotImplemented("dump restore"));
+        }
+
+        if !self.core_halted()? {
+            return Err(Error::GenericCoreError(
+                "The core has to be halted to restore a dump".to_string(),
+            ));
+        }
+
+        // Check that the whole dump can be restored, before anything is overwritten.
+        let fp_registers = match &dump.fp_regs {
+            Some(fp_regs) => {
+                if !self.core_type().is_cortex_m() || !self.fpu_support()? {
+                    return Err(Error::GenericCoreError(
+                        "The dump contains floating point registers, but the core has no FPU"
+                            .to_string(),
+                    ));
+                }
+
+                let fp_registers = &*CORTEX_M_WITH_FP_CORE_REGISTERS;
+                let fp_registers: Vec<_> = fp_registers
+                    .fpu_registers()
+                    .into_iter()
+                    .flatten()
+                    .chain(fp_registers.fpsr())
+                    .collect();
+
+                if fp_regs.len() != fp_registers.len() {
+                    return Err(Error::GenericCoreError(format!(
+                        "The dump contains {} floating point registers, expected {}",
+                        fp_regs.len(),
+                        fp_registers.len()
+                    )));
+                }
+
+                fp_registers.into_iter().zip(fp_regs).collect()
+            }
+            None => Vec::new(),
+        };
+
+        self.write_8(dump.stack_addr() as u64, dump.stack())?;
+        for region in dump.regions() {
+            self.write_8(region.address, &region.data)?;
+        }
+
+        for (index, value) in dump.regs.iter().enumerate() {
+            self.write_core_reg(registers::RegisterId(index as u16), *value)?;
+        }
+
+        for (register, value) in fp_registers {
+            self.write_core_reg(register, *value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read as much of `data` as possible, starting at `address`, and stop at the first access that fails.
+///
+/// Returns the number of bytes which were read successfully.
+fn read_until_fault(memory: &mut dyn MemoryInterface, address: u64, data: &mut [u8]) -> usize {
+    const CHUNK_SIZE: usize = 1024;
+
+    let mut read = 0;
+    for chunk in data.chunks_mut(CHUNK_SIZE) {
+        if memory.read(address + read as u64, chunk).is_ok() {
+            read += chunk.len();
+            continue;
+        }
+
+        // Find out which part of the chunk is still readable, one word at a time.
+        for word in chunk.chunks_mut(4) {
+            if memory.read(address + read as u64, word).is_err() {
+                return read;
+            }
+            read += word.len();
+        }
+    }
+
+    read
 }
 
 impl CoreInterface for Core<'_> {
@@ -598,6 +1217,10 @@ impl CoreInterface for Core<'_> {
         self.halt(timeout)
     }
 
+    fn request_halt(&mut self) -> Result<(), Error> {
+        self.request_halt()
+    }
+
     fn run(&mut self) -> Result<(), Error> {
         self.run()
     }
@@ -708,6 +1331,14 @@ impl CoreInterface for Core<'_> {
     fn is_64_bit(&self) -> bool {
         self.is_64_bit()
     }
+
+    fn semihosting_command(&self) -> Option<SemihostingCommand> {
+        self.semihosting_command()
+    }
+
+    fn sample_pc_running(&mut self) -> Result<Option<u64>, Error> {
+        self.sample_pc_running()
+    }
 }
 
 pub enum ResolvedCoreOptions {
@@ -773,3 +1404,388 @@ impl std::fmt::Debug for ResolvedCoreOptions {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        architecture::arm::core::registers::cortex_m::PC, config::Registry, test::FakeCore,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fake_target() -> Target {
+        Registry::from_builtin_families()
+            .get_target_by_name("nRF52840_xxAA")
+            .unwrap()
+    }
+
+    #[test]
+    fn capture_dump() {
+        let mut fake = FakeCore::new();
+        for index in 0..13 {
+            fake.set_register(RegisterId(index), 0x100 + index as u32);
+        }
+        fake.set_register(RegisterId(13), 0x2000_0000);
+        fake.set_register(RegisterId(14), 0x0000_0435);
+        fake.set_register(RegisterId(15), 0x0000_0520);
+        fake.add_word_range(0x2000_0000, &[0x1111_1111, 0x2222_2222, 0x3333_3333]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core.capture_dump(12).unwrap();
+
+        assert_eq!(
+            dump.regs,
+            [
+                0x100, 0x101, 0x102, 0x103, 0x104, 0x105, 0x106, 0x107, 0x108, 0x109, 0x10a,
+                0x10b, 0x10c, 0x2000_0000, 0x0000_0435, 0x0000_0520
+            ]
+        );
+        assert_eq!(dump.stack_addr(), 0x2000_0000);
+        assert_eq!(
+            dump.stack(),
+            &[
+                0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22, 0x22, 0x33, 0x33, 0x33, 0x33
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_dump_with_regions() {
+        let mut fake = FakeCore::new();
+        for index in 0..16 {
+            fake.set_register(RegisterId(index), 0x100 + index as u32);
+        }
+        fake.set_register(RegisterId(13), 0x2000_0000);
+        fake.add_word_range(0x2000_0000, &[0x1111_1111]);
+        fake.add_word_range(0x2000_1000, &[0x4433_2211, 0x8877_6655]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core
+            .capture_dump_with_regions(4, &[0x2000_1002..0x2000_1006])
+            .unwrap();
+
+        assert_eq!(dump.regs[0], 0x100);
+        assert_eq!(dump.regs[13], 0x2000_0000);
+        assert_eq!(dump.regs[15], 0x10f);
+        assert_eq!(dump.stack(), &[0x11; 4]);
+
+        let regions = dump.regions();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].name, "0x20001002..0x20001006");
+        assert_eq!(regions[0].address, 0x2000_1002);
+        assert_eq!(regions[0].data, [0x33, 0x44, 0x55, 0x66]);
+
+        // Restoring the dump writes the region back.
+        core.write_8(0x2000_1002, &[0; 4]).unwrap();
+        core.restore_dump(&dump).unwrap();
+        let mut data = [0u8; 4];
+        core.read_8(0x2000_1002, &mut data).unwrap();
+        assert_eq!(data, [0x33, 0x44, 0x55, 0x66]);
+    }
+
+    #[test]
+    fn capture_dump_with_reversed_region() {
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, FakeCore::new());
+
+        let reversed = Range {
+            start: 0x2000_1006,
+            end: 0x2000_1002,
+        };
+        assert!(core.capture_dump_with_regions(0, &[reversed]).is_err());
+    }
+
+    #[test]
+    fn restore_dump_round_trip() {
+        let mut fake = FakeCore::new();
+        fake.set_fp_present(true);
+        for index in 0..13 {
+            fake.set_register(RegisterId(index), 0x100 + index as u32);
+        }
+        fake.set_register(RegisterId(13), 0x2000_0000);
+        fake.set_register(RegisterId(14), 0x0000_0435);
+        fake.set_register(RegisterId(15), 0x0000_0520);
+        for index in 0..32 {
+            fake.set_register(RegisterId(64 + index), 0x3f80_0000 + index as u32);
+        }
+        fake.set_register(RegisterId(33), 0x0300_0000);
+        fake.add_word_range(0x2000_0000, &[0x1111_1111, 0x2222_2222, 0x3333_3333]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core.capture_dump(12).unwrap();
+
+        // Zero the registers and the stack.
+        for index in 0..16 {
+            core.write_core_reg(RegisterId(index), 0u32).unwrap();
+        }
+        for index in 0..32 {
+            core.write_core_reg(RegisterId(64 + index), 0u32).unwrap();
+        }
+        core.write_core_reg(RegisterId(33), 0u32).unwrap();
+        core.write_8(0x2000_0000, &[0; 12]).unwrap();
+
+        core.restore_dump(&dump).unwrap();
+
+        let restored = core.capture_dump(12).unwrap();
+        assert_eq!(restored.regs, dump.regs);
+        assert_eq!(restored.fp_regs, dump.fp_regs);
+        assert_eq!(restored.stack_addr(), dump.stack_addr());
+        assert_eq!(restored.stack(), dump.stack());
+    }
+
+    #[test]
+    fn restore_dump_requires_halted_core() {
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, FakeCore::new());
+        let dump = Dump::new(0x2000_0000, vec![0; 4]);
+        core.run().unwrap();
+
+        assert!(core.restore_dump(&dump).is_err());
+    }
+
+    #[test]
+    fn restore_dump_is_validated_before_writing() {
+        let target = fake_target();
+
+        for (fp_present, fp_regs) in [(false, vec![0; 33]), (true, vec![0; 32])] {
+            let mut fake = FakeCore::new();
+            fake.set_fp_present(fp_present);
+            fake.set_register(RegisterId(0), 0x1234_5678);
+            fake.add_word_range(0x2000_0000, &[0x1111_1111]);
+            let mut core = Core::new(0, "main", &target, fake);
+
+            // Either the core has no FPU, or the dump has the wrong number of FP registers.
+            let dump = Dump::with_fp_registers(0x2000_0000, vec![0; 4], fp_regs);
+            assert!(core.restore_dump(&dump).is_err());
+
+            assert_eq!(core.read_word_32(0x2000_0000).unwrap(), 0x1111_1111);
+            let r0: u32 = core.read_core_reg(RegisterId(0)).unwrap();
+            assert_eq!(r0, 0x1234_5678);
+        }
+    }
+
+    #[test]
+    fn write_pc_sets_thumb_bit() {
+        let mut fake = FakeCore::new();
+        fake.set_register(XPSR.id(), 0x6000_0000);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        core.write_pc(0x0800_1235).unwrap();
+
+        assert_eq!(core.read_pc().unwrap(), 0x0800_1234);
+        let xpsr: u32 = core.read_core_reg(XPSR.id()).unwrap();
+        assert_eq!(xpsr, 0x6100_0000);
+    }
+
+    #[test]
+    fn write_pc_requires_halted_core() {
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, FakeCore::new());
+        core.run().unwrap();
+
+        assert!(core.write_pc(0x0800_1234).is_err());
+    }
+
+    #[test]
+    fn read_pc_requires_halted_core() {
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, FakeCore::new());
+        core.run().unwrap();
+
+        assert!(core.read_pc().is_err());
+    }
+
+    #[test]
+    fn read_vector_from_relocated_table() {
+        let mut fake = FakeCore::new();
+        fake.add_word_range(Vtor::get_mmio_address(), &[0x2000_0400]);
+        fake.add_word_range(0x2000_0400, &[0x2000_8000, 0x0800_0101, 0x0800_0201]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        assert_eq!(core.vector_table_base().unwrap(), 0x2000_0400);
+        assert_eq!(core.read_vector(0).unwrap(), 0x2000_8000);
+        // The reset handler
+        assert_eq!(core.read_vector(1).unwrap(), 0x0800_0101);
+    }
+
+    #[test]
+    fn read_core_registers_collects_values() {
+        let mut fake = FakeCore::new();
+        for index in 0..=16 {
+            fake.set_register(RegisterId(index), 0x100 + index as u32);
+        }
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let values = core.read_core_registers().unwrap();
+
+        assert_eq!(
+            values.general_purpose,
+            (0x100..=0x10c).map(RegisterValue::U32).collect::<Vec<_>>()
+        );
+        assert_eq!(values.stack_pointer, RegisterValue::U32(0x10d));
+        assert_eq!(values.return_address, RegisterValue::U32(0x10e));
+        assert_eq!(values.program_counter, RegisterValue::U32(0x10f));
+        assert_eq!(values.processor_status, Some(RegisterValue::U32(0x110)));
+    }
+
+    #[test]
+    fn capture_dump_with_fp_registers() {
+        let mut fake = FakeCore::new();
+        fake.set_fp_present(true);
+        fake.set_register(RegisterId(13), 0x2000_0000);
+        for index in 0..32 {
+            fake.set_register(RegisterId(64 + index), 0x3f80_0000 + index as u32);
+        }
+        fake.set_register(RegisterId(33), 0x0300_0000);
+        fake.add_word_range(0x2000_0000, &[0]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core.capture_dump(4).unwrap();
+
+        let fp_regs = dump.fp_regs.unwrap();
+        assert_eq!(fp_regs.len(), 33);
+        assert_eq!(fp_regs[0], 0x3f80_0000);
+        assert_eq!(fp_regs[31], 0x3f80_001f);
+        assert_eq!(fp_regs[32], 0x0300_0000);
+    }
+
+    #[test]
+    fn capture_dump_without_fpu() {
+        let mut fake = FakeCore::new();
+        fake.set_register(RegisterId(13), 0x2000_0000);
+        fake.add_word_range(0x2000_0000, &[0]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core.capture_dump(4).unwrap();
+
+        assert!(dump.fp_regs.is_none());
+    }
+
+    #[test]
+    fn step_n_stops_at_breakpoint() {
+        let mut fake = FakeCore::new();
+        fake.set_register(PC.id(), 0x100);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+        core.set_hw_breakpoint(0x106).unwrap();
+
+        let stepped = core.step_n(10).unwrap();
+
+        assert_eq!(stepped.completed, 3);
+        assert_eq!(
+            stepped.status,
+            CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware))
+        );
+        assert_eq!(core.read_pc().unwrap(), 0x106);
+    }
+
+    #[test]
+    fn conditional_breakpoint_resumes_if_condition_is_false() {
+        let mut fake = FakeCore::new();
+        fake.set_register(PC.id(), 0x104);
+        fake.set_register(RegisterId(0), 7);
+
+        let target = fake_target();
+        let mut conditional_breakpoints = ConditionalBreakpoints::default();
+        let mut core = Core::new(0, "main", &target, fake)
+            .with_conditional_breakpoints(&mut conditional_breakpoints);
+        core.set_conditional_breakpoint(0x106, |registers, _memory| {
+            registers.general_purpose[0] == RegisterValue::U32(8)
+        })
+        .unwrap();
+
+        // Halt at the breakpoint, with R0 = 7.
+        core.run().unwrap();
+        assert_eq!(
+            core.status().unwrap(),
+            CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware))
+        );
+
+        assert!(core.evaluate_conditional_breakpoint().unwrap());
+        assert_eq!(core.status().unwrap(), CoreStatus::Running);
+
+        core.clear_conditional_breakpoint(0x106).unwrap();
+        drop(core);
+        assert!(conditional_breakpoints.is_empty());
+    }
+
+    #[test]
+    fn conditional_breakpoint_stays_halted_if_condition_is_true() {
+        let mut fake = FakeCore::new();
+        fake.set_register(PC.id(), 0x104);
+
+        let target = fake_target();
+        let mut conditional_breakpoints = ConditionalBreakpoints::default();
+        let mut core = Core::new(0, "main", &target, fake)
+            .with_conditional_breakpoints(&mut conditional_breakpoints);
+        let evaluations = Arc::new(AtomicUsize::new(0));
+        let counter = evaluations.clone();
+        core.set_conditional_breakpoint(0x106, move |_registers, _memory| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            true
+        })
+        .unwrap();
+
+        core.run().unwrap();
+        assert!(core.status().unwrap().is_halted());
+
+        assert!(!core.evaluate_conditional_breakpoint().unwrap());
+        core.wait_for_core_halted(Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(core.read_pc().unwrap(), 0x106);
+
+        // The halt is only evaluated once, until the core is resumed.
+        assert_eq!(evaluations.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn step_n_completes_all_steps() {
+        let mut fake = FakeCore::new();
+        fake.set_register(PC.id(), 0x100);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let stepped = core.step_n(4).unwrap();
+
+        assert_eq!(stepped.completed, 4);
+        assert_eq!(stepped.status, CoreStatus::Halted(HaltReason::Step));
+        assert_eq!(core.read_pc().unwrap(), 0x108);
+    }
+
+    #[test]
+    fn capture_dump_with_faulting_stack() {
+        let mut fake = FakeCore::new();
+        fake.set_register(RegisterId(13), 0x2000_fff8);
+        // Only the last 8 bytes of RAM are readable.
+        fake.add_word_range(0x2000_fff8, &[0xdead_beef, 0xcafe_f00d]);
+
+        let target = fake_target();
+        let mut core = Core::new(0, "main", &target, fake);
+
+        let dump = core.capture_dump(64).unwrap();
+
+        assert_eq!(dump.stack_addr(), 0x2000_fff8);
+        assert_eq!(
+            dump.stack(),
+            &[0xef, 0xbe, 0xad, 0xde, 0x0d, 0xf0, 0xfe, 0xca]
+        );
+    }
+}
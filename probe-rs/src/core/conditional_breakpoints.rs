@@ -0,0 +1,76 @@
+//! Breakpoints with a condition which is evaluated on the host.
+
+use std::fmt::Debug;
+
+use crate::{MemoryInterface, core::CoreRegisterValues};
+
+/// The condition of a conditional breakpoint.
+///
+/// The core is resumed if the condition returns `false`.
+pub(crate) type BreakpointCondition =
+    Box<dyn Fn(&CoreRegisterValues, &mut dyn MemoryInterface) -> bool + Send>;
+
+/// The conditions of the conditional breakpoints of a core, by breakpoint address.
+///
+/// This is kept with the core state of the session, so that the conditions outlive
+/// the [`Core`](crate::Core) handle which set them.
+#[derive(Default)]
+pub(crate) struct ConditionalBreakpoints {
+    conditions: Vec<(u64, BreakpointCondition)>,
+    /// The core was resumed, and the halt which follows has not been evaluated yet.
+    resumed: bool,
+}
+
+impl ConditionalBreakpoints {
+    /// Set the condition of the breakpoint at `address`, replacing any previous condition.
+    pub(crate) fn insert(&mut self, address: u64, condition: BreakpointCondition) {
+        self.remove(address);
+        self.conditions.push((address, condition));
+    }
+
+    /// Remove the condition of the breakpoint at `address`, if there is one.
+    pub(crate) fn remove(&mut self, address: u64) {
+        self.conditions
+            .retain(|(breakpoint, _)| *breakpoint != address);
+    }
+
+    /// The condition of the breakpoint at `address`, if there is one.
+    pub(crate) fn get(&self, address: u64) -> Option<&BreakpointCondition> {
+        self.conditions
+            .iter()
+            .find(|(breakpoint, _)| *breakpoint == address)
+            .map(|(_, condition)| condition)
+    }
+
+    /// Returns `true` if there are no conditional breakpoints.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Record that the core was resumed, so that the next halt is evaluated.
+    pub(crate) fn set_resumed(&mut self) {
+        self.resumed = true;
+    }
+
+    /// Whether the core was resumed since the last evaluated halt.
+    pub(crate) fn is_resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// Record that the halt following the last resume was evaluated.
+    pub(crate) fn set_halted(&mut self) {
+        self.resumed = false;
+    }
+}
+
+impl Debug for ConditionalBreakpoints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.conditions
+                    .iter()
+                    .map(|(address, _)| format!("{address:#010x}")),
+            )
+            .finish()
+    }
+}
@@ -14,7 +14,7 @@ use crate::{
     },
 };
 
-use super::ResolvedCoreOptions;
+use super::{ResolvedCoreOptions, conditional_breakpoints::ConditionalBreakpoints};
 
 #[derive(Debug)]
 pub(crate) struct CombinedCoreState {
@@ -23,6 +23,8 @@ pub(crate) struct CombinedCoreState {
     pub(crate) specific_state: SpecificCoreState,
 
     pub(crate) id: usize,
+
+    pub(crate) conditional_breakpoints: ConditionalBreakpoints,
 }
 
 impl CombinedCoreState {
@@ -56,7 +58,7 @@ impl CombinedCoreState {
         };
         let debug_sequence = sequence.clone();
 
-        Ok(match &mut self.specific_state {
+        let core = match &mut self.specific_state {
             SpecificCoreState::Armv6m(s) => Core::new(
                 self.id,
                 name,
@@ -104,7 +106,9 @@ impl CombinedCoreState {
                     This should never happen. Please file a bug if it does."
                 );
             }
-        })
+        };
+
+        Ok(core.with_conditional_breakpoints(&mut self.conditional_breakpoints))
     }
 
     pub(crate) fn enable_arm_debug(
@@ -190,7 +194,8 @@ impl CombinedCoreState {
             name,
             target,
             crate::architecture::riscv::Riscv32::new(interface, s, debug_sequence)?,
-        ))
+        )
+        .with_conditional_breakpoints(&mut self.conditional_breakpoints))
     }
 
     pub(crate) fn attach_xtensa<'probe>(
@@ -221,7 +226,8 @@ impl CombinedCoreState {
             name,
             target,
             crate::architecture::xtensa::Xtensa::new(interface, s, debug_sequence)?,
-        ))
+        )
+        .with_conditional_breakpoints(&mut self.conditional_breakpoints))
     }
 
     /// Get the memory AP for this core.
@@ -1,7 +1,10 @@
 use crate::semihosting::SemihostingCommand;
 
 /// The status of the core.
+///
+/// New states may be added in future versions, so matches outside of probe-rs need a wildcard arm.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
 pub enum CoreStatus {
     /// The core is currently running.
     Running,
@@ -45,7 +48,10 @@ pub enum BreakpointCause {
 }
 
 /// The reason why a core was halted.
+///
+/// New reasons may be added in future versions, so matches outside of probe-rs need a wildcard arm.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[non_exhaustive]
 pub enum HaltReason {
     /// Multiple reasons for a halt.
     ///
@@ -60,6 +66,13 @@ pub enum HaltReason {
     Exception,
     /// Core halted due to a data watchpoint
     Watchpoint,
+    /// Core halted due to a data watchpoint, and the comparator which matched is known.
+    WatchpointMatch {
+        /// The index of the comparator which matched.
+        comparator: usize,
+        /// The address which the comparator watches.
+        address: u64,
+    },
     /// Core halted after single step
     Step,
     /// Core halted because of a debugger request
@@ -86,3 +99,14 @@ pub enum VectorCatchCondition {
     /// We encountered any exception.
     All,
 }
+
+/// The kind of memory access which triggers a data watchpoint.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WatchAccess {
+    /// Halt when the watched memory is read.
+    Read,
+    /// Halt when the watched memory is written.
+    Write,
+    /// Halt when the watched memory is read or written.
+    ReadWrite,
+}
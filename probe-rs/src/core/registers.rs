@@ -466,6 +466,26 @@ impl<T> RegisterValueResultExt<T> for Result<T, Infallible> {
     }
 }
 
+/// The values of the general purpose and special registers of a core.
+///
+/// See [`Core::read_core_registers`](crate::Core::read_core_registers).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreRegisterValues {
+    /// The values of the general purpose registers, in the order of
+    /// [`CoreRegisters::general_purpose_registers`].
+    pub general_purpose: Vec<RegisterValue>,
+    /// The value of the stack pointer.
+    pub stack_pointer: RegisterValue,
+    /// The value of the return address register, a.k.a. link register.
+    pub return_address: RegisterValue,
+    /// The value of the program counter.
+    pub program_counter: RegisterValue,
+    /// The value of the processor status register, e.g. `XPSR` on Cortex-M cores.
+    ///
+    /// `None` if the core has no processor status register.
+    pub processor_status: Option<RegisterValue>,
+}
+
 /// A static array of all the registers ([`CoreRegister`]) that apply to a specific architecture.
 #[derive(Debug, PartialEq)]
 pub struct CoreRegisters(Vec<&'static CoreRegister>);
@@ -511,6 +531,28 @@ impl CoreRegisters {
         self.core_registers().nth(index)
     }
 
+    /// Returns an iterator over the general purpose registers of this core.
+    ///
+    /// These are the non-FPU registers which do not have a special role, like the program
+    /// counter or the stack pointer. For ARMv6-M, ARMv7-M and ARMv8-M cores, these are `R0`
+    /// to `R12`.
+    pub fn general_purpose_registers(&self) -> impl Iterator<Item = &CoreRegister> {
+        self.core_registers().filter(|r| {
+            !r.roles.iter().any(|role| {
+                matches!(
+                    role,
+                    RegisterRole::ProgramCounter
+                        | RegisterRole::StackPointer
+                        | RegisterRole::MainStackPointer
+                        | RegisterRole::ProcessStackPointer
+                        | RegisterRole::ProcessorStatus
+                        | RegisterRole::ReturnAddress
+                        | RegisterRole::Other(_)
+                )
+            })
+        })
+    }
+
     /// Returns the nth argument register.
     ///
     /// # Panics
@@ -94,11 +94,12 @@ pub use crate::core::registers::RegisterDataType;
 pub use crate::core::registers::UnwindRule;
 pub use crate::core::{
     Architecture, BreakpointCause, Core, CoreInformation, CoreInterface, CoreRegister,
-    CoreRegisters, CoreState, CoreStatus, HaltReason, MemoryMappedRegister, RegisterId,
-    RegisterRole, RegisterValue, SpecificCoreState, VectorCatchCondition,
+    CoreRegisterValues, CoreRegisters, CoreState, CoreStatus, HaltReason, MemoryMappedRegister,
+    RegisterId, RegisterRole, RegisterValue, SpecificCoreState, SteppedInstructions,
+    VectorCatchCondition, WatchAccess,
 };
 pub use crate::error::Error;
-pub use crate::memory::MemoryInterface;
+pub use crate::memory::{FromMemoryBytes, MemoryInterface, MemoryInterfaceExt};
 pub use crate::session::{Permissions, Session, SessionConfig};
 
 #[doc = include_str!("../../README.md")]
@@ -331,6 +331,146 @@ where
     fn flush(&mut self) -> Result<(), ERR>;
 }
 
+/// A scalar value which can be accessed with [`MemoryInterfaceExt::read_scalar`] and
+/// [`MemoryInterfaceExt::write_scalar`].
+///
+/// Values are stored in memory in little endian byte order.
+pub trait FromMemoryBytes: Copy {
+    /// The size of the value in bytes.
+    const SIZE: usize;
+
+    /// Create a value from its little endian bytes. `bytes` is [`Self::SIZE`] bytes long.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Write the little endian bytes of the value to `bytes`, which is [`Self::SIZE`] bytes long.
+    fn write_le_slice(self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_from_memory_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl FromMemoryBytes for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().expect("slice has the size of the value"))
+                }
+
+                fn write_le_slice(self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_from_memory_bytes!(u8, u16, u32, u64);
+
+/// Typed access to single values in memory, on top of [`MemoryInterface`].
+///
+/// Naturally aligned values are accessed with a single transfer of their own width, if the target
+/// supports it. All other accesses are done with 32 bit words, so that values of any width can be
+/// accessed at any address, even on targets which only support 32 bit transfers. Writing a value
+/// which does not cover whole words reads the surrounding words first, and only replaces the bytes
+/// of the value.
+pub trait MemoryInterfaceExt<ERR = Error>: MemoryInterface<ERR>
+where
+    ERR: std::error::Error + From<InvalidDataLengthError> + From<MemoryNotAlignedError>,
+{
+    /// Read a value of type `T` at `address`, which does not need to be aligned.
+    fn read_scalar<T: FromMemoryBytes>(&mut self, address: u64) -> Result<T, ERR> {
+        if supports_native_scalar::<T, _, _>(self, address)? {
+            let mut bytes = [0; 8];
+            match T::SIZE {
+                1 => self.read_8(address, &mut bytes[..1])?,
+                2 => {
+                    let mut value = [0];
+                    self.read_16(address, &mut value)?;
+                    bytes[..2].copy_from_slice(&value[0].to_le_bytes());
+                }
+                4 => bytes[..4].copy_from_slice(&self.read_word_32(address)?.to_le_bytes()),
+                _ => bytes.copy_from_slice(&self.read_word_64(address)?.to_le_bytes()),
+            }
+
+            return Ok(T::from_le_slice(&bytes[..T::SIZE]));
+        }
+
+        let (word_address, offset, mut buffer) = covering_words(address, T::SIZE);
+
+        self.read_mem_32bit(word_address, &mut buffer)?;
+
+        Ok(T::from_le_slice(&buffer[offset..offset + T::SIZE]))
+    }
+
+    /// Write `value` at `address`, which does not need to be aligned.
+    fn write_scalar<T: FromMemoryBytes>(&mut self, address: u64, value: T) -> Result<(), ERR> {
+        if supports_native_scalar::<T, _, _>(self, address)? {
+            let mut bytes = [0; 8];
+            value.write_le_slice(&mut bytes[..T::SIZE]);
+
+            return match T::SIZE {
+                1 => self.write_8(address, &bytes[..1]),
+                2 => self.write_16(address, &[u16::from_le_bytes([bytes[0], bytes[1]])]),
+                4 => self.write_word_32(
+                    address,
+                    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                ),
+                _ => self.write_word_64(address, u64::from_le_bytes(bytes)),
+            };
+        }
+
+        let (word_address, offset, mut buffer) = covering_words(address, T::SIZE);
+
+        // Keep the bytes of the words which are not part of the value.
+        if buffer.len() != T::SIZE {
+            self.read_mem_32bit(word_address, &mut buffer)?;
+        }
+
+        value.write_le_slice(&mut buffer[offset..offset + T::SIZE]);
+
+        let words: Vec<u32> = buffer
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+
+        self.write_32(word_address, &words)
+    }
+}
+
+impl<M, ERR> MemoryInterfaceExt<ERR> for M
+where
+    M: MemoryInterface<ERR> + ?Sized,
+    ERR: std::error::Error + From<InvalidDataLengthError> + From<MemoryNotAlignedError>,
+{
+}
+
+/// Returns whether a value of type `T` at `address` can be accessed with a single transfer of its
+/// own width.
+fn supports_native_scalar<T, M, ERR>(memory: &mut M, address: u64) -> Result<bool, ERR>
+where
+    T: FromMemoryBytes,
+    M: MemoryInterface<ERR> + ?Sized,
+    ERR: std::error::Error + From<InvalidDataLengthError> + From<MemoryNotAlignedError>,
+{
+    if address % T::SIZE as u64 != 0 {
+        return Ok(false);
+    }
+
+    match T::SIZE {
+        1 | 2 => memory.supports_8bit_transfers(),
+        _ => Ok(true),
+    }
+}
+
+/// Returns the address of the first 32 bit word covering `size` bytes at `address`, the offset of
+/// `address` in that word, and a buffer for all the covering words.
+fn covering_words(address: u64, size: usize) -> (u64, usize, Vec<u8>) {
+    let offset = (address % 4) as usize;
+    let buffer = vec![0; (offset + size).div_ceil(4) * 4];
+
+    (address - offset as u64, offset, buffer)
+}
+
 // Helper functions to validate address space constraints
 
 /// Validate that an input address is valid for 32-bit only systems
@@ -342,6 +482,42 @@ pub(crate) fn valid_32bit_address(address: u64) -> Result<u32, Error> {
     Ok(address)
 }
 
+/// Runs `f` with the write protection of a peripheral lifted.
+///
+/// Writes `unlock_value` to `protect_register`, runs `f` and writes `lock_value` back
+/// afterwards. The lock value is written even if `f` fails, in which case the error of `f` is
+/// returned.
+pub(crate) fn with_write_protection_disabled<M, ERR, T>(
+    interface: &mut M,
+    protect_register: u64,
+    unlock_value: u32,
+    lock_value: u32,
+    f: impl FnOnce(&mut M) -> Result<T, ERR>,
+) -> Result<T, ERR>
+where
+    M: MemoryInterface<ERR> + ?Sized,
+    ERR: std::error::Error + From<InvalidDataLengthError> + From<MemoryNotAlignedError>,
+{
+    interface.write_word_32(protect_register, unlock_value)?;
+
+    let result = f(interface);
+    let lock_result = interface.write_word_32(protect_register, lock_value);
+
+    match (result, lock_result) {
+        (Ok(value), Ok(())) => Ok(value),
+        (Ok(_), Err(lock_error)) => Err(lock_error),
+        (Err(error), Ok(())) => Err(error),
+        (Err(error), Err(lock_error)) => {
+            tracing::warn!(
+                "Failed to restore write protection at {:#010x}: {}",
+                protect_register,
+                lock_error
+            );
+            Err(error)
+        }
+    }
+}
+
 /// Simplifies delegating MemoryInterface implementations, with additional error type conversion.
 pub trait CoreMemoryInterface {
     type ErrorType: std::error::Error + From<InvalidDataLengthError> + From<MemoryNotAlignedError>;
@@ -460,3 +636,94 @@ where
         self.memory_mut().flush().map_err(Error::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::FakeCore;
+
+    const PROTECT: u64 = 0x6001_f064;
+    const CONFIG: u64 = 0x6001_f048;
+
+    #[test]
+    fn read_scalar_of_each_width() {
+        let mut core = FakeCore::new();
+        core.add_word_range(0x2000_0000, &[0x4433_2211, 0x8877_6655]);
+
+        assert_eq!(core.read_scalar::<u8>(0x2000_0001).unwrap(), 0x22);
+        assert_eq!(core.read_scalar::<u16>(0x2000_0002).unwrap(), 0x4433);
+        assert_eq!(core.read_scalar::<u32>(0x2000_0000).unwrap(), 0x4433_2211);
+        assert_eq!(
+            core.read_scalar::<u64>(0x2000_0000).unwrap(),
+            0x8877_6655_4433_2211
+        );
+
+        // Unaligned values can cross word boundaries.
+        assert_eq!(core.read_scalar::<u16>(0x2000_0003).unwrap(), 0x5544);
+        assert_eq!(core.read_scalar::<u32>(0x2000_0002).unwrap(), 0x6655_4433);
+    }
+
+    #[test]
+    fn write_scalar_of_each_width() {
+        let mut core = FakeCore::new();
+        core.add_word_range(0x2000_0000, &[0x4433_2211, 0x8877_6655, 0]);
+
+        core.write_scalar::<u8>(0x2000_0001, 0xAA).unwrap();
+        assert_eq!(core.read_word_32(0x2000_0000).unwrap(), 0x4433_AA11);
+
+        // The write crosses a word boundary, and keeps the surrounding bytes.
+        core.write_scalar::<u16>(0x2000_0003, 0xCCBB).unwrap();
+        assert_eq!(core.read_word_32(0x2000_0000).unwrap(), 0xBB33_AA11);
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x8877_66CC);
+
+        core.write_scalar::<u32>(0x2000_0004, 0x1234_5678).unwrap();
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x1234_5678);
+
+        core.write_scalar::<u64>(0x2000_0004, 0x0102_0304_0506_0708)
+            .unwrap();
+        assert_eq!(core.read_word_32(0x2000_0004).unwrap(), 0x0506_0708);
+        assert_eq!(core.read_word_32(0x2000_0008).unwrap(), 0x0102_0304);
+    }
+
+    #[test]
+    fn aligned_scalars_only_access_their_own_bytes() {
+        let mut core = FakeCore::new();
+        core.add_range(0x2000_0002, &[0x11, 0x22]);
+
+        // Only the two bytes of the value exist, so a word access would fail.
+        assert_eq!(core.read_scalar::<u16>(0x2000_0002).unwrap(), 0x2211);
+        assert_eq!(core.read_scalar::<u8>(0x2000_0003).unwrap(), 0x22);
+
+        core.write_scalar::<u8>(0x2000_0002, 0xAA).unwrap();
+        core.write_scalar::<u16>(0x2000_0002, 0xCCBB).unwrap();
+        assert_eq!(core.read_scalar::<u16>(0x2000_0002).unwrap(), 0xCCBB);
+        assert!(core.read_word_32(0x2000_0000).is_err());
+    }
+
+    #[test]
+    fn write_protection_is_restored() {
+        let mut core = FakeCore::new();
+
+        with_write_protection_disabled(&mut core, PROTECT, 0x50D8_3AA1, 0, |core| {
+            assert_eq!(core.read_word_32(PROTECT)?, 0x50D8_3AA1);
+            core.write_word_32(CONFIG, 0)
+        })
+        .unwrap();
+
+        assert_eq!(core.read_word_32(PROTECT).unwrap(), 0);
+        assert_eq!(core.read_word_32(CONFIG).unwrap(), 0);
+    }
+
+    #[test]
+    fn write_protection_is_restored_on_error() {
+        let mut core = FakeCore::new();
+
+        let result: Result<(), Error> =
+            with_write_protection_disabled(&mut core, PROTECT, 0x50D8_3AA1, 0, |_| {
+                Err(Error::Other("body failed".to_string()))
+            });
+
+        assert!(matches!(result, Err(Error::Other(message)) if message == "body failed"));
+        assert_eq!(core.read_word_32(PROTECT).unwrap(), 0);
+    }
+}
@@ -15,10 +15,11 @@ pub mod stlink;
 pub mod wlink;
 
 use crate::architecture::arm::ArmError;
-use crate::architecture::arm::sequences::{ArmDebugSequence, DefaultArmSequence};
+use crate::architecture::arm::sequences::{ArmDebugSequence, DefaultArmSequence, swd_line_reset};
 use crate::architecture::arm::{
-    RegisterAddress, SwoAccess,
+    RegisterAddress, SwjSequence, SwoAccess,
     communication_interface::{DapProbe, UninitializedArmProbe},
+    dp::{DPIDR, DpRegister},
 };
 use crate::architecture::riscv::communication_interface::{RiscvError, RiscvInterfaceBuilder};
 use crate::architecture::xtensa::communication_interface::{
@@ -301,6 +302,7 @@ impl<T: ProbeError> From<T> for ProbeCreationError {
 pub struct Probe {
     inner: Box<dyn DebugProbe>,
     attached: bool,
+    target_max_clock_khz: Option<u32>,
 }
 
 impl Probe {
@@ -309,6 +311,7 @@ impl Probe {
         Self {
             inner: Box::new(probe),
             attached: false,
+            target_max_clock_khz: None,
         }
     }
 
@@ -316,6 +319,7 @@ impl Probe {
         Self {
             inner: probe,
             attached: true,
+            target_max_clock_khz: None,
         }
     }
 
@@ -324,6 +328,7 @@ impl Probe {
         Probe {
             inner: probe,
             attached: false,
+            target_max_clock_khz: None,
         }
     }
 
@@ -469,7 +474,8 @@ impl Probe {
 
     /// Resets the target device.
     pub fn target_reset(&mut self) -> Result<(), DebugProbeError> {
-        self.inner.target_reset()
+        self.inner.target_reset()?;
+        self.apply_target_max_clock()
     }
 
     /// Asserts the reset of the target.
@@ -487,7 +493,8 @@ impl Probe {
     /// This is not supported on all probes.
     pub fn target_reset_deassert(&mut self) -> Result<(), DebugProbeError> {
         tracing::debug!("Deasserting target reset");
-        self.inner.target_reset_deassert()
+        self.inner.target_reset_deassert()?;
+        self.apply_target_max_clock()
     }
 
     /// Configure protocol speed to use in kHz
@@ -499,6 +506,174 @@ impl Probe {
         }
     }
 
+    /// Returns the protocol speeds in kHz supported by the probe for the selected protocol.
+    ///
+    /// Returns `None` if the probe does not report a discrete set of speeds,
+    /// see [`DebugProbe::supported_speeds_khz`].
+    pub fn supported_speeds_khz(&self) -> Option<Vec<u32>> {
+        self.inner.supported_speeds_khz()
+    }
+
+    /// Configure the protocol speed from the maximum clock supported by the target, in kHz.
+    ///
+    /// The highest speed supported by the probe that does not exceed `max_khz` is selected.
+    /// Probes that do not report a list of supported speeds are asked for `max_khz` directly,
+    /// and fall back to a lower speed themselves if needed.
+    ///
+    /// The selected speed is re-applied after the target is reset through the probe.
+    pub fn set_target_max_clock(&mut self, max_khz: u32) -> Result<u32, DebugProbeError> {
+        if self.attached {
+            return Err(DebugProbeError::Attached);
+        }
+
+        self.target_max_clock_khz = Some(max_khz);
+        self.inner.set_speed(self.target_speed_khz(max_khz)?)
+    }
+
+    /// Selects the protocol speed to use for a target with the given maximum clock.
+    fn target_speed_khz(&self, max_khz: u32) -> Result<u32, DebugProbeError> {
+        match self.inner.supported_speeds_khz() {
+            Some(speeds) => {
+                select_speed_khz(&speeds, max_khz).ok_or(DebugProbeError::UnsupportedSpeed(max_khz))
+            }
+            None => Ok(max_khz),
+        }
+    }
+
+    fn apply_target_max_clock(&mut self) -> Result<(), DebugProbeError> {
+        if let Some(max_khz) = self.target_max_clock_khz {
+            let speed_khz = self.target_speed_khz(max_khz)?;
+            tracing::debug!("Re-applying protocol speed of {} kHz", speed_khz);
+            self.inner.set_speed(speed_khz)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the highest protocol speed up to `max_khz` at which the target responds reliably.
+    ///
+    /// The speed is determined with a binary search, checking at every step that the DPIDR
+    /// register of the default debug port can be read. Probes that report a discrete list of
+    /// supported speeds are only checked at those speeds, other probes are first checked at
+    /// `max_khz` directly. The probe is left configured at the highest working speed, which
+    /// is returned.
+    ///
+    /// This requires a probe with raw DAP access, and has to be done before attaching.
+    pub fn autonegotiate_speed(&mut self, max_khz: u32) -> Result<u32, Error> {
+        if self.attached {
+            return Err(DebugProbeError::Attached.into());
+        }
+
+        if self.inner.try_as_dap_probe().is_none() {
+            return Err(DebugProbeError::InterfaceNotAvailable {
+                interface_name: "DAP",
+            }
+            .into());
+        }
+
+        let mut checked = HashMap::new();
+        let best_khz = match self.inner.supported_speeds_khz() {
+            Some(mut speeds) => {
+                speeds.retain(|&speed| speed <= max_khz);
+                speeds.sort_unstable();
+                speeds.dedup();
+
+                self.search_speed(&mut checked, speeds.len() as u32, |index| {
+                    speeds[index as usize]
+                })?
+            }
+            None => match self.check_speed(&mut checked, max_khz)? {
+                Some(applied_khz) => Some(applied_khz),
+                None => {
+                    self.search_speed(&mut checked, max_khz.saturating_sub(1), |index| index + 1)?
+                }
+            },
+        };
+
+        let best_khz = best_khz.ok_or_else(|| {
+            Error::Other(format!(
+                "The target did not respond at any protocol speed up to {max_khz} kHz"
+            ))
+        })?;
+
+        tracing::info!("Negotiated a protocol speed of {} kHz", best_khz);
+        Ok(self.inner.set_speed(best_khz)?)
+    }
+
+    /// Binary searches the `count` candidate speeds returned by `speed_at` in ascending order,
+    /// and returns the highest applied speed at which the target responds.
+    fn search_speed(
+        &mut self,
+        checked: &mut HashMap<u32, bool>,
+        count: u32,
+        speed_at: impl Fn(u32) -> u32,
+    ) -> Result<Option<u32>, Error> {
+        let mut best_khz = None;
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let index = low + (high - low) / 2;
+
+            match self.check_speed(checked, speed_at(index))? {
+                Some(applied_khz) => {
+                    best_khz = best_khz.max(Some(applied_khz));
+                    low = index + 1;
+                }
+                None => high = index,
+            }
+        }
+
+        Ok(best_khz)
+    }
+
+    /// Applies `speed_khz` and checks that the DPIDR register can be read.
+    ///
+    /// Returns the speed actually applied by the probe if the target responds. Results are
+    /// cached by applied speed in `checked`, so every applied speed is only checked once.
+    fn check_speed(
+        &mut self,
+        checked: &mut HashMap<u32, bool>,
+        speed_khz: u32,
+    ) -> Result<Option<u32>, Error> {
+        let applied_khz = match self.inner.set_speed(speed_khz) {
+            Ok(applied_khz) => applied_khz,
+            Err(DebugProbeError::UnsupportedSpeed(_)) => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        if let Some(&responds) = checked.get(&applied_khz) {
+            return Ok(responds.then_some(applied_khz));
+        }
+
+        let result = self.read_dpidr_attached();
+        if let Err(error) = &result {
+            tracing::debug!(
+                "Protocol speed of {} kHz is not reliable: {}",
+                applied_khz,
+                error
+            );
+        }
+
+        checked.insert(applied_khz, result.is_ok());
+        Ok(result.is_ok().then_some(applied_khz))
+    }
+
+    /// Attaches the probe, reads the DPIDR register and detaches again.
+    fn read_dpidr_attached(&mut self) -> Result<u32, Error> {
+        self.inner.attach()?;
+
+        let result = match self.inner.try_as_dap_probe() {
+            Some(dap_probe) => read_dpidr(dap_probe).map_err(Error::from),
+            None => Err(DebugProbeError::InterfaceNotAvailable {
+                interface_name: "DAP",
+            }
+            .into()),
+        };
+
+        self.inner.detach()?;
+
+        result
+    }
+
     /// Configure the scan chain to use for the attached target.
     ///
     /// See [`DebugProbe::set_scan_chain`] for more information and usage
@@ -651,6 +826,27 @@ pub trait ProbeFactory: std::any::Any + std::fmt::Display + std::fmt::Debug + Sy
     fn list_probes(&self) -> Vec<DebugProbeInfo>;
 }
 
+/// Reads the DPIDR register of the default debug port, switching to SWD first if needed.
+fn read_dpidr(dap_probe: &mut dyn DapProbe) -> Result<u32, ArmError> {
+    if dap_probe.active_protocol() == Some(WireProtocol::Swd) {
+        swd_line_reset(dap_probe, 0)?;
+        // SWJ-DP Switch Sequence JTAG to SWD
+        dap_probe.send_swj_sequence(SwjSequence::jtag_to_swd())?;
+        swd_line_reset(dap_probe, 3)?;
+    }
+
+    dap_probe.raw_read_register(DPIDR::ADDRESS.into())
+}
+
+/// Returns the highest speed in `speeds_khz` that does not exceed `max_khz`.
+fn select_speed_khz(speeds_khz: &[u32], max_khz: u32) -> Option<u32> {
+    speeds_khz
+        .iter()
+        .copied()
+        .filter(|&speed| speed <= max_khz)
+        .max()
+}
+
 /// An abstraction over general debug probe.
 ///
 /// This trait has to be implemented by ever debug probe driver.
@@ -678,6 +874,16 @@ pub trait DebugProbe: Send + fmt::Debug {
     ///
     fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError>;
 
+    /// Returns the protocol speeds in kHz supported by the probe for the selected protocol,
+    /// if it only supports a discrete set of speeds.
+    ///
+    /// Returns `None` if the probe does not report its supported speeds. This is the case
+    /// for probes with a continuously adjustable clock, like CMSIS-DAP and J-Link probes,
+    /// which pick the closest lower speed themselves in [`DebugProbe::set_speed`].
+    fn supported_speeds_khz(&self) -> Option<Vec<u32>> {
+        None
+    }
+
     /// Set the JTAG scan chain information for the target under debug.
     ///
     /// This allows the probe to know which TAPs are in the scan chain and their
@@ -1484,6 +1690,73 @@ mod test {
         assert!(!probe_info.is_probe_type::<espusbjtag::EspUsbJtagFactory>());
     }
 
+    #[test]
+    fn target_max_clock_selects_highest_supported_speed() {
+        let mut fake_probe = fake_probe::FakeProbe::new();
+        fake_probe.set_supported_speeds(vec![100, 1000, 4000, 12000]);
+        let mut probe = fake_probe.into_probe();
+
+        assert_eq!(probe.set_target_max_clock(8000).unwrap(), 4000);
+        assert_eq!(probe.speed_khz(), 4000);
+
+        assert_eq!(probe.set_target_max_clock(12000).unwrap(), 12000);
+        assert_eq!(probe.speed_khz(), 12000);
+
+        assert!(matches!(
+            probe.set_target_max_clock(50),
+            Err(DebugProbeError::UnsupportedSpeed(50))
+        ));
+    }
+
+    #[test]
+    fn target_max_clock_without_speed_list() {
+        let mut probe = fake_probe::FakeProbe::new().into_probe();
+
+        assert_eq!(probe.set_target_max_clock(2500).unwrap(), 2500);
+        assert_eq!(probe.speed_khz(), 2500);
+    }
+
+    #[test]
+    fn autonegotiate_speed_stops_below_failing_speed() {
+        let mut fake_probe = fake_probe::FakeProbe::new();
+        fake_probe.set_max_reliable_speed(3000);
+        fake_probe.set_dap_register_read_handler(Box::new(|_| Ok(0x0BC1_2477)));
+        let mut probe = fake_probe.into_probe();
+
+        assert_eq!(probe.autonegotiate_speed(10000).unwrap(), 3000);
+        assert_eq!(probe.speed_khz(), 3000);
+    }
+
+    #[test]
+    fn autonegotiate_speed_uses_supported_speeds() {
+        let mut fake_probe = fake_probe::FakeProbe::new();
+        fake_probe.set_supported_speeds(vec![100, 1000, 4000, 12000]);
+        fake_probe.set_max_reliable_speed(3000);
+        fake_probe.set_dap_register_read_handler(Box::new(|_| Ok(0x0BC1_2477)));
+        let mut probe = fake_probe.into_probe();
+
+        assert_eq!(probe.autonegotiate_speed(10000).unwrap(), 1000);
+        assert_eq!(probe.speed_khz(), 1000);
+    }
+
+    #[test]
+    fn autonegotiate_speed_at_max_speed() {
+        let mut fake_probe = fake_probe::FakeProbe::new();
+        fake_probe.set_dap_register_read_handler(Box::new(|_| Ok(0x0BC1_2477)));
+        let mut probe = fake_probe.into_probe();
+
+        assert_eq!(probe.autonegotiate_speed(4000).unwrap(), 4000);
+    }
+
+    #[test]
+    fn autonegotiate_speed_without_response() {
+        let mut fake_probe = fake_probe::FakeProbe::new();
+        fake_probe.set_max_reliable_speed(0);
+        let mut probe = fake_probe.into_probe();
+
+        assert!(probe.autonegotiate_speed(4000).is_err());
+    }
+
     #[test]
     fn test_parsing_many_colons() {
         let selector: DebugProbeSelector = "303a:1001:DC:DA:0C:D3:FE:D8".try_into().unwrap();
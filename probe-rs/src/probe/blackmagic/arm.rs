@@ -158,6 +158,11 @@ impl BlackMagicProbeArmDebug {
 
                 // Try the more involved debug_port_setup sequence, which also handles dormant mode.
                 sequence.debug_port_setup(&mut *self.probe, dp)?;
+
+                // The setup sequence resets the line, so the cached SELECT value is stale.
+                if let Some(dp_state) = self.dps.get_mut(&dp) {
+                    dp_state.invalidate_select();
+                }
             }
 
             self.current_dp = dp;
@@ -234,11 +239,13 @@ impl BlackMagicProbeArmDebug {
 
         let bank = bank.unwrap_or(0);
 
-        if bank != dp_state.current_select.dp_bank_sel() {
+        if dp_state.select_dirty || bank != dp_state.current_select.dp_bank_sel() {
             dp_state.current_select.set_dp_bank_sel(bank);
 
             tracing::debug!("Changing DP_BANK_SEL to {:x?}", dp_state.current_select);
 
+            dp_state.select_dirty = false;
+
             match dp_state.current_select {
                 SelectCache::DPv1(select) => self.write_dp_register(dp, select)?,
                 SelectCache::DPv3(select, _) => self.write_dp_register(dp, select)?,
@@ -273,7 +280,9 @@ impl ArmProbeInterface for BlackMagicProbeArmDebug {
                 Ok(ap::v1::valid_access_ports(self, dp).into_iter().collect())
             }
             DebugPortVersion::DPv3 => ap::v2::enumerate_access_ports(self, dp),
-            DebugPortVersion::Unsupported(_) => unreachable!(),
+            DebugPortVersion::Unsupported(version) => {
+                Err(ArmError::UnsupportedDebugPortVersion(version))
+            }
         }
     }
 
@@ -285,6 +294,16 @@ impl ArmProbeInterface for BlackMagicProbeArmDebug {
         DpAddress::Default
     }
 
+    fn invalidate_select(&mut self, dp: DpAddress) {
+        if let Some(dp_state) = self.dps.get_mut(&dp) {
+            dp_state.invalidate_select();
+        }
+    }
+
+    fn debug_port_version(&self, dp: DpAddress) -> Option<DebugPortVersion> {
+        self.dps.get(&dp).and_then(DpState::known_version)
+    }
+
     fn memory_interface(
         &mut self,
         access_port: &FullyQualifiedApAddress,
@@ -6,7 +6,7 @@ use crate::{
     CoreStatus,
     architecture::arm::{
         ArmCommunicationInterface, ArmError, DapError, Pins, RawDapAccess, RegisterAddress,
-        SwoAccess, SwoConfig, SwoMode,
+        SwoAccess, SwoConfig, SwoMode, SwoStatus,
         communication_interface::{DapProbe, UninitializedArmProbe},
         dp::{Abort, Ctrl, DpRegister},
         swo::poll_interval_from_buf_size,
@@ -707,7 +707,7 @@ impl CmsisDap {
     }
 
     /// Fetch latest SWO trace data by sending a DAP_SWO_Data request.
-    fn get_swo_data(&mut self) -> Result<Vec<u8>, DebugProbeError> {
+    fn get_swo_data(&mut self) -> Result<SwoStatus, DebugProbeError> {
         match self.swo_buffer_size {
             Some(swo_buffer_size) => {
                 // We'll request the smaller of the probe's SWO buffer and
@@ -720,10 +720,14 @@ impl CmsisDap {
                 if response.status.error {
                     Err(CmsisDapError::SwoTraceStreamError.into())
                 } else {
-                    Ok(response.data)
+                    Ok(SwoStatus {
+                        data: response.data,
+                        overflowed: response.status.overrun,
+                        dropped_bytes: None,
+                    })
                 }
             }
-            None => Ok(Vec::new()),
+            None => Ok(SwoStatus::default()),
         }
     }
 
@@ -1180,12 +1184,12 @@ impl SwoAccess for CmsisDap {
 
         // Set baud rate.
         let baud = self.set_swo_baudrate(swo::BaudrateRequest {
-            baudrate: config.baud(),
+            baudrate: config.probe_baud(),
         })?;
-        if baud != config.baud() {
+        if baud != config.probe_baud() {
             tracing::warn!(
                 "Target SWO baud rate not met: requested {}, got {}",
-                config.baud(),
+                config.probe_baud(),
                 baud
             );
         }
@@ -1196,6 +1200,13 @@ impl SwoAccess for CmsisDap {
         Ok(())
     }
 
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        match mode {
+            SwoMode::Uart => self.capabilities.swo_uart_implemented,
+            SwoMode::Manchester => self.capabilities.swo_manchester_implemented,
+        }
+    }
+
     fn disable_swo(&mut self) -> Result<(), ArmError> {
         tracing::debug!("Stopping SWO capture");
         self.stop_swo_capture()?;
@@ -1204,21 +1215,32 @@ impl SwoAccess for CmsisDap {
     }
 
     fn read_swo_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, ArmError> {
+        self.read_swo_status(timeout).map(|status| status.data)
+    }
+
+    fn read_swo_status(&mut self, timeout: Duration) -> Result<SwoStatus, ArmError> {
         if self.swo_active {
             if self.swo_streaming {
+                // The streaming endpoint only carries trace data, without any status.
                 let buffer = self
                     .device
                     .read_swo_stream(timeout)
                     .map_err(DebugProbeError::from)?;
                 tracing::trace!("SWO streaming buffer: {:?}", buffer);
-                Ok(buffer)
+                Ok(SwoStatus {
+                    data: buffer,
+                    ..SwoStatus::default()
+                })
             } else {
-                let data = self.get_swo_data()?;
-                tracing::trace!("SWO polled data: {:?}", data);
-                Ok(data)
+                let status = self.get_swo_data()?;
+                tracing::trace!("SWO polled data: {:?}", status.data);
+                if status.overflowed {
+                    tracing::warn!("SWO buffer of the probe overflowed, trace data was lost");
+                }
+                Ok(status)
             }
         } else {
-            Ok(Vec::new())
+            Ok(SwoStatus::default())
         }
     }
 
@@ -3,7 +3,7 @@ use crate::{
     Error, MemoryInterface, MemoryMappedRegister,
     architecture::arm::{
         ArmError, ArmProbeInterface, DapAccess, FullyQualifiedApAddress, RawDapAccess,
-        RegisterAddress, SwoAccess,
+        RegisterAddress, SwoAccess, SwoConfig, SwoMode,
         ap::memory_ap::mock::MockMemoryAp,
         armv8m::Dhcsr,
         communication_interface::{
@@ -34,6 +34,8 @@ use std::{
 pub struct FakeProbe {
     protocol: WireProtocol,
     speed: u32,
+    supported_speeds: Option<Vec<u32>>,
+    max_reliable_speed: Option<u32>,
     scan_chain: Option<Vec<ScanChainElement>>,
 
     dap_register_read_handler: Option<Box<dyn Fn(RegisterAddress) -> Result<u32, ArmError> + Send>>,
@@ -41,6 +43,9 @@ pub struct FakeProbe {
     dap_register_write_handler:
         Option<Box<dyn Fn(RegisterAddress, u32) -> Result<(), ArmError> + Send>>,
 
+    swo_modes: Vec<SwoMode>,
+    swo_enable_handler: Option<Box<dyn Fn(&SwoConfig) -> Result<(), ArmError> + Send>>,
+
     operations: RefCell<VecDeque<Operation>>,
 
     memory_ap: MockedAp,
@@ -318,11 +323,16 @@ impl FakeProbe {
         FakeProbe {
             protocol: WireProtocol::Swd,
             speed: 1000,
+            supported_speeds: None,
+            max_reliable_speed: None,
             scan_chain: None,
 
             dap_register_read_handler: None,
             dap_register_write_handler: None,
 
+            swo_modes: Vec::new(),
+            swo_enable_handler: None,
+
             operations: RefCell::new(VecDeque::new()),
 
             memory_ap: MockedAp::MemoryAp(MockMemoryAp::with_pattern()),
@@ -382,11 +392,42 @@ impl FakeProbe {
         self.dap_register_write_handler = Some(handler);
     }
 
+    /// This sets the handler for enabling SWO, and the SWO modes supported by the probe.
+    ///
+    /// Without a handler, the probe does not offer a SWO interface.
+    pub fn set_swo_enable_handler(
+        &mut self,
+        modes: Vec<SwoMode>,
+        handler: Box<dyn Fn(&SwoConfig) -> Result<(), ArmError> + Send>,
+    ) {
+        self.swo_modes = modes;
+        self.swo_enable_handler = Some(handler);
+    }
+
+    /// Sets the discrete list of speeds in kHz reported by the probe.
+    pub fn set_supported_speeds(&mut self, speeds_khz: Vec<u32>) {
+        self.supported_speeds = Some(speeds_khz);
+    }
+
+    /// Sets the highest speed in kHz at which DAP register accesses succeed.
+    ///
+    /// Above this speed, all DAP register accesses time out.
+    pub fn set_max_reliable_speed(&mut self, speed_khz: u32) {
+        self.max_reliable_speed = Some(speed_khz);
+    }
+
     /// Makes a generic probe out of the [`FakeProbe`]
     pub fn into_probe(self) -> Probe {
         Probe::from_specific_probe(Box::new(self))
     }
 
+    fn check_reliable_speed(&self) -> Result<(), ArmError> {
+        match self.max_reliable_speed {
+            Some(max_speed) if self.speed > max_speed => Err(ArmError::Timeout),
+            _ => Ok(()),
+        }
+    }
+
     fn next_operation(&self) -> Option<Operation> {
         self.operations.borrow_mut().pop_front()
     }
@@ -504,9 +545,37 @@ impl DebugProbe for FakeProbe {
     }
 
     fn set_speed(&mut self, speed_khz: u32) -> Result<u32, DebugProbeError> {
-        self.speed = speed_khz;
+        self.speed = match &self.supported_speeds {
+            Some(speeds) => speeds
+                .iter()
+                .copied()
+                .filter(|&speed| speed <= speed_khz)
+                .max()
+                .ok_or(DebugProbeError::UnsupportedSpeed(speed_khz))?,
+            None => speed_khz,
+        };
+
+        Ok(self.speed)
+    }
+
+    fn supported_speeds_khz(&self) -> Option<Vec<u32>> {
+        self.supported_speeds.clone()
+    }
+
+    fn get_swo_interface(&self) -> Option<&dyn SwoAccess> {
+        if self.swo_enable_handler.is_some() {
+            Some(self as _)
+        } else {
+            None
+        }
+    }
 
-        Ok(speed_khz)
+    fn get_swo_interface_mut(&mut self) -> Option<&mut dyn SwoAccess> {
+        if self.swo_enable_handler.is_some() {
+            Some(self as _)
+        } else {
+            None
+        }
     }
 
     fn attach(&mut self) -> Result<(), DebugProbeError> {
@@ -557,11 +626,17 @@ impl DebugProbe for FakeProbe {
     fn has_arm_interface(&self) -> bool {
         true
     }
+
+    fn try_as_dap_probe(&mut self) -> Option<&mut dyn DapProbe> {
+        Some(self)
+    }
 }
 
 impl RawDapAccess for FakeProbe {
     /// Reads the DAP register on the specified port and address
     fn raw_read_register(&mut self, address: RegisterAddress) -> Result<u32, ArmError> {
+        self.check_reliable_speed()?;
+
         let handler = self.dap_register_read_handler.as_ref().unwrap();
 
         handler(address)
@@ -569,6 +644,8 @@ impl RawDapAccess for FakeProbe {
 
     /// Writes a value to the DAP register on the specified port and address
     fn raw_write_register(&mut self, address: RegisterAddress, value: u32) -> Result<(), ArmError> {
+        self.check_reliable_speed()?;
+
         let handler = self.dap_register_write_handler.as_ref().unwrap();
 
         handler(address, value)
@@ -579,7 +656,7 @@ impl RawDapAccess for FakeProbe {
     }
 
     fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), DebugProbeError> {
-        todo!()
+        Ok(())
     }
 
     fn swj_pins(
@@ -600,6 +677,28 @@ impl RawDapAccess for FakeProbe {
     }
 }
 
+impl DapProbe for FakeProbe {}
+
+impl SwoAccess for FakeProbe {
+    fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
+        let handler = self.swo_enable_handler.as_ref().unwrap();
+
+        handler(config)
+    }
+
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        self.swo_modes.contains(&mode)
+    }
+
+    fn disable_swo(&mut self) -> Result<(), ArmError> {
+        Ok(())
+    }
+
+    fn read_swo_timeout(&mut self, _timeout: std::time::Duration) -> Result<Vec<u8>, ArmError> {
+        Ok(Vec::new())
+    }
+}
+
 #[derive(Debug)]
 struct FakeArmInterface<S: ArmDebugState> {
     probe: Box<FakeProbe>,
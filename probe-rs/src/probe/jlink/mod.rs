@@ -1281,11 +1281,16 @@ impl DapProbe for JLink {}
 impl SwoAccess for JLink {
     fn enable_swo(&mut self, config: &SwoConfig) -> Result<(), ArmError> {
         self.swo_config = Some(*config);
-        self.swo_start(SwoMode::Uart, config.baud(), SWO_BUFFER_SIZE.into())
+        self.swo_start(SwoMode::Uart, config.probe_baud(), SWO_BUFFER_SIZE.into())
             .map_err(DebugProbeError::from)?;
         Ok(())
     }
 
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        // Only UART mode is used to start SWO capture.
+        mode == SwoMode::Uart
+    }
+
     fn disable_swo(&mut self) -> Result<(), ArmError> {
         self.swo_config = None;
         self.swo_stop().map_err(DebugProbeError::from)?;
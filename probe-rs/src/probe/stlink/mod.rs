@@ -72,6 +72,8 @@ impl ProbeFactory for StLinkFactory {
             protocol: WireProtocol::Swd,
             swd_speed_khz: 1_800,
             jtag_speed_khz: 1_120,
+            swd_speeds_khz: vec![],
+            jtag_speeds_khz: vec![],
             swo_enabled: false,
             scan_chain: None,
 
@@ -98,6 +100,10 @@ pub struct StLink<D: StLinkUsb> {
     protocol: WireProtocol,
     swd_speed_khz: u32,
     jtag_speed_khz: u32,
+    /// SWD speeds in kHz reported by V3 probes.
+    swd_speeds_khz: Vec<u32>,
+    /// JTAG speeds in kHz reported by V3 probes.
+    jtag_speeds_khz: Vec<u32>,
     swo_enabled: bool,
     scan_chain: Option<Vec<ScanChainElement>>,
 
@@ -168,6 +174,10 @@ impl DebugProbe for StLink<StLinkUsbDevice> {
         }
     }
 
+    fn supported_speeds_khz(&self) -> Option<Vec<u32>> {
+        Some(self.supported_speeds())
+    }
+
     fn set_scan_chain(&mut self, scan_chain: Vec<ScanChainElement>) -> Result<(), DebugProbeError> {
         tracing::info!("Setting scan chain to {:?}", scan_chain);
         self.scan_chain = Some(scan_chain);
@@ -559,10 +569,12 @@ impl<D: StLinkUsb> StLink<D> {
         tracing::debug!("STLink version: {:?}", version);
 
         if self.hw_version >= 3 {
-            let (_, current) = self.get_communication_frequencies(WireProtocol::Swd)?;
+            let (available, current) = self.get_communication_frequencies(WireProtocol::Swd)?;
+            self.swd_speeds_khz = available;
             self.swd_speed_khz = current;
 
-            let (_, current) = self.get_communication_frequencies(WireProtocol::Jtag)?;
+            let (available, current) = self.get_communication_frequencies(WireProtocol::Jtag)?;
+            self.jtag_speeds_khz = available;
             self.jtag_speed_khz = current;
         }
 
@@ -635,6 +647,22 @@ impl<D: StLinkUsb> StLink<D> {
         Ok(())
     }
 
+    /// Returns the speeds in kHz supported for the currently selected protocol.
+    fn supported_speeds(&self) -> Vec<u32> {
+        match (self.hw_version.cmp(&3), self.protocol) {
+            (Ordering::Less, WireProtocol::Swd) => SwdFrequencyToDelayCount::ALL
+                .iter()
+                .map(|setting| setting.to_khz())
+                .collect(),
+            (Ordering::Less, WireProtocol::Jtag) => JTagFrequencyToDivider::ALL
+                .iter()
+                .map(|setting| setting.to_khz())
+                .collect(),
+            (_, WireProtocol::Swd) => self.swd_speeds_khz.clone(),
+            (_, WireProtocol::Jtag) => self.jtag_speeds_khz.clone(),
+        }
+    }
+
     /// Returns the current and available communication frequencies (V3 only)
     fn get_communication_frequencies(
         &mut self,
@@ -764,7 +792,7 @@ impl<D: StLinkUsb> StLink<D> {
     pub fn start_trace_reception(&mut self, config: &SwoConfig) -> Result<(), DebugProbeError> {
         let mut buf = [0; 2];
         let bufsize = 4096u16.to_le_bytes();
-        let baud = config.baud().to_le_bytes();
+        let baud = config.probe_baud().to_le_bytes();
         let mut command = vec![commands::JTAG_COMMAND, commands::SWO_START_TRACE_RECEPTION];
         command.extend_from_slice(&bufsize);
         command.extend_from_slice(&baud);
@@ -1212,6 +1240,10 @@ impl<D: StLinkUsb> SwoAccess for StLink<D> {
         }
     }
 
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        mode == SwoMode::Uart
+    }
+
     fn disable_swo(&mut self) -> Result<(), ArmError> {
         self.stop_trace_reception()?;
         Ok(())
@@ -1522,6 +1554,10 @@ impl SwoAccess for StlinkArmDebug {
         self.probe.enable_swo(config)
     }
 
+    fn supports_swo_mode(&self, mode: SwoMode) -> bool {
+        self.probe.supports_swo_mode(mode)
+    }
+
     fn disable_swo(&mut self) -> Result<(), ArmError> {
         self.probe.disable_swo()
     }
@@ -1907,6 +1943,8 @@ mod test {
                 jtag_version: 0,
                 swd_speed_khz: 0,
                 jtag_speed_khz: 0,
+                swd_speeds_khz: vec![],
+                jtag_speeds_khz: vec![],
                 scan_chain: None,
                 swo_enabled: false,
                 opened_aps: vec![],
@@ -2041,6 +2079,34 @@ mod test {
             .expect("Selecting AP other than AP 0 should work");
     }
 
+    #[test]
+    fn v2_supported_speeds() {
+        let usb_mock = MockUsb {
+            hw_version: 2,
+            jtag_version: 30,
+            swim_version: 0,
+            target_voltage_a0: 1.0,
+            _target_voltage_a1: 2.0,
+        };
+
+        let mut probe = usb_mock.build();
+
+        probe.init().expect("Init function failed");
+
+        assert_eq!(
+            probe.supported_speeds(),
+            [
+                4600, 1800, 1200, 950, 650, 480, 400, 360, 240, 150, 125, 100
+            ]
+        );
+
+        probe.protocol = WireProtocol::Jtag;
+        assert_eq!(
+            probe.supported_speeds(),
+            [18_000, 9_000, 4_500, 2_250, 1_120, 560, 280, 140]
+        );
+    }
+
     #[test]
     fn test_is_wait_error() {
         assert!(!is_wait_error(&StlinkError::BanksNotAllowedOnDPRegister));
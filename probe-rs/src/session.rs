@@ -1,8 +1,8 @@
 use crate::{
-    Core, CoreType, Error,
+    Core, CoreStatus, CoreType, Error, HaltReason,
     architecture::{
         arm::{
-            ArmError, SwoReader,
+            ArmError, Dump, SwoReader,
             communication_interface::ArmProbeInterface,
             component::{TraceSink, get_arm_components},
             dp::DpAddress,
@@ -23,9 +23,13 @@ use crate::{
         fake_probe::FakeProbe, list::Lister,
     },
 };
-use std::ops::DerefMut;
+use std::ops::{DerefMut, Range};
 use std::{fmt, sync::Arc, time::Duration};
 
+/// The number of stack bytes captured by [`Session::dump_core`], if no memory regions are
+/// requested.
+const DEFAULT_DUMP_STACK_BYTES: usize = 1024;
+
 /// The `Session` struct represents an active debug session.
 ///
 /// ## Creating a session
@@ -65,6 +69,9 @@ pub struct SessionConfig {
     pub permissions: Permissions,
     /// Speed of the WireProtocol in kHz
     pub speed: Option<u32>,
+    /// Maximum debug clock supported by the target in kHz, used to select the probe speed
+    /// with [`Probe::set_target_max_clock`]. Ignored if `speed` is set.
+    pub target_max_clock: Option<u32>,
     /// WireProtocol to use
     pub protocol: Option<WireProtocol>,
 }
@@ -445,6 +452,8 @@ impl Session {
         // If the caller has specified speed or protocol in SessionConfig, set them
         if let Some(speed) = session_config.speed {
             probe.set_speed(speed)?;
+        } else if let Some(max_khz) = session_config.target_max_clock {
+            probe.set_target_max_clock(max_khz)?;
         }
 
         if let Some(protocol) = session_config.protocol {
@@ -492,6 +501,22 @@ impl Session {
         r
     }
 
+    /// Halt all cores as close to simultaneously as possible.
+    ///
+    /// The halt requests for all cores are issued first, and only then the cores are polled until
+    /// they are halted. This keeps the window in which some cores are already halted while others
+    /// are still running as small as possible, e.g. to get a consistent view of shared memory.
+    /// Disabled cores are skipped.
+    ///
+    /// Only ARMv6-M, ARMv7-M and ARMv8-M cores support halt requests. All other cores are halted
+    /// one after the other, each waiting up to `timeout`, once the requests have been issued.
+    ///
+    /// Returns the halt reason of every halted core.
+    pub fn halt_all_cores(&mut self, timeout: Duration) -> Result<Vec<(usize, HaltReason)>, Error> {
+        let core_indices = 0..self.cores.len();
+        halt_simultaneously(self, core_indices, timeout)
+    }
+
     fn interface_idx(&self, core: usize) -> Result<usize, Error> {
         self.cores
             .get(core)
@@ -534,6 +559,24 @@ impl Session {
         }
     }
 
+    /// Capture a [`Dump`] of the core with index `core_index`, e.g. for crash triage.
+    ///
+    /// The dump contains the core registers and the memory of each range in `regions`. If no
+    /// regions are requested, the dump contains the first KiB of the stack instead, starting at
+    /// the current stack pointer.
+    ///
+    /// The core has to be halted, see [`Core::capture_dump`] for the supported cores.
+    pub fn dump_core(&mut self, core_index: usize, regions: &[Range<u64>]) -> Result<Dump, Error> {
+        let stack_bytes = if regions.is_empty() {
+            DEFAULT_DUMP_STACK_BYTES
+        } else {
+            0
+        };
+
+        self.core(core_index)?
+            .capture_dump_with_regions(stack_bytes, regions)
+    }
+
     /// Read available trace data from the specified data sink.
     ///
     /// This method is only supported for ARM-based targets, and will
@@ -800,6 +843,22 @@ impl Session {
         crate::architecture::arm::component::remove_swv_data_trace(interface, &components, unit)
     }
 
+    /// Begin emitting exception entry, exit and return packets over SWV.
+    ///
+    /// Fails if the DWT of the target does not support trace packets.
+    pub fn enable_exception_trace(&mut self) -> Result<(), ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::enable_exception_trace(interface, &components)
+    }
+
+    /// Stop emitting exception trace packets over SWV.
+    pub fn disable_exception_trace(&mut self) -> Result<(), ArmError> {
+        let components = self.get_arm_components(DpAddress::Default)?;
+        let interface = self.get_arm_interface()?;
+        crate::architecture::arm::component::disable_exception_trace(interface, &components)
+    }
+
     /// Return the `Architecture` of the currently connected chip.
     pub fn architecture(&self) -> Architecture {
         match &self.interfaces {
@@ -848,6 +907,76 @@ impl Session {
     }
 }
 
+/// The steps to halt multiple cores at once, see [`Session::halt_all_cores`].
+trait SimultaneousHalt {
+    /// Request the core to halt, without waiting for it.
+    ///
+    /// Returns `false` if the core is disabled, and has to be skipped.
+    fn request_halt(&mut self, core_index: usize) -> Result<bool, Error>;
+
+    /// Halt the core and wait for it, for cores which don't support halt requests.
+    fn halt(&mut self, core_index: usize, timeout: Duration) -> Result<(), Error>;
+
+    /// Wait until the core is halted, and return the reason for the halt.
+    fn wait_for_halt(&mut self, core_index: usize, timeout: Duration) -> Result<HaltReason, Error>;
+}
+
+impl SimultaneousHalt for Session {
+    fn request_halt(&mut self, core_index: usize) -> Result<bool, Error> {
+        match self.core(core_index) {
+            Ok(mut core) => core.request_halt().map(|_| true),
+            Err(Error::CoreDisabled(_)) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn halt(&mut self, core_index: usize, timeout: Duration) -> Result<(), Error> {
+        self.core(core_index)?.halt(timeout).map(|_| ())
+    }
+
+    fn wait_for_halt(&mut self, core_index: usize, timeout: Duration) -> Result<HaltReason, Error> {
+        let mut core = self.core(core_index)?;
+        core.wait_for_core_halted(timeout)?;
+
+        // Reading the status also updates the cached status of the core.
+        match core.status()? {
+            CoreStatus::Halted(reason) => Ok(reason),
+            _ => Ok(HaltReason::Unknown),
+        }
+    }
+}
+
+/// Halt the cores in `core_indices`, requesting all of them to halt before waiting for any.
+///
+/// Cores which don't support halt requests are halted one by one after the requests.
+fn halt_simultaneously(
+    cores: &mut impl SimultaneousHalt,
+    core_indices: Range<usize>,
+    timeout: Duration,
+) -> Result<Vec<(usize, HaltReason)>, Error> {
+    let mut halted = Vec::new();
+    let mut sequential = Vec::new();
+    for core_index in core_indices {
+        match cores.request_halt(core_index) {
+            Ok(true) => halted.push(core_index),
+            Ok(false) => {}
+            Err(Error::NotImplemented(_)) => sequential.push(core_index),
+            Err(error) => return Err(error),
+        }
+    }
+
+    for core_index in sequential {
+        cores.halt(core_index, timeout)?;
+        halted.push(core_index);
+    }
+    halted.sort_unstable();
+
+    halted
+        .into_iter()
+        .map(|core_index| Ok((core_index, cores.wait_for_halt(core_index, timeout)?)))
+        .collect()
+}
+
 // This test ensures that [Session] is fully [Send] + [Sync].
 const _: fn() = || {
     fn assert_impl_all<T: ?Sized + Send>() {}
@@ -967,3 +1096,98 @@ impl Permissions {
 #[derive(Debug, Clone, thiserror::Error)]
 #[error("An operation could not be performed because it lacked the permission to do so: {0}")]
 pub struct MissingPermissions(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Request(usize),
+        Halt(usize, Duration),
+        Wait(usize),
+    }
+
+    /// Cores which record the order of all requests, where core 1 is disabled, and core 3 does
+    /// not support halt requests.
+    #[derive(Default)]
+    struct MockCores {
+        events: Vec<Event>,
+    }
+
+    impl SimultaneousHalt for MockCores {
+        fn request_halt(&mut self, core_index: usize) -> Result<bool, Error> {
+            self.events.push(Event::Request(core_index));
+            match core_index {
+                1 => Ok(false),
+                3 => Err(Error::NotImplemented("halt requests")),
+                _ => Ok(true),
+            }
+        }
+
+        fn halt(&mut self, core_index: usize, timeout: Duration) -> Result<(), Error> {
+            self.events.push(Event::Halt(core_index, timeout));
+            Ok(())
+        }
+
+        fn wait_for_halt(
+            &mut self,
+            core_index: usize,
+            _timeout: Duration,
+        ) -> Result<HaltReason, Error> {
+            self.events.push(Event::Wait(core_index));
+            Ok(HaltReason::Request)
+        }
+    }
+
+    #[test]
+    fn halt_requests_before_polling() {
+        let mut cores = MockCores::default();
+
+        let reasons = halt_simultaneously(&mut cores, 0..3, Duration::from_millis(100)).unwrap();
+
+        assert_eq!(
+            cores.events,
+            [
+                Event::Request(0),
+                Event::Request(1),
+                Event::Request(2),
+                Event::Wait(0),
+                Event::Wait(2),
+            ]
+        );
+        assert_eq!(
+            reasons,
+            [(0, HaltReason::Request), (2, HaltReason::Request)]
+        );
+    }
+
+    #[test]
+    fn halt_cores_without_requests_after_requesting() {
+        let mut cores = MockCores::default();
+        let timeout = Duration::from_millis(250);
+
+        let reasons = halt_simultaneously(&mut cores, 2..5, timeout).unwrap();
+
+        assert_eq!(
+            cores.events,
+            [
+                Event::Request(2),
+                Event::Request(3),
+                Event::Request(4),
+                Event::Halt(3, timeout),
+                Event::Wait(2),
+                Event::Wait(3),
+                Event::Wait(4),
+            ]
+        );
+        assert_eq!(
+            reasons,
+            [
+                (2, HaltReason::Request),
+                (3, HaltReason::Request),
+                (4, HaltReason::Request)
+            ]
+        );
+    }
+}
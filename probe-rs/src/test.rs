@@ -1,6 +1,27 @@
 //! Helpers for testing the crate
 
-use crate::MemoryInterface;
+use crate::{
+    Architecture, BreakpointCause, CoreInformation, CoreInterface, CoreRegister, CoreRegisters,
+    CoreStatus, CoreType, Error, HaltReason, InstructionSet, MemoryInterface, RegisterId,
+    RegisterValue,
+    architecture::arm::{
+        ArmError, ArmProbeInterface, DapAccess, FullyQualifiedApAddress, SwoAccess, SwoConfig,
+        SwoStatus,
+        ap::CSW,
+        communication_interface::{DapProbe, SwdSequence},
+        core::registers::cortex_m::{
+            CORTEX_M_CORE_REGISTERS, CORTEX_M_WITH_FP_CORE_REGISTERS, FP, PC, RA, SP,
+        },
+        dp::{DpAddress, DpRegisterAddress},
+        memory::ArmMemoryInterface,
+    },
+    probe::{DebugProbeError, Probe, fake_probe::FakeProbe},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 /// A mock memory implementation that can be used for testing
 ///
@@ -213,6 +234,821 @@ impl MemoryInterface for MockMemory {
     }
 }
 
+/// A fake Cortex-M core that can be used for testing
+///
+/// Registers are kept in a map and default to zero. Memory is sparse, and reading
+/// memory which has not been added before results in an error, which allows
+/// testing how callers handle faulting accesses.
+///
+/// A running core halts at the closest enabled breakpoint after its program counter, the next
+/// time its status is checked. Stepping onto a breakpoint is reported as a step, like on real
+/// Cortex-M cores.
+#[derive(Debug)]
+pub struct FakeCore {
+    registers: HashMap<RegisterId, RegisterValue>,
+    memory: BTreeMap<u64, u8>,
+    status: CoreStatus,
+    breakpoints: Vec<Option<u64>>,
+    breakpoints_enabled: bool,
+    fp_present: bool,
+    write_hook: Option<WriteHook>,
+}
+
+/// A function called with the address of every memory write to a [`FakeCore`].
+struct WriteHook(Box<dyn FnMut(&mut FakeCore, u64)>);
+
+impl std::fmt::Debug for WriteHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WriteHook")
+    }
+}
+
+impl FakeCore {
+    /// The number of hardware breakpoint units of the fake core.
+    pub const BREAKPOINT_UNITS: usize = 4;
+
+    /// Create a new halted fake core, with all registers set to zero and no memory.
+    pub fn new() -> Self {
+        Self {
+            registers: HashMap::new(),
+            memory: BTreeMap::new(),
+            status: CoreStatus::Halted(HaltReason::Request),
+            breakpoints: vec![None; Self::BREAKPOINT_UNITS],
+            breakpoints_enabled: false,
+            fp_present: false,
+            write_hook: None,
+        }
+    }
+
+    /// Set a function which is called with the address of every memory write, after the
+    /// write is done, e.g. to emulate a peripheral which reacts to register writes.
+    ///
+    /// Memory writes done by the function itself don't call it again.
+    pub fn set_write_hook(&mut self, hook: impl FnMut(&mut FakeCore, u64) + 'static) {
+        self.write_hook = Some(WriteHook(Box::new(hook)));
+    }
+
+    /// Set whether the fake core has an FPU.
+    pub fn set_fp_present(&mut self, fp_present: bool) {
+        self.fp_present = fp_present;
+    }
+
+    /// Set the value of a register.
+    pub fn set_register(&mut self, register: impl Into<RegisterId>, value: u32) {
+        self.registers
+            .insert(register.into(), RegisterValue::U32(value));
+    }
+
+    /// Add a range of bytes at the given address.
+    pub fn add_range(&mut self, address: u64, data: &[u8]) {
+        for (offset, byte) in data.iter().enumerate() {
+            self.memory.insert(address + offset as u64, *byte);
+        }
+    }
+
+    /// Add a range of words at the given address.
+    pub fn add_word_range(&mut self, address: u64, data: &[u32]) {
+        for (offset, word) in data.iter().enumerate() {
+            self.add_range(address + (offset * 4) as u64, &word.to_le_bytes());
+        }
+    }
+
+    fn pc(&self) -> u32 {
+        match self.registers.get(&PC.id()) {
+            Some(RegisterValue::U32(pc)) => *pc,
+            _ => 0,
+        }
+    }
+
+    /// Halt a running core at the next enabled breakpoint, as if it ran into it.
+    fn run_to_breakpoint(&mut self) {
+        if self.status != CoreStatus::Running || !self.breakpoints_enabled {
+            return;
+        }
+
+        let pc = self.pc() as u64;
+        let next_breakpoint = self
+            .breakpoints
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&breakpoint| breakpoint > pc)
+            .min();
+
+        if let Some(breakpoint) = next_breakpoint {
+            self.set_register(PC.id(), breakpoint as u32);
+            self.status = CoreStatus::Halted(HaltReason::Breakpoint(BreakpointCause::Hardware));
+        }
+    }
+}
+
+impl Default for FakeCore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryInterface for FakeCore {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), Error> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 8];
+            self.read_8(address + (offset * 8) as u64, &mut bytes)?;
+            *word = u64::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), Error> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 4];
+            self.read_8(address + (offset * 4) as u64, &mut bytes)?;
+            *word = u32::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), Error> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 2];
+            self.read_8(address + (offset * 2) as u64, &mut bytes)?;
+            *word = u16::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), Error> {
+        for (offset, byte) in data.iter_mut().enumerate() {
+            let address = address + offset as u64;
+            *byte = *self
+                .memory
+                .get(&address)
+                .ok_or_else(|| Error::Other(format!("No memory at address {address:#010x}")))?;
+        }
+        Ok(())
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), Error> {
+        for (offset, word) in data.iter().enumerate() {
+            self.write_8(address + (offset * 8) as u64, &word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), Error> {
+        for (offset, word) in data.iter().enumerate() {
+            self.write_8(address + (offset * 4) as u64, &word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), Error> {
+        for (offset, word) in data.iter().enumerate() {
+            self.write_8(address + (offset * 2) as u64, &word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), Error> {
+        self.add_range(address, data);
+
+        if let Some(mut hook) = self.write_hook.take() {
+            (hook.0)(self, address);
+            self.write_hook = Some(hook);
+        }
+
+        Ok(())
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl CoreInterface for FakeCore {
+    fn wait_for_core_halted(&mut self, _timeout: Duration) -> Result<(), Error> {
+        self.run_to_breakpoint();
+
+        if self.status.is_halted() {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    fn core_halted(&mut self) -> Result<bool, Error> {
+        self.run_to_breakpoint();
+
+        Ok(self.status.is_halted())
+    }
+
+    fn status(&mut self) -> Result<CoreStatus, Error> {
+        self.run_to_breakpoint();
+
+        Ok(self.status)
+    }
+
+    fn halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        if !self.status.is_halted() {
+            self.status = CoreStatus::Halted(HaltReason::Request);
+        }
+        Ok(CoreInformation {
+            pc: self.pc() as u64,
+        })
+    }
+
+    fn run(&mut self) -> Result<(), Error> {
+        self.status = CoreStatus::Running;
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), Error> {
+        self.registers.clear();
+        self.status = CoreStatus::Running;
+        Ok(())
+    }
+
+    fn reset_and_halt(&mut self, _timeout: Duration) -> Result<CoreInformation, Error> {
+        self.registers.clear();
+        self.status = CoreStatus::Halted(HaltReason::Request);
+        Ok(CoreInformation { pc: 0 })
+    }
+
+    /// Advances the program counter by one 16-bit instruction.
+    fn step(&mut self) -> Result<CoreInformation, Error> {
+        let pc = self.pc() + 2;
+        self.set_register(PC.id(), pc);
+        self.status = CoreStatus::Halted(HaltReason::Step);
+
+        Ok(CoreInformation { pc: pc as u64 })
+    }
+
+    fn read_core_reg(&mut self, address: RegisterId) -> Result<RegisterValue, Error> {
+        Ok(self.registers.get(&address).copied().unwrap_or_default())
+    }
+
+    fn write_core_reg(&mut self, address: RegisterId, value: RegisterValue) -> Result<(), Error> {
+        self.registers.insert(address, value);
+        Ok(())
+    }
+
+    fn available_breakpoint_units(&mut self) -> Result<u32, Error> {
+        Ok(Self::BREAKPOINT_UNITS as u32)
+    }
+
+    fn hw_breakpoints(&mut self) -> Result<Vec<Option<u64>>, Error> {
+        Ok(self.breakpoints.clone())
+    }
+
+    fn enable_breakpoints(&mut self, state: bool) -> Result<(), Error> {
+        self.breakpoints_enabled = state;
+        Ok(())
+    }
+
+    fn set_hw_breakpoint(&mut self, unit_index: usize, addr: u64) -> Result<(), Error> {
+        self.breakpoints[unit_index] = Some(addr);
+        Ok(())
+    }
+
+    fn clear_hw_breakpoint(&mut self, unit_index: usize) -> Result<(), Error> {
+        self.breakpoints[unit_index] = None;
+        Ok(())
+    }
+
+    fn registers(&self) -> &'static CoreRegisters {
+        if self.fp_present {
+            &CORTEX_M_WITH_FP_CORE_REGISTERS
+        } else {
+            &CORTEX_M_CORE_REGISTERS
+        }
+    }
+
+    fn program_counter(&self) -> &'static CoreRegister {
+        &PC
+    }
+
+    fn frame_pointer(&self) -> &'static CoreRegister {
+        &FP
+    }
+
+    fn stack_pointer(&self) -> &'static CoreRegister {
+        &SP
+    }
+
+    fn return_address(&self) -> &'static CoreRegister {
+        &RA
+    }
+
+    fn hw_breakpoints_enabled(&self) -> bool {
+        self.breakpoints_enabled
+    }
+
+    fn architecture(&self) -> Architecture {
+        Architecture::Arm
+    }
+
+    fn core_type(&self) -> CoreType {
+        CoreType::Armv7m
+    }
+
+    fn instruction_set(&mut self) -> Result<InstructionSet, Error> {
+        Ok(InstructionSet::Thumb2)
+    }
+
+    fn fpu_support(&mut self) -> Result<bool, Error> {
+        Ok(self.fp_present)
+    }
+
+    fn floating_point_register_count(&mut self) -> Result<usize, Error> {
+        Ok(if self.fp_present { 32 } else { 0 })
+    }
+
+    fn reset_catch_set(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn reset_catch_clear(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn debug_core_stop(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A fake memory AP, for testing code which accesses the target through an
+/// [`ArmMemoryInterface`].
+///
+/// Like for [`FakeCore`], memory is sparse, and reading memory which has not been added or written
+/// before results in an error. The debug port and the probe behind the memory AP are not
+/// available. Core status notifications are recorded.
+///
+/// Clones share the memory contents, so a test can keep a clone to check what was written
+/// through another one.
+#[derive(Debug, Clone)]
+pub struct FakeArmMemory {
+    memory: Arc<Mutex<BTreeMap<u64, u8>>>,
+    base_address: u64,
+    supports_8bit_transfers: bool,
+    core_status_notifications: Vec<CoreStatus>,
+}
+
+impl FakeArmMemory {
+    /// Create a new fake memory AP without any memory, and a base address of zero.
+    pub fn new() -> Self {
+        Self {
+            memory: Arc::default(),
+            base_address: 0,
+            supports_8bit_transfers: true,
+            core_status_notifications: Vec::new(),
+        }
+    }
+
+    /// Add a range of bytes at the given address.
+    pub fn add_range(&mut self, address: u64, data: &[u8]) {
+        let mut memory = self.memory.lock().unwrap();
+        for (offset, byte) in data.iter().enumerate() {
+            memory.insert(address + offset as u64, *byte);
+        }
+    }
+
+    /// Add a range of words at the given address.
+    pub fn add_word_range(&mut self, address: u64, data: &[u32]) {
+        for (offset, word) in data.iter().enumerate() {
+            self.add_range(address + (offset * 4) as u64, &word.to_le_bytes());
+        }
+    }
+
+    /// Set the address of the debug component, usually a ROM table, which is described by the
+    /// BASE register of the memory AP.
+    pub fn set_base_address(&mut self, base_address: u64) {
+        self.base_address = base_address;
+    }
+
+    /// Set whether the memory AP supports 8-bit and 16-bit transfers. If not, these accesses
+    /// fail with [`ArmError::UnsupportedTransferWidth`].
+    pub fn set_supports_8bit_transfers(&mut self, supported: bool) {
+        self.supports_8bit_transfers = supported;
+    }
+
+    /// Add the identification registers of a CoreSight component designed by ARM, with the
+    /// component `class` and `part` number, for the component at `base`.
+    pub fn add_coresight_component(&mut self, base: u64, class: u32, part: u32) {
+        let registers = [
+            // DEVARCH, DEVTYPE
+            (0xFBC, 0),
+            (0xFCC, 0),
+            // PIDR4 to PIDR7
+            (0xFD0, 0x04),
+            (0xFD4, 0),
+            (0xFD8, 0),
+            (0xFDC, 0),
+            // PIDR0 to PIDR3, designed by ARM
+            (0xFE0, part & 0xff),
+            (0xFE4, 0xb0 | (part >> 8)),
+            (0xFE8, 0x0b),
+            (0xFEC, 0),
+            // CIDR0 to CIDR3
+            (0xFF0, 0x0d),
+            (0xFF4, class << 4),
+            (0xFF8, 0x05),
+            (0xFFC, 0xb1),
+        ];
+
+        for (offset, value) in registers {
+            self.add_word_range(base + offset, &[value]);
+        }
+    }
+
+    /// Add the entries of a ROM table at `base`, followed by the end marker. Each entry is the
+    /// offset of a component from `base`, in 4 KiB pages.
+    pub fn add_rom_table_entries(&mut self, base: u64, entries: &[i32]) {
+        for (index, offset) in entries.iter().chain(&[0]).enumerate() {
+            let entry = match offset {
+                0 => 0,
+                offset => ((*offset as u32) << 12) | 0b11,
+            };
+            self.add_word_range(base + index as u64 * 4, &[entry]);
+        }
+    }
+
+    /// The core status notifications received so far, oldest first.
+    pub fn core_status_notifications(&self) -> &[CoreStatus] {
+        &self.core_status_notifications
+    }
+
+    fn check_transfer_width(&self, width: usize) -> Result<(), ArmError> {
+        if width < 32 && !self.supports_8bit_transfers {
+            return Err(ArmError::UnsupportedTransferWidth(width));
+        }
+
+        Ok(())
+    }
+
+    fn read_bytes(&self, address: u64, data: &mut [u8]) -> Result<(), ArmError> {
+        let memory = self.memory.lock().unwrap();
+        for (offset, byte) in data.iter_mut().enumerate() {
+            let address = address + offset as u64;
+            *byte = *memory
+                .get(&address)
+                .ok_or_else(|| ArmError::Other(format!("No memory at address {address:#010x}")))?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for FakeArmMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryInterface<ArmError> for FakeArmMemory {
+    fn supports_native_64bit_access(&mut self) -> bool {
+        false
+    }
+
+    fn read_64(&mut self, address: u64, data: &mut [u64]) -> Result<(), ArmError> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 8];
+            self.read_bytes(address + (offset * 8) as u64, &mut bytes)?;
+            *word = u64::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_32(&mut self, address: u64, data: &mut [u32]) -> Result<(), ArmError> {
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 4];
+            self.read_bytes(address + (offset * 4) as u64, &mut bytes)?;
+            *word = u32::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_16(&mut self, address: u64, data: &mut [u16]) -> Result<(), ArmError> {
+        self.check_transfer_width(16)?;
+
+        for (offset, word) in data.iter_mut().enumerate() {
+            let mut bytes = [0u8; 2];
+            self.read_bytes(address + (offset * 2) as u64, &mut bytes)?;
+            *word = u16::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+
+    fn read_8(&mut self, address: u64, data: &mut [u8]) -> Result<(), ArmError> {
+        self.check_transfer_width(8)?;
+
+        self.read_bytes(address, data)
+    }
+
+    fn write_64(&mut self, address: u64, data: &[u64]) -> Result<(), ArmError> {
+        for (offset, word) in data.iter().enumerate() {
+            self.add_range(address + (offset * 8) as u64, &word.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn write_32(&mut self, address: u64, data: &[u32]) -> Result<(), ArmError> {
+        self.add_word_range(address, data);
+        Ok(())
+    }
+
+    fn write_16(&mut self, address: u64, data: &[u16]) -> Result<(), ArmError> {
+        self.check_transfer_width(16)?;
+
+        for (offset, word) in data.iter().enumerate() {
+            self.add_range(address + (offset * 2) as u64, &word.to_le_bytes());
+        }
+        Ok(())
+    }
+
+    fn write_8(&mut self, address: u64, data: &[u8]) -> Result<(), ArmError> {
+        self.check_transfer_width(8)?;
+
+        self.add_range(address, data);
+        Ok(())
+    }
+
+    fn supports_8bit_transfers(&self) -> Result<bool, ArmError> {
+        Ok(self.supports_8bit_transfers)
+    }
+
+    fn flush(&mut self) -> Result<(), ArmError> {
+        Ok(())
+    }
+}
+
+impl ArmMemoryInterface for FakeArmMemory {
+    fn fully_qualified_address(&self) -> FullyQualifiedApAddress {
+        FullyQualifiedApAddress::v1_with_default_dp(0)
+    }
+
+    fn base_address(&mut self) -> Result<u64, ArmError> {
+        Ok(self.base_address)
+    }
+
+    fn get_swd_sequence(&mut self) -> Result<&mut dyn SwdSequence, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "get_swd_sequence",
+        })
+    }
+
+    fn get_arm_probe_interface(&mut self) -> Result<&mut dyn ArmProbeInterface, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "get_arm_probe_interface",
+        })
+    }
+
+    fn get_dap_access(&mut self) -> Result<&mut dyn DapAccess, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "get_dap_access",
+        })
+    }
+
+    fn generic_status(&mut self) -> Result<CSW, ArmError> {
+        Err(ArmError::NotImplemented("generic_status"))
+    }
+
+    fn update_core_status(&mut self, state: CoreStatus) {
+        self.core_status_notifications.push(state);
+    }
+}
+
+/// A fake ARM debug interface, for testing code which works with an [`ArmProbeInterface`].
+///
+/// Debug port and access port registers are plain values, which are read back as written.
+/// Reading a register which was not set before results in an error. Access ports can have a
+/// [`FakeArmMemory`] behind them, of which each [`ArmProbeInterface::memory_interface`] returns
+/// a clone, so memory writes are kept.
+///
+/// SWO reads return the results added with [`FakeArmProbe::add_swo_read`] in order, and no data
+/// once all of them were returned.
+///
+/// All debug ports share the same registers. Accessing a debug port selects it, and accesses to
+/// a debug port marked with [`FakeArmProbe::set_dp_unreachable`] fail.
+#[derive(Debug, Default)]
+pub struct FakeArmProbe {
+    dp_registers: Vec<(DpRegisterAddress, u32)>,
+    current_dp: DpAddress,
+    unreachable_dps: BTreeSet<DpAddress>,
+    access_ports: BTreeMap<FullyQualifiedApAddress, FakeAccessPort>,
+    ap_register_delay: Duration,
+    swo_reads: VecDeque<Result<SwoStatus, ArmError>>,
+}
+
+#[derive(Debug, Default)]
+struct FakeAccessPort {
+    registers: HashMap<u64, u32>,
+    memory: Option<FakeArmMemory>,
+}
+
+impl FakeArmProbe {
+    /// Create a new fake interface without any access ports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of a debug port register.
+    pub fn set_dp_register(&mut self, address: DpRegisterAddress, value: u32) {
+        match self
+            .dp_registers
+            .iter_mut()
+            .find(|(register, _)| *register == address)
+        {
+            Some((_, register_value)) => *register_value = value,
+            None => self.dp_registers.push((address, value)),
+        }
+    }
+
+    /// Make all accesses to the debug port `dp` fail, like for a debug port which does not
+    /// respond.
+    pub fn set_dp_unreachable(&mut self, dp: DpAddress) {
+        self.unreachable_dps.insert(dp);
+    }
+
+    /// Add an access port without any registers or memory.
+    pub fn add_access_port(&mut self, address: FullyQualifiedApAddress) {
+        self.access_ports.entry(address).or_default();
+    }
+
+    /// Set the value of a register of an access port, adding the access port if necessary.
+    pub fn set_ap_register(&mut self, address: FullyQualifiedApAddress, register: u64, value: u32) {
+        self.access_ports
+            .entry(address)
+            .or_default()
+            .registers
+            .insert(register, value);
+    }
+
+    /// Put `memory` behind an access port, adding the access port if necessary.
+    pub fn set_ap_memory(&mut self, address: FullyQualifiedApAddress, memory: FakeArmMemory) {
+        self.access_ports.entry(address).or_default().memory = Some(memory);
+    }
+
+    /// Make every access port register read take `delay`, like on a slow probe.
+    pub fn set_ap_register_delay(&mut self, delay: Duration) {
+        self.ap_register_delay = delay;
+    }
+
+    /// Add the result of a SWO read, after the ones added before.
+    pub fn add_swo_read(&mut self, read: Result<SwoStatus, ArmError>) {
+        self.swo_reads.push_back(read);
+    }
+
+    fn select_debug_port(&mut self, dp: DpAddress) -> Result<(), ArmError> {
+        self.current_dp = dp;
+
+        if self.unreachable_dps.contains(&dp) {
+            return Err(ArmError::Other(format!("{dp:?} does not respond")));
+        }
+
+        Ok(())
+    }
+}
+
+impl SwdSequence for FakeArmProbe {
+    fn swj_sequence(&mut self, _bit_len: u8, _bits: u64) -> Result<(), DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "swj_sequence",
+        })
+    }
+
+    fn swj_pins(
+        &mut self,
+        _pin_out: u32,
+        _pin_select: u32,
+        _pin_wait: u32,
+    ) -> Result<u32, DebugProbeError> {
+        Err(DebugProbeError::NotImplemented {
+            function_name: "swj_pins",
+        })
+    }
+}
+
+impl SwoAccess for FakeArmProbe {
+    fn enable_swo(&mut self, _config: &SwoConfig) -> Result<(), ArmError> {
+        Ok(())
+    }
+
+    fn disable_swo(&mut self) -> Result<(), ArmError> {
+        Ok(())
+    }
+
+    fn read_swo_timeout(&mut self, timeout: Duration) -> Result<Vec<u8>, ArmError> {
+        self.read_swo_status(timeout).map(|status| status.data)
+    }
+
+    fn read_swo_status(&mut self, _timeout: Duration) -> Result<SwoStatus, ArmError> {
+        self.swo_reads
+            .pop_front()
+            .unwrap_or_else(|| Ok(SwoStatus::default()))
+    }
+}
+
+impl DapAccess for FakeArmProbe {
+    fn read_raw_dp_register(
+        &mut self,
+        dp: DpAddress,
+        address: DpRegisterAddress,
+    ) -> Result<u32, ArmError> {
+        self.select_debug_port(dp)?;
+        self.dp_registers
+            .iter()
+            .find(|(register, _)| *register == address)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| ArmError::Other(format!("No DP register {address:?}")))
+    }
+
+    fn write_raw_dp_register(
+        &mut self,
+        dp: DpAddress,
+        address: DpRegisterAddress,
+        value: u32,
+    ) -> Result<(), ArmError> {
+        self.select_debug_port(dp)?;
+        self.set_dp_register(address, value);
+        Ok(())
+    }
+
+    fn read_raw_ap_register(
+        &mut self,
+        ap: &FullyQualifiedApAddress,
+        address: u64,
+    ) -> Result<u32, ArmError> {
+        std::thread::sleep(self.ap_register_delay);
+        self.access_ports
+            .get(ap)
+            .and_then(|access_port| access_port.registers.get(&address))
+            .copied()
+            .ok_or_else(|| ArmError::Other(format!("No register {address:#x} on {ap:?}")))
+    }
+
+    fn write_raw_ap_register(
+        &mut self,
+        ap: &FullyQualifiedApAddress,
+        address: u64,
+        value: u32,
+    ) -> Result<(), ArmError> {
+        self.set_ap_register(ap.clone(), address, value);
+        Ok(())
+    }
+
+    fn try_dap_probe(&self) -> Option<&dyn DapProbe> {
+        None
+    }
+}
+
+impl ArmProbeInterface for FakeArmProbe {
+    fn reinitialize(&mut self) -> Result<(), ArmError> {
+        Ok(())
+    }
+
+    fn access_ports(
+        &mut self,
+        dp: DpAddress,
+    ) -> Result<BTreeSet<FullyQualifiedApAddress>, ArmError> {
+        self.select_debug_port(dp)?;
+
+        Ok(self
+            .access_ports
+            .keys()
+            .filter(|address| address.dp() == dp)
+            .cloned()
+            .collect())
+    }
+
+    fn close(self: Box<Self>) -> Probe {
+        FakeProbe::new().into_probe()
+    }
+
+    fn current_debug_port(&self) -> DpAddress {
+        self.current_dp
+    }
+
+    fn memory_interface(
+        &mut self,
+        access_port: &FullyQualifiedApAddress,
+    ) -> Result<Box<dyn ArmMemoryInterface + '_>, ArmError> {
+        match self
+            .access_ports
+            .get(access_port)
+            .and_then(|access_port| access_port.memory.as_ref())
+        {
+            Some(memory) => Ok(Box::new(memory.clone())),
+            None => Err(ArmError::WrongApType),
+        }
+    }
+}
+
 #[test]
 fn mock_memory_read() {
     let mut mock_memory = MockMemory::new();
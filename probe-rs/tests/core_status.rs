@@ -0,0 +1,27 @@
+//! `CoreStatus` and `HaltReason` are `#[non_exhaustive]`, these tests make sure that
+//! matches outside of probe-rs work as intended.
+use probe_rs::{BreakpointCause, CoreStatus, HaltReason};
+
+fn describe(status: CoreStatus) -> &'static str {
+    match status {
+        CoreStatus::Running => "running",
+        CoreStatus::Halted(HaltReason::Breakpoint(_)) => "breakpoint",
+        CoreStatus::Halted(HaltReason::Step) => "step",
+        CoreStatus::Halted(_) => "halted",
+        _ => "other",
+    }
+}
+
+#[test]
+fn downstream_match_with_wildcard() {
+    assert_eq!(describe(CoreStatus::Running), "running");
+    assert_eq!(
+        describe(CoreStatus::Halted(HaltReason::Breakpoint(
+            BreakpointCause::Hardware
+        ))),
+        "breakpoint"
+    );
+    assert_eq!(describe(CoreStatus::Halted(HaltReason::Step)), "step");
+    assert_eq!(describe(CoreStatus::Halted(HaltReason::Request)), "halted");
+    assert_eq!(describe(CoreStatus::LockedUp), "other");
+}
@@ -95,6 +95,7 @@ pub fn cmd_test(
         permissions,
         speed,
         protocol,
+        target_max_clock: None,
     };
 
     // We need to get the chip name so that special startup procedure can be used. (matched on name)